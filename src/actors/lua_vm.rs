@@ -21,7 +21,10 @@ pub mod messages;
 
 use crate::{
     actors::{queue::Queue, scanmgr::ScanMgr, user_engine::UserEngine},
-    userscript_api::{about_api::AboutApi, fs_api::FsApi, help_system::HelpSystem},
+    userscript_api::{
+        about_api::AboutApi, fs_api::FsApi, help_system::HelpSystem, net_api::NetApi,
+        proc_api::ProcApi,
+    },
 };
 use kameo::{actor::ActorRef, error::BoxError, mailbox::unbounded::UnboundedMailbox, Actor};
 use messages::RegisterUserApi;
@@ -74,7 +77,7 @@ impl Actor for LuaVM {
         // Spawn other actors
         let queue: ActorRef<Queue> = Queue::spawn_with_size(lua_vm.downgrade(), 16384);
         let user_engine: ActorRef<UserEngine> =
-            UserEngine::spawn_with_capacity(lua_vm.downgrade(), 128);
+            UserEngine::spawn_with_capacity(lua_vm.downgrade(), self.vm.clone(), 128);
         let scanmgr: ActorRef<ScanMgr> = ScanMgr::spawn(
             lua_vm.downgrade(),
             queue.downgrade(),
@@ -89,6 +92,10 @@ impl Actor for LuaVM {
             .tell(RegisterUserApi::with(AboutApi::default()))
             .await?;
         lua_vm.tell(RegisterUserApi::with(FsApi)).await?;
+        lua_vm.tell(RegisterUserApi::with(ProcApi)).await?;
+        lua_vm
+            .tell(RegisterUserApi::with(NetApi::new(queue.downgrade())))
+            .await?;
 
         // Link all actors to self
         lua_vm.link(&queue).await;
@@ -122,13 +129,30 @@ impl Actor for LuaVM {
     }
 }
 
+/// # Settings of a [`LuaVM`].
+///
+/// The settings are kept in the Lua state's app data, so that services
+/// sharing the state, such as [`UserEngine`], can start more virtual
+/// machines alike.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VmSettings {
+    /// Whether unsafe libraries, such as `debug`, are loaded.
+    pub unsafe_mode: bool,
+
+    /// The memory limit of the virtual machine in bytes, or zero if
+    /// there is no limit.
+    pub memory_limit: usize,
+}
+
 impl LuaVM {
     /// Spawn a new Lua virtual machine in default execution mode.
     #[must_use]
     pub fn spawn(args: Option<&[String]>) -> ActorRef<Self> {
         // Create the VM
+        let vm: Lua = Lua::new();
+        vm.set_app_data(VmSettings::default());
         let mut lua_vm: Self = Self {
-            vm: Lua::new(),
+            vm,
             queue: None,
             user_engine: None,
             scanmgr: None,
@@ -162,8 +186,13 @@ impl LuaVM {
     #[must_use]
     pub unsafe fn spawn_unsafe(args: Option<&[String]>) -> ActorRef<Self> {
         // Create the VM
+        let vm: Lua = Lua::unsafe_new();
+        vm.set_app_data(VmSettings {
+            unsafe_mode: true,
+            memory_limit: 0,
+        });
         let mut lua_vm: Self = Self {
-            vm: Lua::unsafe_new(),
+            vm,
             queue: None,
             user_engine: None,
             scanmgr: None,
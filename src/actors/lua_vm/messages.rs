@@ -11,7 +11,7 @@
 
 use crate::{
     actors::{
-        lua_vm::{error::LuaVmResult, LuaVM},
+        lua_vm::{error::LuaVmResult, LuaVM, VmSettings},
         queue::Queue,
         scanmgr::ScanMgr,
         user_engine::UserEngine,
@@ -227,6 +227,112 @@ impl Message<SendWarning> for LuaVM {
     }
 }
 
+/// # Limit the memory used by the virtual machine.
+///
+/// Requests for [`LuaVM`] to cap the memory the Lua state may allocate,
+/// in bytes. Allocations past the limit fail with a Lua memory error,
+/// which fails the userscript or scan engine responsible instead of
+/// exhausting the host's memory. A limit of zero removes the limit.
+/// Engine pool workers started afterwards are held to the same limit.
+///
+/// ## Reply
+///
+/// Expect a reply of type [`LuaVmResult<()>`](LuaVmResult)
+///
+/// ## Example
+///
+/// ```
+/// # use sscan::actors::lua_vm::{LuaVM, messages::SetMemoryLimit};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let vm = LuaVM::spawn(None);
+/// vm.ask(SetMemoryLimit(512 * 1024 * 1024)).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct SetMemoryLimit(pub usize);
+
+impl Message<SetMemoryLimit> for LuaVM {
+    type Reply = LuaVmResult<()>;
+
+    async fn handle(
+        &mut self,
+        msg: SetMemoryLimit,
+        _: Context<'_, Self, Self::Reply>,
+    ) -> Self::Reply {
+        self.vm.set_memory_limit(msg.0)?;
+        if let Some(mut settings) = self.vm.app_data_mut::<VmSettings>() {
+            settings.memory_limit = msg.0;
+        }
+        Ok(())
+    }
+}
+
+/// # Get the virtual machine's [`UserEngine`] service.
+///
+/// Requests a reference to the userscript scan engine service belonging
+/// to this [`LuaVM`], so that engines it has registered can be invoked
+/// directly.
+///
+/// ## Reply
+///
+/// Expect a reply of type [`Option<ActorRef<UserEngine>>`], which is
+/// [`None`] if the virtual machine has not finished starting up.
+///
+/// ## Example
+///
+/// ```
+/// # use sscan::actors::lua_vm::{LuaVM, messages::{GetUserEngine, WaitStartup}};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let vm = LuaVM::spawn(None);
+/// vm.ask(WaitStartup).await?;
+/// let user_engine = vm.ask(GetUserEngine).await?.expect("vm should be started");
+/// # Ok(())
+/// # }
+/// ```
+pub struct GetUserEngine;
+
+impl Message<GetUserEngine> for LuaVM {
+    type Reply = Option<ActorRef<UserEngine>>;
+
+    async fn handle(&mut self, _: GetUserEngine, _: Context<'_, Self, Self::Reply>) -> Self::Reply {
+        self.user_engine.clone()
+    }
+}
+
+/// # Get the virtual machine's [`ScanMgr`] service.
+///
+/// Requests a reference to the scan manager service belonging to this
+/// [`LuaVM`], so that scans can be configured from outside of Lua.
+///
+/// ## Reply
+///
+/// Expect a reply of type [`Option<ActorRef<ScanMgr>>`], which is
+/// [`None`] if the virtual machine has not finished starting up.
+///
+/// ## Example
+///
+/// ```
+/// # use sscan::actors::lua_vm::{LuaVM, messages::{GetScanMgr, WaitStartup}};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let vm = LuaVM::spawn(None);
+/// vm.ask(WaitStartup).await?;
+/// let scanmgr = vm.ask(GetScanMgr).await?.expect("vm should be started");
+/// # Ok(())
+/// # }
+/// ```
+pub struct GetScanMgr;
+
+impl Message<GetScanMgr> for LuaVM {
+    type Reply = Option<ActorRef<ScanMgr>>;
+
+    async fn handle(&mut self, _: GetScanMgr, _: Context<'_, Self, Self::Reply>) -> Self::Reply {
+        self.scanmgr.clone()
+    }
+}
+
 /// # Waits until all actors have started up.
 ///
 /// This should be called after [`LuaVM::spawn(None)`] to ensure all actors
@@ -304,24 +410,24 @@ mod tests {
         let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
 
         // Create a chunk and execute it.
-        let exec_request: ExecChunk = r#"
+        let exec_request: ExecChunk = r"
             assert(5 == 5)
-        "#
+        "
         .into();
         vm.ask(exec_request).await.unwrap();
     }
 
-    /// This contains a bad Lua assertion, and should panic on unwrap().
+    /// This contains a bad Lua assertion, and should panic on `unwrap()`.
     #[tokio::test]
-    #[should_panic]
+    #[should_panic(expected = "assertion failed")]
     async fn should_fail_exec() {
         // Create a LuaVM actor
         let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
 
         // Create a chunk and execute it.
-        let exec_request: ExecChunk = r#"
+        let exec_request: ExecChunk = r"
             assert(5 == 4)
-        "#
+        "
         .into();
         vm.ask(exec_request).await.unwrap();
     }
@@ -333,9 +439,9 @@ mod tests {
         let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
 
         // Create an expression and execute it.
-        let expr_request: EvalChunk = r#"
+        let expr_request: EvalChunk = r"
             5 + 6
-        "#
+        "
         .into();
         let result: mlua::Value = vm.ask(expr_request).await.unwrap();
         assert_eq!(result, mlua::Value::Integer(11));
@@ -343,16 +449,16 @@ mod tests {
 
     /// This contains a bad expression, so should panic.
     #[tokio::test]
-    #[should_panic]
+    #[should_panic(expected = "nonexistent_table")]
     async fn should_error_on_invalid_expr() {
         // Create a LuaVM actor
         let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
 
         // Create an expression and execute it.
         // The table and key don't exist, so this should error.
-        let expr_request: EvalChunk = r#"
+        let expr_request: EvalChunk = r"
             nonexistent_table.nonexistent_key
-        "#
+        "
         .into();
         vm.ask(expr_request).await.unwrap();
     }
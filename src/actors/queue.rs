@@ -17,6 +17,7 @@
 
 pub mod data_item;
 pub mod error;
+pub mod memory_region;
 pub mod messages;
 
 use super::lua_vm::{messages::RegisterUserApi, LuaVM};
@@ -2,12 +2,23 @@
 //!
 //! This module defines the [`DataItem`] trait, which is any type the
 //! [`Queue`] can accept, as well as a few impls of data items, such as
-//! the [`RawDatum`] and [`FileDatum`] types.
+//! the [`RawDatum`], [`FileDatum`], [`StdinDatum`], and
+//! [`ProcessMemoryDatum`] types.
 //!
 //! [`Queue`]: super::Queue
 
-use super::error::QueueResult;
-use std::path::PathBuf;
+use super::{error::QueueResult, memory_region::MemoryRegion};
+use serde_json::Value;
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// Extra key-value information describing a [`DataItem`].
+///
+/// Metadata is carried alongside a data item's content through to its
+/// scan results, and is serialized along with them.
+pub type Metadata = serde_json::Map<String, Value>;
 
 /// An item that can be enqueued in the [`Queue`].
 ///
@@ -52,7 +63,7 @@ use std::path::PathBuf;
 /// queue.ask(Enqueue::item(my_dummy_data)).await.unwrap();
 ///
 /// // Let's dequeue the item we just enqueued and validate it.
-/// let (name, path, content) = queue.ask(Dequeue).await.unwrap();
+/// let (name, path, content, _) = queue.ask(Dequeue).await.unwrap();
 /// assert_eq!(name, "my_dummy_data");
 /// assert_eq!(path, None);
 /// assert_eq!(content, b"some dummy content".to_vec());
@@ -74,6 +85,15 @@ where
     /// loading of files.
     fn path(&self) -> Option<PathBuf>;
 
+    /// Extra information describing the data item, if any.
+    ///
+    /// The default implementation returns empty [`Metadata`]. Override
+    /// this to attach context that scan results should carry, such as
+    /// the process a memory region was read from.
+    fn metadata(&self) -> Metadata {
+        Metadata::new()
+    }
+
     /// Consumes the [`DataItem`], returning its content.
     ///
     /// This method consumes a [`Box<dyn DataItem>`], returning its
@@ -101,11 +121,22 @@ pub struct RawDatum {
 
     /// The raw bytes comprising the data item.
     content: Vec<u8>,
+
+    /// Extra information to carry through to scan results.
+    metadata: Metadata,
 }
 
 impl RawDatum {
     /// Create a new, boxed [`RawDatum`].
     pub fn new<D>(name: &str, content: D) -> Box<Self>
+    where
+        D: Into<Vec<u8>>,
+    {
+        Self::with_metadata(name, content, Metadata::new())
+    }
+
+    /// Create a new, boxed [`RawDatum`] carrying [`Metadata`].
+    pub fn with_metadata<D>(name: &str, content: D, metadata: Metadata) -> Box<Self>
     where
         D: Into<Vec<u8>>,
     {
@@ -114,6 +145,7 @@ impl RawDatum {
         Box::new(Self {
             dname: name,
             content,
+            metadata,
         })
     }
 }
@@ -127,6 +159,10 @@ impl DataItem for RawDatum {
         None
     }
 
+    fn metadata(&self) -> Metadata {
+        self.metadata.clone()
+    }
+
     fn realize(self: Box<Self>) -> QueueResult<(String, Option<PathBuf>, Vec<u8>)> {
         Ok((self.dname, None, self.content))
     }
@@ -147,9 +183,19 @@ impl DataItem for RawDatum {
 /// If you need to eagerly load file contents into memory, consider
 /// implementing trait [`DataItem`] on a custom file-based data item,
 /// and then enqueueing that custom item instead.
+///
+/// A file may also be read through another root directory, such as a
+/// process's `/proc/<pid>/root`, with [`FileDatum::in_root()`]. Its path
+/// is then reported as seen from that root.
 pub struct FileDatum {
     /// Reference path to the file to be loaded.
     path: PathBuf,
+
+    /// Root directory to read the file through, if not the host's.
+    root: Option<PathBuf>,
+
+    /// Extra information to carry through to scan results.
+    metadata: Metadata,
 }
 
 impl FileDatum {
@@ -160,9 +206,45 @@ impl FileDatum {
     pub fn new<P>(path: P) -> Box<Self>
     where
         P: Into<PathBuf>,
+    {
+        Self::with_metadata(path, Metadata::new())
+    }
+
+    /// Create a new, boxed [`FileDatum`] data item carrying [`Metadata`].
+    ///
+    /// Like [`FileDatum::new()`], this does not immediately load the
+    /// file from disk.
+    pub fn with_metadata<P>(path: P, metadata: Metadata) -> Box<Self>
+    where
+        P: Into<PathBuf>,
+    {
+        let path: PathBuf = path.into();
+        Box::new(Self {
+            path,
+            root: None,
+            metadata,
+        })
+    }
+
+    /// Create a new, boxed [`FileDatum`] data item for the file at
+    /// `path` as seen from the directory `root`, carrying [`Metadata`].
+    ///
+    /// The file is read through `root`, but its path is reported as
+    /// `path`. Symlinks are not resolved against the host's root, so
+    /// this reads the right file through a `/proc/<pid>/root` of a
+    /// process in another mount namespace, such as a container.
+    pub fn in_root<P, R>(path: P, root: R, metadata: Metadata) -> Box<Self>
+    where
+        P: Into<PathBuf>,
+        R: Into<PathBuf>,
     {
         let path: PathBuf = path.into();
-        Box::new(Self { path })
+        let root: Option<PathBuf> = Some(root.into());
+        Box::new(Self {
+            path,
+            root,
+            metadata,
+        })
     }
 }
 
@@ -179,10 +261,132 @@ impl DataItem for FileDatum {
         Some(self.path.clone())
     }
 
+    fn metadata(&self) -> Metadata {
+        self.metadata.clone()
+    }
+
     fn realize(self: Box<Self>) -> QueueResult<(String, Option<PathBuf>, Vec<u8>)> {
         let name: String = self.name();
+        if let Some(root) = &self.root {
+            let relative: &Path = self.path.strip_prefix("/").unwrap_or(&self.path);
+            let contents: Vec<u8> = std::fs::read(root.join(relative))?;
+            return Ok((name, Some(self.path), contents));
+        }
         let path: PathBuf = self.path.canonicalize()?;
         let contents: Vec<u8> = std::fs::read(&path)?;
         Ok((name, Some(path), contents))
     }
 }
+
+/// # Standard Input Data Item
+///
+/// Use this type to scan data piped into sscan through standard input,
+/// for example `cat blob | sscan run rules.lua -`.
+///
+/// ## Behavior
+///
+/// Like [`FileDatum`], this type is lazy: standard input is only read
+/// (until EOF) once [`DataItem::realize()`] is called. Standard input
+/// can only be consumed once, so any further [`StdinDatum`] realized
+/// after the first will have empty content.
+pub struct StdinDatum {
+    /// Human-friendly name of the data item.
+    dname: String,
+}
+
+impl StdinDatum {
+    /// Create a new, boxed [`StdinDatum`] data item.
+    ///
+    /// This does not immediately read from standard input. See section
+    /// `Behavior` at the top of this page to learn more.
+    #[must_use]
+    pub fn new(name: &str) -> Box<Self> {
+        let name: String = name.to_string();
+        Box::new(Self { dname: name })
+    }
+}
+
+impl DataItem for StdinDatum {
+    fn name(&self) -> String {
+        self.dname.clone()
+    }
+
+    fn path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn realize(self: Box<Self>) -> QueueResult<(String, Option<PathBuf>, Vec<u8>)> {
+        let mut content: Vec<u8> = Vec::with_capacity(8192);
+        std::io::stdin().lock().read_to_end(&mut content)?;
+        Ok((self.dname, None, content))
+    }
+}
+
+/// # Process Memory Data Item
+///
+/// Use this type to scan one mapped region of a live process's memory.
+/// Regions are described by a [`MemoryRegion`], as parsed from
+/// `/proc/<pid>/maps`, and are read through `/proc/<pid>/mem`.
+///
+/// ## Behavior
+///
+/// This type is lazy: on creation it stores just the process ID and
+/// region bounds, and only once [`DataItem::realize()`] is called does
+/// it read the region from the process. If the region has been unmapped
+/// or is unreadable by then, or is larger than [`MAX_REGION_SIZE`], only
+/// this data item fails to realize.
+///
+/// [`MAX_REGION_SIZE`]: super::memory_region::MAX_REGION_SIZE
+///
+/// The item carries [`Metadata`] with the keys `pid`, `process`,
+/// `base_address`, `size`, `perms`, and `mapping` (if file-backed).
+pub struct ProcessMemoryDatum {
+    /// The process ID to read memory from.
+    pid: u32,
+
+    /// The process name, for human-friendly naming.
+    pname: String,
+
+    /// The memory region to read.
+    region: MemoryRegion,
+}
+
+impl ProcessMemoryDatum {
+    /// Create a new, boxed [`ProcessMemoryDatum`] data item.
+    ///
+    /// This does not immediately read process memory. See section
+    /// `Behavior` at the top of this page to learn more.
+    #[must_use]
+    pub fn new(pid: u32, process_name: &str, region: MemoryRegion) -> Box<Self> {
+        let pname: String = process_name.to_string();
+        Box::new(Self { pid, pname, region })
+    }
+}
+
+impl DataItem for ProcessMemoryDatum {
+    fn name(&self) -> String {
+        format!("{}[{}]@{:#x}", self.pname, self.pid, self.region.start)
+    }
+
+    fn path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn metadata(&self) -> Metadata {
+        let mut metadata: Metadata = Metadata::new();
+        metadata.insert("pid".into(), self.pid.into());
+        metadata.insert("process".into(), self.pname.clone().into());
+        metadata.insert("base_address".into(), self.region.start.into());
+        metadata.insert("size".into(), self.region.size().into());
+        metadata.insert("perms".into(), self.region.perms.clone().into());
+        if let Some(mapping) = &self.region.pathname {
+            metadata.insert("mapping".into(), mapping.clone().into());
+        }
+        metadata
+    }
+
+    fn realize(self: Box<Self>) -> QueueResult<(String, Option<PathBuf>, Vec<u8>)> {
+        let content: Vec<u8> = self.region.read(self.pid)?;
+        Ok((self.name(), None, content))
+    }
+}
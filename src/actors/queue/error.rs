@@ -46,6 +46,16 @@ pub enum Error {
     /// The Lua userscript environment is not running.
     #[error("the Lua userscript environment does not appear to be running")]
     NoLuaVm,
+
+    /// An enqueue option was given an unsupported value.
+    #[error("invalid value `{value}` for option `{option}`")]
+    InvalidOption {
+        /// Name of the option.
+        option: String,
+
+        /// The unsupported value that was given.
+        value: String,
+    },
 }
 
 impl Error {
@@ -54,6 +64,15 @@ impl Error {
     pub fn empty() -> Self {
         Self::QueueEmpty
     }
+
+    /// Creates a new [`Error::InvalidOption`].
+    #[must_use]
+    pub fn invalid_option(option: &str, value: &str) -> Self {
+        Self::InvalidOption {
+            option: option.to_owned(),
+            value: value.to_owned(),
+        }
+    }
 }
 
 impl From<std::io::Error> for Error {
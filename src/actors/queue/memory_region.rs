@@ -0,0 +1,143 @@
+//! # A mapped region of process memory.
+//!
+//! The [`MemoryRegion`] type describes one line of a process's
+//! `/proc/<pid>/maps` file: an address range, its permissions, and the
+//! file or pseudo-file (such as `[heap]`) backing it, if any.
+//!
+//! [`MemoryRegion`] is used both to select regions for process memory
+//! scanning, and to expose memory maps to userscripts. Regions are read
+//! through `/proc/<pid>/mem` by [`MemoryRegion::read()`].
+
+use serde::Serialize;
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+};
+
+/// The largest memory region that is read for scanning, in bytes.
+///
+/// Larger regions, such as huge anonymous reservations, fail to load
+/// rather than exhausting the host's memory.
+pub const MAX_REGION_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Represents a Mapped Memory Region
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MemoryRegion {
+    /// Start address of the region (inclusive).
+    pub start: u64,
+
+    /// End address of the region (exclusive).
+    pub end: u64,
+
+    /// Permission flags, such as `r-xp`.
+    pub perms: String,
+
+    /// Offset into the backing file, if any.
+    pub offset: u64,
+
+    /// Inode of the backing file, or zero if anonymous.
+    pub inode: u64,
+
+    /// Backing file path or pseudo-path (`[heap]`, `[stack]`, ...).
+    pub pathname: Option<String>,
+}
+
+impl MemoryRegion {
+    /// Size of the region, in bytes.
+    #[must_use]
+    pub fn size(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Returns true if the region is readable.
+    #[must_use]
+    pub fn is_readable(&self) -> bool {
+        self.perms.starts_with('r')
+    }
+
+    /// Returns true if the region is a private (copy-on-write) mapping.
+    #[must_use]
+    pub fn is_private(&self) -> bool {
+        self.perms.ends_with('p')
+    }
+
+    /// Returns true if the region is not backed by a file.
+    ///
+    /// Pseudo-paths such as `[heap]` and `[stack]` count as anonymous.
+    #[must_use]
+    pub fn is_anonymous(&self) -> bool {
+        self.inode == 0
+    }
+
+    /// Returns true if the region is backed by a file on disk.
+    #[must_use]
+    pub fn is_file_backed(&self) -> bool {
+        self.inode != 0
+            && self
+                .pathname
+                .as_ref()
+                .is_some_and(|path: &String| path.starts_with('/'))
+    }
+
+    /// Returns true if the region is a special kernel-provided page.
+    ///
+    /// These regions (`[vvar]`, `[vsyscall]`, ...) cannot be read
+    /// through `/proc/<pid>/mem`, even if marked readable.
+    #[must_use]
+    pub fn is_kernel_special(&self) -> bool {
+        self.pathname
+            .as_ref()
+            .is_some_and(|path: &String| path.starts_with("[vvar") || path == "[vsyscall]")
+    }
+
+    /// Parse a single line of a `/proc/<pid>/maps` file.
+    #[must_use]
+    pub fn parse(line: &str) -> Option<Self> {
+        // address perms offset dev inode [pathname]
+        let mut fields = line.splitn(6, ' ');
+        let (start, end) = fields.next()?.split_once('-')?;
+        let perms: String = fields.next()?.to_string();
+        let offset: &str = fields.next()?;
+        let _dev: &str = fields.next()?;
+        let inode: &str = fields.next()?;
+        let pathname: Option<String> = fields
+            .next()
+            .map(str::trim)
+            .filter(|path: &&str| !path.is_empty())
+            .map(ToString::to_string);
+
+        Some(Self {
+            start: u64::from_str_radix(start, 16).ok()?,
+            end: u64::from_str_radix(end, 16).ok()?,
+            perms,
+            offset: u64::from_str_radix(offset, 16).ok()?,
+            inode: inode.parse::<u64>().ok()?,
+            pathname,
+        })
+    }
+
+    /// Read the region from the memory of the process with the given
+    /// `pid`.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the region is larger than [`MAX_REGION_SIZE`], or it
+    /// cannot be read, such as when it has been unmapped.
+    pub fn read(&self, pid: u32) -> std::io::Result<Vec<u8>> {
+        if self.size() > MAX_REGION_SIZE {
+            return Err(std::io::Error::other(format!(
+                "memory region of {} bytes exceeds the {MAX_REGION_SIZE} byte limit",
+                self.size()
+            )));
+        }
+        let size: usize = usize::try_from(self.size())
+            .map_err(|_| std::io::Error::other("memory region is too large to read"))?;
+
+        // Seek to the region and read it in full.
+        let mut mem: File = File::open(format!("/proc/{pid}/mem"))?;
+        mem.seek(SeekFrom::Start(self.start))?;
+        let mut content: Vec<u8> = vec![0; size];
+        mem.read_exact(&mut content)?;
+        Ok(content)
+    }
+}
@@ -10,7 +10,7 @@
 //!
 
 use super::{
-    data_item::DataItem,
+    data_item::{DataItem, Metadata},
     error::{Error, QueueResult},
     Queue,
 };
@@ -71,7 +71,8 @@ impl Enqueue {
 ///
 /// ## Reply
 ///
-/// Expect a reply of type [`QueueResult`].
+/// Expect a reply of type [`QueueResult`], holding the item's name,
+/// path, content, and [`Metadata`].
 ///
 /// ## Example
 ///
@@ -91,21 +92,24 @@ impl Enqueue {
 /// queue.ask(Enqueue::item(data)).await.unwrap();
 ///
 /// // Now, dequeue the data item.
-/// let (name, path, content) = queue.ask(Dequeue).await.unwrap();
+/// let (name, path, content, metadata) = queue.ask(Dequeue).await.unwrap();
 /// assert_eq!(name, "hello_world");
 /// assert_eq!(path, None);
 /// assert_eq!(content, b"blablabla-Hello World-blablabla".to_vec());
+/// assert!(metadata.is_empty());
 /// # }
 /// ```
 /// [`realized`]: super::data_item::DataItem::realize()
 pub struct Dequeue;
 
 impl Message<Dequeue> for Queue {
-    type Reply = QueueResult<(String, Option<PathBuf>, Vec<u8>)>;
+    type Reply = QueueResult<(String, Option<PathBuf>, Vec<u8>, Metadata)>;
 
     async fn handle(&mut self, _: Dequeue, _: Context<'_, Self, Self::Reply>) -> Self::Reply {
         if let Some(item) = self.items.pop_front() {
-            Ok(item.realize()?)
+            let metadata: Metadata = item.metadata();
+            let (name, path, content) = item.realize()?;
+            Ok((name, path, content, metadata))
         } else {
             Err(Error::empty())
         }
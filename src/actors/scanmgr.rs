@@ -20,15 +20,19 @@
 
 pub mod error;
 pub mod messages;
+pub mod summary;
 
 use crate::{
     actors::{
         lua_vm::{messages::RegisterUserApi, LuaVM},
         queue::Queue,
-        scanmgr::error::Error,
+        scanmgr::{
+            error::{Error, ScanMgrResult},
+            summary::ScanSummary,
+        },
         user_engine::UserEngine,
     },
-    userscript_api::scanmgr_api::ScanMgrApi,
+    userscript_api::scanmgr_api::{output::OutputOptions, scanresult::ScanResult, ScanMgrApi},
 };
 use kameo::{
     actor::{ActorRef, WeakActorRef},
@@ -36,6 +40,7 @@ use kameo::{
     mailbox::unbounded::UnboundedMailbox,
     Actor,
 };
+use std::path::PathBuf;
 
 /// # The Scan Manager Service
 ///
@@ -53,6 +58,19 @@ pub struct ScanMgr {
 
     /// Weak ref to the [`UserEngine`], for calling userscript engines.
     user_engine_ref: WeakActorRef<UserEngine>,
+
+    /// Summary of the most recent scan, if any.
+    last_summary: Option<ScanSummary>,
+
+    /// Number of scan results produced by every scan so far.
+    total_matches: usize,
+
+    /// File every scan's results are written to, if any.
+    output: Option<(PathBuf, OutputOptions)>,
+
+    /// Every result written to the scan output so far, for formats that
+    /// cannot be appended to and are rewritten whole after each scan.
+    output_results: Vec<ScanResult>,
 }
 
 impl Actor for ScanMgr {
@@ -83,7 +101,37 @@ impl ScanMgr {
             lua_ref: vm,
             queue_ref: queue,
             user_engine_ref: user_engine,
+            last_summary: None,
+            total_matches: 0,
+            output: None,
+            output_results: Vec::new(),
         };
         kameo::spawn(actor)
     }
+
+    /// Write scan results to the scan output, if one is set.
+    ///
+    /// The first scan replaces the output file; later scans append to
+    /// it. Formats that cannot be appended to, such as JSON, are instead
+    /// rewritten with the results of every scan so far. ECS and HEC
+    /// events are stamped with the time the last scan started.
+    fn write_output(&mut self, results: &[ScanResult]) -> ScanMgrResult<()> {
+        let Some((path, options)) = &mut self.output else {
+            return Ok(());
+        };
+        options.ecs.time = self.last_summary.as_ref().map(|summary| summary.started);
+        let written: std::io::Result<()> = if options.format.appendable() {
+            let written: std::io::Result<()> = options.write(path, results);
+            options.append = true;
+            written
+        } else {
+            self.output_results.extend_from_slice(results);
+            options.append = false;
+            options.write(path, &self.output_results)
+        };
+        written.map_err(|source: std::io::Error| Error::WriteOutput {
+            path: path.clone(),
+            source,
+        })
+    }
 }
@@ -6,6 +6,7 @@
 //!
 //! [`ScanMgr`]: super::ScanMgr
 
+use std::path::PathBuf;
 use thiserror::Error as ThisError;
 
 /// Type alias for fallible return types that may return [`Error`].
@@ -31,4 +32,14 @@ pub enum Error {
     /// The scan manager service is not running
     #[error("the scan manager service is not running")]
     NoScanMgr,
+
+    /// Scan results could not be written to the scan output
+    #[error("failed to write scan results to `{}`: {source}", path.display())]
+    WriteOutput {
+        /// Path of the scan output.
+        path: PathBuf,
+
+        /// The inner IO error causing the failure.
+        source: std::io::Error,
+    },
 }
@@ -11,26 +11,52 @@
 
 use crate::{
     actors::{
-        lua_vm::messages::SendWarning,
+        lua_vm::{messages::SendWarning, LuaVM},
         queue::messages::{Dequeue, GetLength},
         scanmgr::{
             error::{Error, ScanMgrResult},
+            summary::ScanSummary,
             ScanMgr,
         },
-        user_engine::messages::ScanBytes,
+        user_engine::{
+            error::UserEngineResult,
+            item_info::ItemInfo,
+            messages::{GetEnginePool, TakeScanProfile},
+            pool::{scan_with_pool, EnginePool},
+            verdict::Verdict,
+            UserEngine,
+        },
     },
     userscript_api::{
         fs_api::path_obj::PathObj,
-        scanmgr_api::scanresult::{DataItemResult, ScanResult},
+        scanmgr_api::{
+            output::OutputOptions,
+            scanresult::{DataItemResult, ScanResult},
+        },
     },
 };
-use kameo::message::{Context, Message};
-use std::path::PathBuf;
+use kameo::{
+    actor::ActorRef,
+    message::{Context, Message},
+};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::Arc,
+    time::{Instant, SystemTime},
+};
+use tokio::task::{Id, JoinError, JoinSet};
 
 /// # Scan all data items in the queue against all active scan engines.
 ///
 /// A request for [`ScanMgr`] to dequeue all [`DataItem`] objects in the
-/// queue and test them against all activated scan engines.
+/// queue and test them against all activated scan engines. If an engine
+/// pool is running, items are scanned in parallel across its workers,
+/// and results are still reported in queue order.
+///
+/// A [`ScanSummary`] of the scan is kept, and can be fetched with
+/// [`GetScanSummary`]. If a scan output is set with [`SetScanOutput`],
+/// the results are also written to it.
 ///
 /// ## Reply
 ///
@@ -60,45 +86,239 @@ impl Message<InvokeScan> for ScanMgr {
             return Err(Error::NoUserEngine);
         };
 
-        // Create a vector of ScanResult items
-        let mut scan_results: Vec<ScanResult> = Vec::with_capacity(16384);
+        // With an engine pool, keep enough items in flight to busy it
+        let pool: Option<Arc<EnginePool>> = user_engine
+            .ask(GetEnginePool)
+            .await
+            .expect("should be infallible");
+        let parallelism: usize = pool.as_ref().map_or(1, |pool| pool.size() * 2);
+
+        // Profile only this scan, by resetting the scan profile first
+        let started: Instant = Instant::now();
+        let started_at: SystemTime = SystemTime::now();
+        let _ = user_engine.ask(TakeScanProfile).await;
+        let mut items: usize = 0;
+        let mut errors: Vec<String> = Vec::new();
+        let mut scanned_items: Vec<DataItemResult> = Vec::new();
+
+        // Scan tasks, each reporting the item's index in the queue
+        let mut scans: JoinSet<(usize, ItemScan)> = JoinSet::new();
+        let mut in_flight: HashMap<Id, String> = HashMap::new();
+        let mut scanned: Vec<(usize, ItemScan)> = Vec::new();
+        let mut index: usize = 0;
 
         // Get the current queue length
         while queue.ask(GetLength).await.expect("should be infallible") > 0 {
             // Dequeue an item or raise a warning on failure
-            let (name, path, content) = match queue.ask(Dequeue).await {
-                Ok((name, path, content)) => (name, path, content),
+            items += 1;
+            let (name, path, content, metadata) = match queue.ask(Dequeue).await {
+                Ok((name, path, content, metadata)) => (name, path, content, metadata),
                 Err(err) => {
                     let warning: String = format!("failed to load data item: {err}");
-                    lua_vm
-                        .tell(SendWarning::Complete(warning))
-                        .await
-                        .expect("should be infallible");
+                    errors.push(send_warning(&lua_vm, warning).await);
                     continue;
                 }
             };
 
-            // Scan the item against all user engines or raise a warning
-            let Ok(results) = user_engine.ask(ScanBytes::from(content)).await else {
-                let warning: String = format!("failed to scan data item `{name}`.\n  HINT: is the path accessible?\n        {path:?}");
-                lua_vm
-                    .tell(SendWarning::Complete(warning))
-                    .await
-                    .expect("should be infallible");
-                continue;
+            // Wait for a scan to finish if enough are in flight
+            while scans.len() >= parallelism {
+                if let Some(joined) = scans.join_next_with_id().await {
+                    collect_scan(&lua_vm, joined, &mut in_flight, &mut scanned, &mut errors).await;
+                }
+            }
+
+            // Scan the item against all user engines
+            let info: ItemInfo = ItemInfo {
+                name: name.clone(),
+                path: path.clone(),
+                size: content.len(),
+                metadata: metadata.clone(),
+            };
+            let user_engine: ActorRef<UserEngine> = user_engine.clone();
+            let pool: Option<Arc<EnginePool>> = pool.clone();
+            let item_name: String = name.clone();
+            let task = scans.spawn(async move {
+                let path: Option<PathObj> = path.map(PathObj);
+                let item: DataItemResult = DataItemResult::new(name, path, &content, metadata);
+                let results = scan_with_pool(&user_engine, pool.as_deref(), content, info).await;
+                (index, (item, results))
+            });
+            in_flight.insert(task.id(), item_name);
+            index += 1;
+        }
+        while let Some(joined) = scans.join_next_with_id().await {
+            collect_scan(&lua_vm, joined, &mut in_flight, &mut scanned, &mut errors).await;
+        }
+
+        // Report results in queue order, regardless of completion order
+        scanned.sort_by_key(|(index, _)| *index);
+        let mut scan_results: Vec<ScanResult> = Vec::with_capacity(scanned.len());
+        for (_, (item, results)) in scanned {
+            // Raise a warning for items that failed to scan
+            let results: Vec<(String, Verdict)> = match results {
+                Ok(results) => results,
+                Err(err) => {
+                    let name: &str = &item.name;
+                    let warning: String = format!("failed to scan data item `{name}`: {err}");
+                    errors.push(send_warning(&lua_vm, warning).await);
+                    continue;
+                }
             };
 
             // Create a ScanResult item for each user engine result
-            for engine_name in results {
-                let name: String = name.clone();
-                let path: Option<PathObj> = path.clone().map(|path: PathBuf| PathObj(path));
+            scanned_items.push(item.clone());
+            for (engine_name, verdict) in results {
                 let result = ScanResult {
                     engine: engine_name,
-                    item: DataItemResult { name, path },
+                    item: item.clone(),
+                    verdict,
                 };
                 scan_results.push(result);
             }
         }
+
+        // Summarize the scan
+        self.last_summary = Some(ScanSummary {
+            started: started_at,
+            items,
+            failed: errors.len(),
+            errors,
+            scanned: scanned_items,
+            matches: scan_results.len(),
+            elapsed: started.elapsed(),
+            engines: user_engine.ask(TakeScanProfile).await.unwrap_or_default(),
+        });
+
+        self.total_matches += scan_results.len();
+
+        // Write the results to the scan output, if any
+        self.write_output(&scan_results)?;
         Ok(scan_results)
     }
 }
+
+/// Raise a scan warning in the Lua virtual machine, returning it to be
+/// recorded in the scan summary.
+async fn send_warning(lua_vm: &ActorRef<LuaVM>, warning: String) -> String {
+    lua_vm
+        .tell(SendWarning::Complete(warning.clone()))
+        .await
+        .expect("should be infallible");
+    warning
+}
+
+/// Collect a finished scan task into the scanned items, or raise a
+/// warning naming its data item if the task panicked.
+async fn collect_scan(
+    lua_vm: &ActorRef<LuaVM>,
+    joined: Result<(Id, (usize, ItemScan)), JoinError>,
+    in_flight: &mut HashMap<Id, String>,
+    scanned: &mut Vec<(usize, ItemScan)>,
+    errors: &mut Vec<String>,
+) {
+    match joined {
+        Ok((id, item)) => {
+            in_flight.remove(&id);
+            scanned.push(item);
+        }
+        Err(err) => {
+            let name: String = in_flight.remove(&err.id()).unwrap_or_default();
+            let warning: String = format!("failed to scan data item `{name}`: {err}");
+            errors.push(send_warning(lua_vm, warning).await);
+        }
+    }
+}
+
+/// A scanned data item, and its engine results.
+type ItemScan = (DataItemResult, UserEngineResult<Vec<(String, Verdict)>>);
+
+/// # Get the Summary of the Last Scan
+///
+/// A request for [`ScanMgr`] to report the [`ScanSummary`] of the most
+/// recent scan, including the time each engine took during the scan.
+///
+/// ## Reply
+///
+/// Expect a reply of type [`Option<ScanSummary>`], which is [`None`] if
+/// no scan has been run yet.
+///
+/// ## Example
+///
+/// ```lua
+/// local results = scanmgr:scan()
+/// print(results:summary())
+/// ```
+pub struct GetScanSummary;
+
+impl Message<GetScanSummary> for ScanMgr {
+    type Reply = Option<ScanSummary>;
+
+    async fn handle(
+        &mut self,
+        _: GetScanSummary,
+        _: Context<'_, Self, Self::Reply>,
+    ) -> Self::Reply {
+        self.last_summary.clone()
+    }
+}
+
+/// # Count the Matches of Every Scan
+///
+/// A request for [`ScanMgr`] to report how many scan results all scans
+/// so far have produced. `sscan run` exits with a non-zero status if
+/// any scan matched.
+///
+/// ## Reply
+///
+/// Expect a reply of type [`usize`].
+///
+/// ## Example
+///
+/// ```sh
+/// sscan run scan.lua || echo "found matches"
+/// ```
+pub struct GetTotalMatches;
+
+impl Message<GetTotalMatches> for ScanMgr {
+    type Reply = usize;
+
+    async fn handle(
+        &mut self,
+        _: GetTotalMatches,
+        _: Context<'_, Self, Self::Reply>,
+    ) -> Self::Reply {
+        self.total_matches
+    }
+}
+
+/// # Write Every Scan's Results to a File
+///
+/// A request for [`ScanMgr`] to write the results of every following
+/// scan to the file at `path`. The first scan replaces the file, unless
+/// the options say to append, and later scans append to it. JSON and
+/// SARIF files are instead rewritten with the results of every scan so
+/// far. Setting the output to [`None`] stops writing results.
+///
+/// ## Reply
+///
+/// Expect no reply from the scan manager service.
+///
+/// ## Example
+///
+/// ```sh
+/// sscan run --output results.sarif scan.lua
+/// ```
+pub struct SetScanOutput(pub Option<(PathBuf, OutputOptions)>);
+
+impl Message<SetScanOutput> for ScanMgr {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        msg: SetScanOutput,
+        _: Context<'_, Self, Self::Reply>,
+    ) -> Self::Reply {
+        self.output = msg.0;
+        self.output_results.clear();
+    }
+}
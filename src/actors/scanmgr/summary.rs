@@ -0,0 +1,90 @@
+//! # Summaries of Completed Scans
+//!
+//! After each scan, the [`ScanMgr`] keeps a [`ScanSummary`] of how many
+//! data items were scanned and matched, and how long each engine took.
+//! Userscripts can read it from the scan results:
+//!
+//! ```lua
+//! local results = scanmgr:scan()
+//! print(results:summary())
+//! ```
+//!
+//! [`ScanMgr`]: super::ScanMgr
+
+use crate::{
+    actors::user_engine::stats::EngineProfile,
+    userscript_api::{
+        include::{IntoLua, Lua, LuaResult, LuaTable, LuaValue},
+        scanmgr_api::scanresult::DataItemResult,
+    },
+};
+use std::{
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
+
+/// Summarizes a completed scan.
+#[derive(Debug, Clone)]
+pub struct ScanSummary {
+    /// Time the scan started.
+    pub started: SystemTime,
+
+    /// Number of data items dequeued for scanning.
+    pub items: usize,
+
+    /// Number of data items that failed to load or scan.
+    pub failed: usize,
+
+    /// Why each failed data item failed to load or scan.
+    pub errors: Vec<String>,
+
+    /// Every data item scanned without error, whether it matched or not.
+    pub scanned: Vec<DataItemResult>,
+
+    /// Number of scan results, one per matching engine and item.
+    pub matches: usize,
+
+    /// Wall-clock time the scan took.
+    pub elapsed: Duration,
+
+    /// Performance statistics of each engine during the scan.
+    pub engines: EngineProfile,
+}
+
+impl Display for ScanSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Scanned {} items ({} failed) in {:.3} ms, with {} matches.",
+            self.items,
+            self.failed,
+            self.elapsed.as_secs_f64() * 1000.0,
+            self.matches,
+        )?;
+        write!(f, "{}", self.engines)
+    }
+}
+
+impl IntoLua for ScanSummary {
+    /// Converts the summary into a table, which prints as a report in
+    /// the REPL.
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let summary: LuaTable = lua.create_table()?;
+        summary.set("items", self.items)?;
+        summary.set("failed", self.failed)?;
+        summary.set("matches", self.matches)?;
+        summary.set("elapsed_ms", self.elapsed.as_secs_f64() * 1000.0)?;
+        summary.set("errors", self.errors.clone())?;
+
+        // Print the summary as a report through `__tostring`.
+        let report: String = self.to_string();
+        summary.set("engines", self.engines)?;
+        let metatable: LuaTable = lua.create_table()?;
+        metatable.set(
+            "__tostring",
+            lua.create_function(move |_, _: LuaTable| Ok(report.clone()))?,
+        )?;
+        summary.set_metatable(Some(metatable));
+        Ok(LuaValue::Table(summary))
+    }
+}
@@ -2,8 +2,24 @@
 //!
 //! The [`UserEngine`] actor provides a mechanism for userscripts to
 //! register custom scan engines. Each custom scan engine is a valid Lua
-//! function, which must accept a single argument of Lua type `string`,
-//! and which must return a single argument `bool`.
+//! function, which must accept an argument of Lua type `string` and an
+//! [info] table describing the data item, and which must return either a
+//! `bool` or a [verdict] table.
+//!
+//! Engines are invoked in a deterministic order: by their `order` (zero
+//! unless given at registration), then by registration order. An engine
+//! may require other engines to have matched before it is invoked, in
+//! which case it always runs after them and is passed their verdicts.
+//!
+//! Each engine invocation runs on its own Lua thread, held to the
+//! engine's resource [limits] so that a runaway engine fails with an
+//! error instead of hanging the scan.
+//!
+//! Engines may also be run in parallel by an engine [pool] of separate
+//! virtual machines, each initialized from the same engine script.
+//!
+//! Every invocation is timed, and each engine's [stats] are kept for
+//! the session and for the most recent scan.
 //!
 //! ## Interacting with the Userscript Scan Engine Service.
 //!
@@ -15,12 +31,33 @@
 //! messages that can be sent to the userscript scan engine service to
 //! interact with it, along with usage and code examples.
 //!
+//! [info]: item_info::ItemInfo
+//! [verdict]: verdict::Verdict
+//! [limits]: limits::EngineLimits
+//! [pool]: pool::EnginePool
+//! [stats]: stats::EngineStats
 
+pub mod engine_info;
 pub mod error;
+pub mod item_info;
+pub mod limits;
 pub mod messages;
+pub mod pool;
+pub mod selector;
+pub mod stats;
+pub mod verdict;
 
 use crate::{
-    actors::lua_vm::{messages::RegisterUserApi, LuaVM},
+    actors::{
+        lua_vm::{messages::RegisterUserApi, LuaVM},
+        user_engine::{
+            engine_info::EngineInfo,
+            error::{Error, UserEngineResult},
+            limits::EngineLimits,
+            pool::EnginePool,
+            stats::{EngineProfile, EngineStats},
+        },
+    },
     userscript_api::user_engine_api::UserEngineApi,
 };
 use kameo::{
@@ -29,8 +66,8 @@ use kameo::{
     mailbox::unbounded::UnboundedMailbox,
     Actor,
 };
-use mlua::Function;
-use std::collections::HashMap;
+use mlua::{Function, IntoLuaMulti, Lua, Thread, Value};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 /// # The Userscript Scan Engine Service
 ///
@@ -38,13 +75,41 @@ use std::collections::HashMap;
 /// scan engines, as well as invoking scans against all registered
 /// engines for any byte vector.
 pub struct UserEngine {
-    /// Stores all registered userscript scan engines.
-    engines: HashMap<String, Function>,
+    /// Stores all registered userscript scan engines, in registration order.
+    engines: Vec<RegisteredEngine>,
+
+    /// Indices into `engines`, in the order the engines are invoked.
+    schedule: Vec<usize>,
+
+    /// Limits for engines that were not registered with their own.
+    default_limits: EngineLimits,
+
+    /// Handle to the Lua state, for running engines under limits.
+    lua: Lua,
+
+    /// Workers running engines from an engine script in parallel.
+    pool: Option<Arc<EnginePool>>,
+
+    /// Performance statistics of each engine, for the whole session.
+    stats: HashMap<String, EngineStats>,
+
+    /// Performance statistics of each engine, since last taken.
+    scan_stats: HashMap<String, EngineStats>,
 
     /// Weak ref to the Lua virtual machine, for registering the API.
     lua_vm: WeakActorRef<LuaVM>,
 }
 
+/// A userscript scan engine and its description.
+#[derive(Clone)]
+struct RegisteredEngine {
+    /// The Lua function implementing the scan engine.
+    spec: Function,
+
+    /// Describes the scan engine to userscripts.
+    info: EngineInfo,
+}
+
 impl Actor for UserEngine {
     type Mailbox = UnboundedMailbox<Self>;
 
@@ -67,9 +132,15 @@ impl UserEngine {
     /// initial allocation size that makes sense. Otherwise, the
     /// standard `spawn()` function will allocate very often.
     #[must_use]
-    pub fn spawn(vm: WeakActorRef<LuaVM>) -> ActorRef<Self> {
+    pub fn spawn(vm: WeakActorRef<LuaVM>, lua: Lua) -> ActorRef<Self> {
         let engine: Self = Self {
-            engines: HashMap::new(),
+            engines: Vec::new(),
+            schedule: Vec::new(),
+            default_limits: EngineLimits::default(),
+            lua,
+            pool: None,
+            stats: HashMap::new(),
+            scan_stats: HashMap::new(),
             lua_vm: vm,
         };
         kameo::spawn(engine)
@@ -77,11 +148,126 @@ impl UserEngine {
 
     /// Spawn a new [`UserEngine`] with the given initial capacity.
     #[must_use]
-    pub fn spawn_with_capacity(vm: WeakActorRef<LuaVM>, capacity: usize) -> ActorRef<Self> {
+    pub fn spawn_with_capacity(
+        vm: WeakActorRef<LuaVM>,
+        lua: Lua,
+        capacity: usize,
+    ) -> ActorRef<Self> {
         let engine: Self = Self {
-            engines: HashMap::with_capacity(capacity),
+            engines: Vec::with_capacity(capacity),
+            schedule: Vec::with_capacity(capacity),
+            default_limits: EngineLimits::default(),
+            lua,
+            pool: None,
+            stats: HashMap::new(),
+            scan_stats: HashMap::new(),
             lua_vm: vm,
         };
         kameo::spawn(engine)
     }
+
+    /// Replace the registered engines, updating the invocation order.
+    ///
+    /// The engines are left unchanged if their requirements would form
+    /// a cycle.
+    fn set_engines(&mut self, engines: Vec<RegisteredEngine>) -> UserEngineResult<()> {
+        self.schedule = schedule(&engines)?;
+        self.engines = engines;
+        Ok(())
+    }
+
+    /// Invoke a scan engine on its own thread, under its limits.
+    async fn invoke(
+        &self,
+        engine: &RegisteredEngine,
+        args: impl IntoLuaMulti,
+    ) -> mlua::Result<Value> {
+        let thread: Thread = self.lua.create_thread(engine.spec.clone())?;
+        let limits: EngineLimits = engine.info.limits.or(self.default_limits);
+        limits.run(&self.lua, thread, args).await
+    }
+
+    /// Record an engine invocation in the engine's statistics.
+    fn record(&mut self, engine: &str, elapsed: Duration, bytes: usize, failed: bool) {
+        for stats in [&mut self.stats, &mut self.scan_stats] {
+            stats
+                .entry(engine.to_owned())
+                .or_default()
+                .record(elapsed, bytes, failed);
+        }
+    }
+
+    /// Profile the registered engines from `stats`, in invocation order.
+    fn profile(&self, stats: &HashMap<String, EngineStats>) -> EngineProfile {
+        let engines: Vec<(String, EngineStats)> = self
+            .schedule
+            .iter()
+            .map(|&index: &usize| {
+                let name: &String = &self.engines[index].info.name;
+                (name.clone(), stats.get(name).copied().unwrap_or_default())
+            })
+            .collect();
+        EngineProfile { engines }
+    }
+
+    /// Find the index of the named engine.
+    fn position(&self, name: &str) -> Option<usize> {
+        self.engines
+            .iter()
+            .position(|engine: &RegisteredEngine| engine.info.name == name)
+    }
+}
+
+/// Work out the order to invoke engines in.
+///
+/// Engines are ordered by their `order`, then by registration, except
+/// that an engine is always moved after any engines it requires.
+/// Requirements naming unregistered engines are ignored here, and
+/// simply never match during scans.
+fn schedule(engines: &[RegisteredEngine]) -> UserEngineResult<Vec<usize>> {
+    let mut pending: Vec<usize> = (0..engines.len()).collect();
+    pending.sort_by_key(|&index: &usize| engines[index].info.order);
+
+    let mut scheduled: Vec<usize> = Vec::with_capacity(engines.len());
+    while !pending.is_empty() {
+        // Take the first engine whose requirements are all scheduled.
+        let is_ready = |&index: &usize| {
+            engines[index]
+                .info
+                .requires
+                .iter()
+                .all(|required: &String| {
+                    !pending
+                        .iter()
+                        .any(|&other: &usize| &engines[other].info.name == required)
+                })
+        };
+        let Some(next) = pending.iter().position(is_ready) else {
+            return Err(Error::requirement_cycle(
+                &in_cycle(engines, &pending).info.name,
+            ));
+        };
+        scheduled.push(pending.remove(next));
+    }
+    Ok(scheduled)
+}
+
+/// Find an engine within a requirement cycle among `pending` engines.
+///
+/// Every pending engine requires another pending engine, so following
+/// requirements for as many steps as there are pending engines must end
+/// up inside a cycle.
+fn in_cycle<'a>(engines: &'a [RegisteredEngine], pending: &[usize]) -> &'a RegisteredEngine {
+    let mut engine: &RegisteredEngine = &engines[pending[0]];
+    for _ in 0..pending.len() {
+        let required: Option<&RegisteredEngine> = pending
+            .iter()
+            .map(|&index: &usize| &engines[index])
+            .find(|other: &&RegisteredEngine| engine.info.requires.contains(&other.info.name));
+        match required {
+            Some(required) => engine = required,
+            None => break,
+        }
+    }
+    engine
 }
@@ -0,0 +1,111 @@
+//! # Registered Userscript Scan Engine Descriptions
+//!
+//! The [`EngineInfo`] type describes a registered userscript scan
+//! engine: its name, whether it is enabled, and the optional description,
+//! version, [selector], order, requirements, and limits given at
+//! registration. It is what userscripts see when listing or describing
+//! engines.
+//!
+//! [selector]: super::selector::Selector
+
+use crate::{
+    actors::user_engine::{limits::EngineLimits, selector::Selector},
+    userscript_api::include::{LuaUserData, LuaUserDataFields, LuaUserDataMethods, LuaUserDataRef},
+};
+
+/// Describes a Registered Userscript Scan Engine
+#[derive(Debug, Clone)]
+pub struct EngineInfo {
+    /// Name of the scan engine, as shown in scan results.
+    pub name: String,
+
+    /// What the scan engine looks for, if given at registration.
+    pub description: Option<String>,
+
+    /// Version of the scan engine, if given at registration.
+    pub version: Option<String>,
+
+    /// Restricts the data items the scan engine is called on.
+    pub selector: Selector,
+
+    /// Engines run in ascending order, then in registration order.
+    pub order: i64,
+
+    /// Engines that must match an item before this engine is called.
+    pub requires: Vec<String>,
+
+    /// Resource limits on each invocation, beyond the defaults.
+    pub limits: EngineLimits,
+
+    /// Whether the scan engine is called during scans.
+    pub enabled: bool,
+}
+
+impl EngineInfo {
+    /// Describe a newly registered, enabled scan engine.
+    #[must_use]
+    pub fn new(name: String, description: Option<String>, version: Option<String>) -> Self {
+        Self {
+            name,
+            description,
+            version,
+            selector: Selector::default(),
+            order: 0,
+            requires: Vec::new(),
+            limits: EngineLimits::default(),
+            enabled: true,
+        }
+    }
+}
+
+impl std::fmt::Display for EngineInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(version) = &self.version {
+            write!(f, " v{version}")?;
+        }
+        if !self.enabled {
+            write!(f, " [disabled]")?;
+        }
+        if !self.selector.is_empty() {
+            write!(f, " [{}]", self.selector)?;
+        }
+        if !self.requires.is_empty() {
+            write!(f, " [requires={}]", self.requires.join(","))?;
+        }
+        if let Some(description) = &self.description {
+            write!(f, " - {description}")?;
+        }
+        Ok(())
+    }
+}
+
+impl LuaUserData for EngineInfo {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("name", |_, this: &EngineInfo| Ok(this.name.clone()));
+        fields.add_field_method_get("description", |_, this: &EngineInfo| {
+            Ok(this.description.clone())
+        });
+        fields.add_field_method_get("version", |_, this: &EngineInfo| Ok(this.version.clone()));
+        fields.add_field_method_get("filters", |_, this: &EngineInfo| {
+            Ok((!this.selector.is_empty()).then(|| this.selector.to_string()))
+        });
+        fields.add_field_method_get("order", |_, this: &EngineInfo| Ok(this.order));
+        fields.add_field_method_get("requires", |_, this: &EngineInfo| Ok(this.requires.clone()));
+        fields.add_field_method_get("max_instructions", |_, this: &EngineInfo| {
+            Ok(this.limits.max_instructions)
+        });
+        fields.add_field_method_get("max_memory", |_, this: &EngineInfo| {
+            Ok(this.limits.max_memory)
+        });
+        fields.add_field_method_get("enabled", |_, this: &EngineInfo| Ok(this.enabled));
+    }
+
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        // Converts the EngineInfo to a one-line summary.
+        methods.add_async_meta_method(
+            "__tostring",
+            |_, this: LuaUserDataRef<EngineInfo>, ()| async move { Ok(this.to_string()) },
+        );
+    }
+}
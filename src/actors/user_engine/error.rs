@@ -6,6 +6,7 @@
 //!
 //! [`UserEngine`]: super::UserEngine
 
+use kameo::error::SendError;
 use thiserror::Error as ThisError;
 
 /// Type alias for results that may be [`Error`]
@@ -28,6 +29,27 @@ pub enum Error {
     #[error("the userscript scan engine service is not running")]
     NoUserEngine,
 
+    /// No userscript scan engine is registered with the given name.
+    #[error("no userscript engine named `{engine}` is registered")]
+    NoSuchEngine {
+        /// Name of the userscript scan engine that was requested.
+        engine: String,
+    },
+
+    /// Userscript scan engines require each other's verdicts in a cycle.
+    #[error("userscript engine `{engine}` would require its own verdict")]
+    RequirementCycle {
+        /// Name of a userscript scan engine within the cycle.
+        engine: String,
+    },
+
+    /// A worker of the engine pool failed to start.
+    #[error("failed to start engine pool worker: {reason}")]
+    PoolStartup {
+        /// Why the worker failed to start, such as an engine script error.
+        reason: String,
+    },
+
     /// An error occurred trying to invoke a userscript scan engine.
     #[error("failed to invoke userscript engine {engine}: {source}")]
     EngineInvocation {
@@ -40,9 +62,42 @@ pub enum Error {
 }
 
 impl Error {
+    /// Create a new [`Error::NoSuchEngine`].
+    #[must_use]
+    pub fn no_such_engine(engine: &str) -> Self {
+        Self::NoSuchEngine {
+            engine: engine.to_owned(),
+        }
+    }
+
+    /// Create a new [`Error::RequirementCycle`].
+    #[must_use]
+    pub fn requirement_cycle(engine: &str) -> Self {
+        Self::RequirementCycle {
+            engine: engine.to_owned(),
+        }
+    }
+
+    /// Create a new [`Error::PoolStartup`].
+    #[must_use]
+    pub fn pool_startup(reason: &impl ToString) -> Self {
+        Self::PoolStartup {
+            reason: reason.to_string(),
+        }
+    }
+
     /// Create a new [`Error::EngineInvocation`].
     #[must_use]
     pub fn engine_invocation(engine: String, source: mlua::Error) -> Self {
         Self::EngineInvocation { engine, source }
     }
 }
+
+impl<M> From<SendError<M, Error>> for Error {
+    fn from(value: SendError<M, Error>) -> Self {
+        match value {
+            SendError::HandlerError(err) => err,
+            _ => Self::NoUserEngine,
+        }
+    }
+}
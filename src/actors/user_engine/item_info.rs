@@ -0,0 +1,60 @@
+//! # Data Item Information for Userscript Scan Engines
+//!
+//! Alongside the payload bytestring, each userscript scan engine is
+//! passed an info table describing the data item being scanned, so that
+//! engines can decide what to match based on a file's name or path, or
+//! on metadata attached when the item was enqueued:
+//!
+//! ```lua
+//! user_engines:register('ps1_only', function(payload, info)
+//!     if not info.name:match('%.ps1$') then return false end
+//!     return payload:find('IEX', 1, true) ~= nil
+//! end)
+//! ```
+
+use crate::{
+    actors::queue::data_item::Metadata,
+    userscript_api::{
+        fs_api::path_obj::PathObj,
+        include::{IntoLua, Lua, LuaResult, LuaSerdeExt, LuaTable, LuaValue},
+    },
+};
+use std::path::PathBuf;
+
+/// Describes the data item passed to a userscript scan engine.
+#[derive(Debug, Clone, Default)]
+pub struct ItemInfo {
+    /// Name of the data item.
+    pub name: String,
+
+    /// Path of the data item, if it originates from a file.
+    pub path: Option<PathBuf>,
+
+    /// Size of the payload, in bytes.
+    pub size: usize,
+
+    /// Extra information attached to the data item when enqueued.
+    pub metadata: Metadata,
+}
+
+impl ItemInfo {
+    /// Describe a data item with no path or metadata.
+    #[must_use]
+    pub fn named(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoLua for ItemInfo {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let info: LuaTable = lua.create_table()?;
+        info.set("name", self.name)?;
+        info.set("path", self.path.map(PathObj))?;
+        info.set("size", self.size)?;
+        info.set("metadata", lua.to_value(&self.metadata)?)?;
+        Ok(LuaValue::Table(info))
+    }
+}
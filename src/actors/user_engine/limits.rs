@@ -0,0 +1,227 @@
+//! # Resource Limits for Userscript Scan Engines
+//!
+//! A userscript scan engine with an infinite loop or runaway allocation
+//! would otherwise hang or exhaust the whole Lua virtual machine. Each
+//! engine invocation can be held to [`EngineLimits`], set per engine at
+//! registration or as defaults for all engines:
+//!
+//! ```lua
+//! user_engines:set_limits({max_instructions = 10000000})
+//! user_engines:register('slow', fn, {max_instructions = 1e9, max_memory = '64MB'})
+//! ```
+//!
+//! Overrunning a limit fails the engine invocation with an error, rather
+//! than hanging the scan. The other engines still scan the item.
+
+use crate::userscript_api::include::{Lua, LuaError, LuaTable, LuaValue};
+use mlua::{Debug, FromLuaMulti, HookTriggers, IntoLuaMulti, Thread, VmState};
+use std::{
+    future::{poll_fn, Future},
+    pin::pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+/// How often the instruction limit is checked, in Lua instructions.
+const INSTRUCTION_CHECK_INTERVAL: u64 = 1000;
+
+/// Resource limits on a single userscript scan engine invocation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EngineLimits {
+    /// Most Lua instructions an invocation may run.
+    pub max_instructions: Option<u64>,
+
+    /// Most additional memory an invocation may allocate, in bytes.
+    pub max_memory: Option<u64>,
+}
+
+impl EngineLimits {
+    /// Parse limits from a userscript options table.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if a limit is not a positive number, or a size string
+    /// cannot be parsed.
+    pub fn from_opts(opts: &LuaTable) -> Result<Self, LuaError> {
+        let max_instructions: Option<u64> = match opts.get::<LuaValue>("max_instructions")? {
+            LuaValue::Nil => None,
+            LuaValue::Integer(limit) if limit > 0 => limit.try_into().ok(),
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            LuaValue::Number(limit) if limit >= 1.0 => Some(limit as u64),
+            _ => {
+                return Err(LuaError::runtime(
+                    "limit `max_instructions` must be a positive number",
+                ))
+            }
+        };
+        Ok(Self {
+            max_instructions,
+            max_memory: lua_size(opts.get("max_memory")?, "max_memory")?,
+        })
+    }
+
+    /// Fill in any limits not set from `defaults`.
+    #[must_use]
+    pub fn or(self, defaults: Self) -> Self {
+        Self {
+            max_instructions: self.max_instructions.or(defaults.max_instructions),
+            max_memory: self.max_memory.or(defaults.max_memory),
+        }
+    }
+
+    /// Run a scan engine invocation on `thread` under the limits.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the invocation fails or overruns a limit, or if the
+    /// limits cannot be applied.
+    pub async fn run<A, R>(&self, lua: &Lua, thread: Thread, args: A) -> Result<R, LuaError>
+    where
+        A: IntoLuaMulti,
+        R: FromLuaMulti,
+    {
+        // The guard puts the VM back however the invocation ends.
+        let mut guard: LimitsGuard<'_> = LimitsGuard {
+            lua,
+            hooked: None,
+            previous_memory_limit: self.apply_memory_limit(lua)?,
+        };
+        let exceeded: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        if let Some(max_instructions) = self.max_instructions {
+            thread.set_hook(
+                instruction_triggers(max_instructions),
+                instruction_hook(max_instructions, Arc::clone(&exceeded)),
+            );
+            guard.hooked = Some(thread.clone());
+        }
+
+        // A yield from the instruction hook means the invocation must
+        // be abandoned, as protected calls could catch an error.
+        let mut invocation = pin!(thread.into_async::<R>(args));
+        poll_fn(|cx: &mut Context<'_>| {
+            let poll: Poll<Result<R, LuaError>> = invocation.as_mut().poll(cx);
+            if exceeded.load(Ordering::Relaxed) {
+                return Poll::Ready(Err(instruction_limit_error(self.max_instructions)));
+            }
+            poll
+        })
+        .await
+    }
+
+    /// Allow `max_memory` beyond the memory already in use, without
+    /// raising a VM-wide limit. Returns the previous memory limit.
+    fn apply_memory_limit(&self, lua: &Lua) -> Result<usize, LuaError> {
+        let previous: usize = lua.set_memory_limit(0)?;
+        let mut limit: usize = previous;
+        if let Some(max_memory) = self.max_memory {
+            let max_memory: usize = usize::try_from(max_memory).unwrap_or(usize::MAX);
+            let budget: usize = lua.used_memory().saturating_add(max_memory);
+            limit = if previous == 0 {
+                budget
+            } else {
+                budget.min(previous)
+            };
+        }
+        lua.set_memory_limit(limit)?;
+        Ok(previous)
+    }
+}
+
+/// Restores the VM after an invocation run under [`EngineLimits`],
+/// whether it returns, fails, or is dropped part way through.
+struct LimitsGuard<'lua> {
+    /// The VM the invocation ran in.
+    lua: &'lua Lua,
+
+    /// The invocation's thread, if an instruction hook was set on it.
+    hooked: Option<Thread>,
+
+    /// The memory limit in place before the invocation.
+    previous_memory_limit: usize,
+}
+
+impl Drop for LimitsGuard<'_> {
+    fn drop(&mut self) {
+        // Only the invocation's own thread is unhooked, leaving any hook
+        // on other threads, such as one from `debug.sethook`, in place.
+        if let Some(thread) = self.hooked.take() {
+            thread.set_hook(HookTriggers::new(), |_, _| Ok(VmState::Continue));
+        }
+        let _ = self.lua.set_memory_limit(self.previous_memory_limit);
+    }
+}
+
+/// How often to run the instruction hook for `max_instructions`.
+fn instruction_triggers(max_instructions: u64) -> HookTriggers {
+    let interval: u64 = max_instructions.clamp(1, INSTRUCTION_CHECK_INTERVAL);
+    HookTriggers::new().every_nth_instruction(u32::try_from(interval).unwrap_or(u32::MAX))
+}
+
+/// Create a hook stopping a thread after `max_instructions`.
+///
+/// Once the limit is passed, the hook alternates between yielding,
+/// which abandons the invocation, and raising an error, for code that
+/// cannot yield, such as callbacks from C functions.
+fn instruction_hook(
+    max_instructions: u64,
+    exceeded: Arc<AtomicBool>,
+) -> impl Fn(&Lua, Debug) -> Result<VmState, LuaError> + Send + 'static {
+    let interval: u64 = max_instructions.clamp(1, INSTRUCTION_CHECK_INTERVAL);
+    let executed: AtomicU64 = AtomicU64::new(0);
+    move |_, _| {
+        let executed: u64 = executed.fetch_add(interval, Ordering::Relaxed) + interval;
+        if executed <= max_instructions {
+            Ok(VmState::Continue)
+        } else if exceeded.swap(true, Ordering::Relaxed) && (executed / interval).is_multiple_of(2)
+        {
+            Err(instruction_limit_error(Some(max_instructions)))
+        } else {
+            Ok(VmState::Yield)
+        }
+    }
+}
+
+/// The error for an invocation overrunning its instruction limit.
+fn instruction_limit_error(max_instructions: Option<u64>) -> LuaError {
+    let max_instructions: u64 = max_instructions.unwrap_or_default();
+    LuaError::runtime(format!("instruction limit of {max_instructions} exceeded"))
+}
+
+/// Parse a size given as a string such as `'5MB'`, or in bytes.
+///
+/// Units are binary: `KB` and `KiB` are both 1024 bytes.
+#[must_use]
+pub fn parse_size(size: &str) -> Option<u64> {
+    let size: String = size.trim().to_uppercase();
+    let digits: usize = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (number, unit): (&str, &str) = size.split_at(digits);
+    let number: u64 = number.parse().ok()?;
+    let shift: u32 = match unit.trim() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 10,
+        "M" | "MB" | "MIB" => 20,
+        "G" | "GB" | "GIB" => 30,
+        _ => return None,
+    };
+    number.checked_mul(1 << shift)
+}
+
+/// Parse a size option, given in bytes or as a string such as `'5MB'`.
+pub(crate) fn lua_size(value: LuaValue, option: &str) -> Result<Option<u64>, LuaError> {
+    let size: Option<u64> = match value {
+        LuaValue::Nil => return Ok(None),
+        LuaValue::Integer(size) => u64::try_from(size).ok(),
+        LuaValue::String(size) => parse_size(&size.to_str()?),
+        _ => None,
+    };
+    size.map(Some).ok_or_else(|| {
+        LuaError::runtime(format!(
+            "option `{option}` must be a size in bytes or a string such as '5MB'"
+        ))
+    })
+}
@@ -11,13 +11,24 @@
 //!
 
 use crate::{
-    actors::user_engine::{
-        error::{Error, UserEngineResult},
-        UserEngine,
+    actors::{
+        lua_vm::{messages::SendWarning, VmSettings},
+        user_engine::{
+            engine_info::EngineInfo,
+            error::{Error, UserEngineResult},
+            item_info::ItemInfo,
+            limits::EngineLimits,
+            pool::EnginePool,
+            selector::Selector,
+            stats::EngineProfile,
+            verdict::Verdict,
+            RegisteredEngine, UserEngine,
+        },
     },
     userscript_api::include::{LuaFunction, LuaString},
 };
 use kameo::message::{Context, Message};
+use std::{collections::HashMap, sync::Arc, time::Instant};
 
 /// # Register a Userscript Scan Engine
 ///
@@ -25,9 +36,18 @@ use kameo::message::{Context, Message};
 /// scan engine for use during scans. Once registered, the custom scan
 /// engine will be called on every request to [`ScanBytes`].
 ///
+/// Registering an engine under an existing name replaces it, and
+/// re-enables it if it was disabled. An engine registered with a
+/// [`Selector`] is only called on data items that match it.
+///
+/// An engine may require other engines to match an item before it is
+/// called on that item, and is then passed their verdicts.
+///
 /// ## Reply
 ///
-/// Expect no reply from the userscript scan engine service.
+/// Expect a reply of type [`UserEngineResult<()>`], which fails with
+/// [`Error::RequirementCycle`] if the engine's requirements would
+/// depend on its own verdict.
 ///
 /// ## Example
 ///
@@ -44,17 +64,51 @@ pub struct RegisterUserEngine {
 
     /// The function to register as the userscript scan engine
     spec: LuaFunction,
+
+    /// What the userscript scan engine looks for
+    description: Option<String>,
+
+    /// Version of the userscript scan engine
+    version: Option<String>,
+
+    /// Restricts the data items the userscript scan engine is called on
+    selector: Selector,
+
+    /// Position of the userscript scan engine in the invocation order
+    order: i64,
+
+    /// Engines that must match before the userscript scan engine is called
+    requires: Vec<String>,
+
+    /// Resource limits on each invocation of the userscript scan engine
+    limits: EngineLimits,
 }
 
 impl Message<RegisterUserEngine> for UserEngine {
-    type Reply = ();
+    type Reply = UserEngineResult<()>;
 
     async fn handle(
         &mut self,
         msg: RegisterUserEngine,
         _: Context<'_, Self, Self::Reply>,
     ) -> Self::Reply {
-        self.engines.insert(msg.name, msg.spec);
+        let mut info: EngineInfo = EngineInfo::new(msg.name, msg.description, msg.version);
+        info.selector = msg.selector;
+        info.order = msg.order;
+        info.requires = msg.requires;
+        info.limits = msg.limits;
+        let engine: RegisteredEngine = RegisteredEngine {
+            spec: msg.spec,
+            info,
+        };
+
+        // A replaced engine keeps its place in the registration order.
+        let mut engines: Vec<RegisteredEngine> = self.engines.clone();
+        match self.position(&engine.info.name) {
+            Some(index) => engines[index] = engine,
+            None => engines.push(engine),
+        }
+        self.set_engines(engines)
     }
 }
 
@@ -62,7 +116,342 @@ impl RegisterUserEngine {
     /// Create a new [`RegisterUserEngine`] message.
     #[must_use]
     pub fn using(name: String, spec: LuaFunction) -> Self {
-        Self { name, spec }
+        Self {
+            name,
+            spec,
+            description: None,
+            version: None,
+            selector: Selector::default(),
+            order: 0,
+            requires: Vec::new(),
+            limits: EngineLimits::default(),
+        }
+    }
+
+    /// Attach a description and version to the userscript scan engine.
+    #[must_use]
+    pub fn with_details(mut self, description: Option<String>, version: Option<String>) -> Self {
+        self.description = description;
+        self.version = version;
+        self
+    }
+
+    /// Restrict the data items the userscript scan engine is called on.
+    #[must_use]
+    pub fn with_selector(mut self, selector: Selector) -> Self {
+        self.selector = selector;
+        self
+    }
+
+    /// Set the userscript scan engine's position in the invocation order.
+    #[must_use]
+    pub fn with_order(mut self, order: i64) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Only call the userscript scan engine on items the named engines
+    /// matched.
+    #[must_use]
+    pub fn with_requirements(mut self, requires: Vec<String>) -> Self {
+        self.requires = requires;
+        self
+    }
+
+    /// Hold each invocation of the userscript scan engine to `limits`,
+    /// in place of the defaults.
+    #[must_use]
+    pub fn with_limits(mut self, limits: EngineLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// # Unregister a Userscript Scan Engine
+///
+/// A request for the [`UserEngine`] to remove a registered userscript
+/// scan engine, so that it is no longer called during scans.
+///
+/// ## Reply
+///
+/// Expect a reply of type [`UserEngineResult<()>`], which fails with
+/// [`Error::NoSuchEngine`] if no engine has the given name.
+///
+/// ## Example
+///
+/// ```lua
+/// user_engines:unregister('match_hello')
+/// ```
+pub struct UnregisterUserEngine(pub String);
+
+impl Message<UnregisterUserEngine> for UserEngine {
+    type Reply = UserEngineResult<()>;
+
+    async fn handle(
+        &mut self,
+        msg: UnregisterUserEngine,
+        _: Context<'_, Self, Self::Reply>,
+    ) -> Self::Reply {
+        let index: usize = self
+            .position(&msg.0)
+            .ok_or_else(|| Error::no_such_engine(&msg.0))?;
+        let mut engines: Vec<RegisteredEngine> = self.engines.clone();
+        engines.remove(index);
+        self.set_engines(engines)
+    }
+}
+
+/// # Enable or Disable a Userscript Scan Engine
+///
+/// A request for the [`UserEngine`] to enable or disable a registered
+/// userscript scan engine. Disabled engines stay registered, but are
+/// skipped by [`ScanBytes`] until enabled again.
+///
+/// ## Reply
+///
+/// Expect a reply of type [`UserEngineResult<()>`], which fails with
+/// [`Error::NoSuchEngine`] if no engine has the given name.
+///
+/// ## Example
+///
+/// ```lua
+/// user_engines:disable('match_hello')
+/// user_engines:enable('match_hello')
+/// ```
+pub struct SetUserEngineEnabled {
+    /// Name of the userscript scan engine
+    name: String,
+
+    /// Whether the userscript scan engine should be called during scans
+    enabled: bool,
+}
+
+impl Message<SetUserEngineEnabled> for UserEngine {
+    type Reply = UserEngineResult<()>;
+
+    async fn handle(
+        &mut self,
+        msg: SetUserEngineEnabled,
+        _: Context<'_, Self, Self::Reply>,
+    ) -> Self::Reply {
+        let index: usize = self
+            .position(&msg.name)
+            .ok_or_else(|| Error::no_such_engine(&msg.name))?;
+        self.engines[index].info.enabled = msg.enabled;
+        Ok(())
+    }
+}
+
+impl SetUserEngineEnabled {
+    /// Create a message enabling the named userscript scan engine.
+    #[must_use]
+    pub fn enable(name: String) -> Self {
+        Self {
+            name,
+            enabled: true,
+        }
+    }
+
+    /// Create a message disabling the named userscript scan engine.
+    #[must_use]
+    pub fn disable(name: String) -> Self {
+        Self {
+            name,
+            enabled: false,
+        }
+    }
+}
+
+/// # Set Default Limits for Userscript Scan Engines
+///
+/// A request for the [`UserEngine`] to hold every invocation of a
+/// userscript scan engine to the given [`EngineLimits`], unless the
+/// engine was registered with its own. Engines overrunning a limit fail
+/// with an error instead of hanging the scan. The limits also apply to
+/// the engine pool, if one is running.
+///
+/// ## Reply
+///
+/// Expect a reply of type [`UserEngineResult<()>`], which fails if an
+/// engine pool worker is no longer running.
+///
+/// ## Example
+///
+/// ```lua
+/// user_engines:set_limits({max_instructions = 10000000, max_memory = '256MB'})
+/// ```
+pub struct SetDefaultLimits(pub EngineLimits);
+
+impl Message<SetDefaultLimits> for UserEngine {
+    type Reply = UserEngineResult<()>;
+
+    async fn handle(
+        &mut self,
+        msg: SetDefaultLimits,
+        _: Context<'_, Self, Self::Reply>,
+    ) -> Self::Reply {
+        self.default_limits = msg.0;
+        if let Some(pool) = &self.pool {
+            pool.set_default_limits(msg.0).await?;
+        }
+        Ok(())
+    }
+}
+
+/// # Start an Engine Pool
+///
+/// A request for the [`UserEngine`] to start an [`EnginePool`] of
+/// `workers` separate virtual machines, each running the engine
+/// `script`. The engines the script registers are then run in parallel
+/// during scans, alongside the engines registered here. Any existing
+/// pool is stopped.
+///
+/// ## Reply
+///
+/// Expect a reply of type [`UserEngineResult<Vec<EngineInfo>>`],
+/// describing the engines in the pool, which fails with
+/// [`Error::PoolStartup`] if the script fails.
+///
+/// ## Example
+///
+/// ```lua
+/// user_engines:start_pool('engines.lua', {workers = 8})
+/// ```
+pub struct StartEnginePool {
+    /// Source of the script registering the pool's engines
+    pub script: String,
+
+    /// Number of worker virtual machines to start
+    pub workers: usize,
+}
+
+impl Message<StartEnginePool> for UserEngine {
+    type Reply = UserEngineResult<Vec<EngineInfo>>;
+
+    async fn handle(
+        &mut self,
+        msg: StartEnginePool,
+        _: Context<'_, Self, Self::Reply>,
+    ) -> Self::Reply {
+        self.pool = None;
+        let settings: VmSettings = self
+            .lua
+            .app_data_ref::<VmSettings>()
+            .map(|settings| *settings)
+            .unwrap_or_default();
+        let pool: EnginePool =
+            EnginePool::start(&msg.script, msg.workers, self.default_limits, settings).await?;
+        let engines: Vec<EngineInfo> = pool.list().await?;
+        self.pool = Some(Arc::new(pool));
+        Ok(engines)
+    }
+}
+
+/// # Stop the Engine Pool
+///
+/// A request for the [`UserEngine`] to stop its [`EnginePool`], if one
+/// is running. Scans already in progress finish using the pool.
+///
+/// ## Reply
+///
+/// Expect no reply from the userscript scan engine service.
+///
+/// ## Example
+///
+/// ```lua
+/// user_engines:stop_pool()
+/// ```
+pub struct StopEnginePool;
+
+impl Message<StopEnginePool> for UserEngine {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _: StopEnginePool,
+        _: Context<'_, Self, Self::Reply>,
+    ) -> Self::Reply {
+        self.pool = None;
+    }
+}
+
+/// # Get the Engine Pool
+///
+/// A request for the [`UserEngine`] to share its [`EnginePool`], so that
+/// data items can be sent to the pool's workers in parallel.
+///
+/// ## Reply
+///
+/// Expect a reply of type [`Option<Arc<EnginePool>>`], which is [`None`]
+/// if no pool is running.
+pub struct GetEnginePool;
+
+impl Message<GetEnginePool> for UserEngine {
+    type Reply = Option<Arc<EnginePool>>;
+
+    async fn handle(&mut self, _: GetEnginePool, _: Context<'_, Self, Self::Reply>) -> Self::Reply {
+        self.pool.clone()
+    }
+}
+
+/// # List Registered Userscript Scan Engines
+///
+/// A request for the [`UserEngine`] to describe every registered
+/// userscript scan engine, enabled or not.
+///
+/// ## Reply
+///
+/// Expect a reply of type [`Vec<EngineInfo>`], in invocation order.
+///
+/// ## Example
+///
+/// ```lua
+/// for _,engine in ipairs(user_engines:list()) do print(engine) end
+/// ```
+pub struct ListUserEngines;
+
+impl Message<ListUserEngines> for UserEngine {
+    type Reply = Vec<EngineInfo>;
+
+    async fn handle(
+        &mut self,
+        _: ListUserEngines,
+        _: Context<'_, Self, Self::Reply>,
+    ) -> Self::Reply {
+        self.schedule
+            .iter()
+            .map(|&index: &usize| self.engines[index].info.clone())
+            .collect()
+    }
+}
+
+/// # Describe a Registered Userscript Scan Engine
+///
+/// A request for the [`UserEngine`] to describe the registered
+/// userscript scan engine called `name`, enabled or not.
+///
+/// ## Reply
+///
+/// Expect a reply of type [`Option<EngineInfo>`], which is [`None`] if
+/// no engine is registered under that name.
+///
+/// ## Example
+///
+/// ```lua
+/// print(user_engines:describe('find_ssh'))
+/// ```
+pub struct DescribeUserEngine(pub String);
+
+impl Message<DescribeUserEngine> for UserEngine {
+    type Reply = Option<EngineInfo>;
+
+    async fn handle(
+        &mut self,
+        msg: DescribeUserEngine,
+        _: Context<'_, Self, Self::Reply>,
+    ) -> Self::Reply {
+        self.position(&msg.0)
+            .map(|index: usize| self.engines[index].info.clone())
     }
 }
 
@@ -70,14 +459,23 @@ impl RegisterUserEngine {
 ///
 /// A request for [`UserEngine`] to scan a [`Vec<u8>`] against all
 /// registered userscript scan engines. The userscript scan engine
-/// service will pass the byte vector to each engine individually,
-/// recording the name of each engine that returned [`true`](bool).
+/// service will pass the byte vector to each engine individually, along
+/// with an [`ItemInfo`] table describing the data item and a table of
+/// the verdicts of engines that already matched, recording the name of
+/// each engine that returned [`true`](bool) or a matching verdict table.
+///
+/// Engines are invoked in a deterministic order, each held to its
+/// resource limits. Disabled engines are
+/// skipped, as are engines whose [`Selector`] does not match the data
+/// item, and engines whose required engines did not all match. An
+/// engine that fails or overruns a limit is raised as a warning and
+/// counted in its statistics, and the scan moves on to the next engine.
 ///
 /// ## Reply
 ///
-/// Expect a reply of type [`UserEngineResult<Vec<String>>`], where each
-/// [`String`] in the vector is the name of a scan engine that returned
-/// a match result of [`true`](bool).
+/// Expect a reply of type [`UserEngineResult<Vec<(String, Verdict)>>`],
+/// with the name and [`Verdict`] of each scan engine that matched, in
+/// invocation order.
 ///
 /// ## Example
 ///
@@ -88,29 +486,73 @@ impl RegisterUserEngine {
 /// ```
 ///
 /// [`topics::user_engines`]: crate::userscript_api::help_system::topics::user_engines
-pub struct ScanBytes(Vec<u8>);
+pub struct ScanBytes {
+    /// The payload to scan.
+    content: Vec<u8>,
+
+    /// Describes the data item the payload came from.
+    info: ItemInfo,
+}
 
 impl Message<ScanBytes> for UserEngine {
-    type Reply = UserEngineResult<Vec<String>>;
+    type Reply = UserEngineResult<Vec<(String, Verdict)>>;
 
-    async fn handle(&mut self, msg: ScanBytes, _: Context<'_, Self, Self::Reply>) -> Self::Reply {
-        // The `_vm_guard` keeps LuaVM alive long enough to call all Lua scan engines.
-        if let Some(_vm_guard) = self.lua_vm.upgrade() {
+    async fn handle(
+        &mut self,
+        mut msg: ScanBytes,
+        _: Context<'_, Self, Self::Reply>,
+    ) -> Self::Reply {
+        // The `vm_guard` keeps LuaVM alive long enough to call all Lua scan engines.
+        if let Some(vm_guard) = self.lua_vm.upgrade() {
             // Stores a list of matching engines for `msg`
-            let mut results: Vec<String> = Vec::with_capacity(1024);
+            let mut results: Vec<(String, Verdict)> = Vec::with_capacity(1024);
+            msg.info.size = msg.content.len();
+
+            // Invoke each selected scan engine in order and get its result.
+            for index in self.schedule.clone() {
+                let engine: &RegisteredEngine = &self.engines[index];
+                let info: &EngineInfo = &engine.info;
+                let matched = |required: &String| {
+                    results
+                        .iter()
+                        .any(|(name, _): &(String, Verdict)| name == required)
+                };
+                if !info.enabled
+                    || !info.requires.iter().all(matched)
+                    || !info.selector.matches(&msg.info, &msg.content)
+                {
+                    continue;
+                }
 
-            // Invoke each scan engine and get its result.
-            for (name, spec) in &self.engines {
                 // Convert the `Vec<u8>` into a Lua bytestring
-                let bytestring = LuaString::wrap(msg.0.as_slice());
+                let bytestring = LuaString::wrap(msg.content.as_slice());
+                let verdicts: HashMap<String, Verdict> = results.iter().cloned().collect();
+                let name: String = info.name.clone();
 
-                // Invoke the scan engine and get the result.
-                let result: UserEngineResult<bool> = spec
-                    .call_async(bytestring)
+                // Invoke the scan engine, timing it, and get the result.
+                let started: Instant = Instant::now();
+                let verdict: mlua::Result<Option<Verdict>> = self
+                    .invoke(engine, (bytestring, msg.info.clone(), verdicts))
                     .await
-                    .map_err(|err: mlua::Error| Error::engine_invocation(name.clone(), err));
-                if result? {
-                    results.push(name.clone());
+                    .and_then(Verdict::from_engine_return);
+                self.record(
+                    &name,
+                    started.elapsed(),
+                    msg.content.len(),
+                    verdict.is_err(),
+                );
+
+                // A failed engine is reported, without failing the others.
+                match verdict {
+                    Ok(Some(verdict)) => results.push((name, verdict)),
+                    Ok(None) => {}
+                    Err(err) => {
+                        let warning: String = Error::engine_invocation(name, err).to_string();
+                        vm_guard
+                            .tell(SendWarning::Complete(warning))
+                            .await
+                            .map_err(|_| Error::NoLuaVm)?;
+                    }
                 }
             }
             Ok(results)
@@ -120,8 +562,81 @@ impl Message<ScanBytes> for UserEngine {
     }
 }
 
+impl ScanBytes {
+    /// Create a new [`ScanBytes`] message for a described data item.
+    #[must_use]
+    pub fn with_info(content: Vec<u8>, info: ItemInfo) -> Self {
+        Self { content, info }
+    }
+}
+
 impl From<Vec<u8>> for ScanBytes {
     fn from(value: Vec<u8>) -> Self {
-        Self(value)
+        Self::with_info(value, ItemInfo::named("<content>"))
+    }
+}
+
+/// # Get the Engine Profile
+///
+/// A request for the [`UserEngine`] to report the performance
+/// statistics of every registered engine, including engines in the
+/// engine pool, for the whole session.
+///
+/// ## Reply
+///
+/// Expect a reply of type [`UserEngineResult<EngineProfile>`], which
+/// fails if an engine pool worker is no longer running.
+///
+/// ## Example
+///
+/// ```lua
+/// print(user_engines:stats())
+/// ```
+pub struct GetEngineProfile;
+
+impl Message<GetEngineProfile> for UserEngine {
+    type Reply = UserEngineResult<EngineProfile>;
+
+    async fn handle(
+        &mut self,
+        _: GetEngineProfile,
+        _: Context<'_, Self, Self::Reply>,
+    ) -> Self::Reply {
+        let mut profile: EngineProfile = self.profile(&self.stats);
+        if let Some(pool) = &self.pool {
+            profile.merge(&pool.profile().await?);
+        }
+        Ok(profile)
+    }
+}
+
+/// # Take the Scan Profile
+///
+/// A request for the [`UserEngine`] to report the performance
+/// statistics of every registered engine, including engines in the
+/// engine pool, since the scan profile was last taken. The statistics
+/// are then reset, so taking the profile before and after a scan
+/// profiles only that scan.
+///
+/// ## Reply
+///
+/// Expect a reply of type [`UserEngineResult<EngineProfile>`], which
+/// fails if an engine pool worker is no longer running.
+pub struct TakeScanProfile;
+
+impl Message<TakeScanProfile> for UserEngine {
+    type Reply = UserEngineResult<EngineProfile>;
+
+    async fn handle(
+        &mut self,
+        _: TakeScanProfile,
+        _: Context<'_, Self, Self::Reply>,
+    ) -> Self::Reply {
+        let scan_stats = std::mem::take(&mut self.scan_stats);
+        let mut profile: EngineProfile = self.profile(&scan_stats);
+        if let Some(pool) = &self.pool {
+            profile.merge(&pool.take_scan_profile().await?);
+        }
+        Ok(profile)
     }
 }
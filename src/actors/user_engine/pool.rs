@@ -0,0 +1,234 @@
+//! # A Pool of Virtual Machines for Parallel Scan Engines
+//!
+//! Every userscript scan engine registered in the main userscript
+//! environment runs in that one Lua virtual machine, so Lua-heavy scans
+//! are single-threaded. An [`EnginePool`] instead runs a set of engines
+//! across several independent [`LuaVM`] workers, each initialized from
+//! the same engine script:
+//!
+//! ```lua
+//! -- engines.lua
+//! user_engines:register('find_eval', function(payload)
+//!     return payload:find('eval(', 1, true) ~= nil
+//! end)
+//! ```
+//!
+//! ```lua
+//! user_engines:start_pool('engines.lua', {workers = 8})
+//! scanmgr:scan()
+//! ```
+//!
+//! As the engines are defined by source rather than as closures, each
+//! worker has its own copy, with its own globals. The [`ScanMgr`] fans
+//! data items out across the workers and merges the results back.
+//!
+//! [`ScanMgr`]: crate::actors::scanmgr::ScanMgr
+
+use crate::actors::{
+    lua_vm::{
+        messages::{ExecChunk, GetUserEngine, SetMemoryLimit, WaitStartup},
+        LuaVM, VmSettings,
+    },
+    user_engine::{
+        engine_info::EngineInfo,
+        error::{Error, UserEngineResult},
+        item_info::ItemInfo,
+        limits::EngineLimits,
+        messages::{
+            GetEngineProfile, ListUserEngines, ScanBytes, SetDefaultLimits, TakeScanProfile,
+        },
+        stats::EngineProfile,
+        verdict::Verdict,
+        UserEngine,
+    },
+};
+use kameo::actor::ActorRef;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// # A Pool of Scan Engine Workers
+///
+/// Each worker is a separate [`LuaVM`] which has run the engine script,
+/// and scans data items against the engines the script registered.
+pub struct EnginePool {
+    /// The worker virtual machines, and their scan engine services.
+    workers: Vec<(ActorRef<LuaVM>, ActorRef<UserEngine>)>,
+
+    /// Index of the worker to send the next data item to.
+    next: AtomicUsize,
+}
+
+impl EnginePool {
+    /// Start `size` workers, each running the engine `script`.
+    ///
+    /// The workers are spawned with `settings`, those of the virtual
+    /// machine starting the pool, and hold each engine to `limits`,
+    /// unless the engine was registered with its own.
+    ///
+    /// ## Errors
+    ///
+    /// Fails with [`Error::PoolStartup`] if the engine script fails to
+    /// run in any worker.
+    pub async fn start(
+        script: &str,
+        size: usize,
+        limits: EngineLimits,
+        settings: VmSettings,
+    ) -> UserEngineResult<Self> {
+        let mut workers: Vec<(ActorRef<LuaVM>, ActorRef<UserEngine>)> = Vec::with_capacity(size);
+        for _ in 0..size.max(1) {
+            let vm: ActorRef<LuaVM> = if settings.unsafe_mode {
+                // SAFETY: The main virtual machine already runs in
+                // unsafe mode, as the user asked for.
+                unsafe { LuaVM::spawn_unsafe(None) }
+            } else {
+                LuaVM::spawn(None)
+            };
+            vm.ask(WaitStartup)
+                .await
+                .map_err(|err| Error::pool_startup(&err))?;
+            if settings.memory_limit > 0 {
+                vm.ask(SetMemoryLimit(settings.memory_limit))
+                    .await
+                    .map_err(|err| Error::pool_startup(&err))?;
+            }
+            let Some(user_engine) = vm
+                .ask(GetUserEngine)
+                .await
+                .map_err(|err| Error::pool_startup(&err))?
+            else {
+                return Err(Error::NoUserEngine);
+            };
+            user_engine.ask(SetDefaultLimits(limits)).await?;
+            vm.ask(ExecChunk::from(script.to_owned()))
+                .await
+                .map_err(|err| Error::pool_startup(&err))?;
+            workers.push((vm, user_engine));
+        }
+        Ok(Self {
+            workers,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// The number of workers in the pool.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Describe the engines the engine script registered.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the first worker is no longer running.
+    pub async fn list(&self) -> UserEngineResult<Vec<EngineInfo>> {
+        let (_, user_engine) = &self.workers[0];
+        user_engine
+            .ask(ListUserEngines)
+            .await
+            .map_err(|_| Error::NoUserEngine)
+    }
+
+    /// Hold every worker's engines to `limits`, unless registered with
+    /// their own.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if a worker is no longer running.
+    pub async fn set_default_limits(&self, limits: EngineLimits) -> UserEngineResult<()> {
+        for (_, user_engine) in &self.workers {
+            user_engine.ask(SetDefaultLimits(limits)).await?;
+        }
+        Ok(())
+    }
+
+    /// Combine every worker's engine statistics for the session.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if a worker is no longer running.
+    pub async fn profile(&self) -> UserEngineResult<EngineProfile> {
+        let mut profile: EngineProfile = EngineProfile::default();
+        for (_, user_engine) in &self.workers {
+            profile.merge(&user_engine.ask(GetEngineProfile).await?);
+        }
+        Ok(profile)
+    }
+
+    /// Get each worker's engine statistics for the session, in worker
+    /// order.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if a worker is no longer running.
+    pub async fn worker_profiles(&self) -> UserEngineResult<Vec<EngineProfile>> {
+        let mut profiles: Vec<EngineProfile> = Vec::with_capacity(self.workers.len());
+        for (_, user_engine) in &self.workers {
+            profiles.push(user_engine.ask(GetEngineProfile).await?);
+        }
+        Ok(profiles)
+    }
+
+    /// Combine and reset every worker's engine statistics since they
+    /// were last taken.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if a worker is no longer running.
+    pub async fn take_scan_profile(&self) -> UserEngineResult<EngineProfile> {
+        let mut profile: EngineProfile = EngineProfile::default();
+        for (_, user_engine) in &self.workers {
+            profile.merge(&user_engine.ask(TakeScanProfile).await?);
+        }
+        Ok(profile)
+    }
+
+    /// Scan a data item against the pool's engines, on the next worker.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if an engine fails, or the worker is no longer running.
+    pub async fn scan(
+        &self,
+        content: Vec<u8>,
+        info: ItemInfo,
+    ) -> UserEngineResult<Vec<(String, Verdict)>> {
+        let index: usize = self.next.fetch_add(1, Ordering::Relaxed) % self.workers.len();
+        let (_, user_engine) = &self.workers[index];
+        Ok(user_engine.ask(ScanBytes::with_info(content, info)).await?)
+    }
+}
+
+/// Scan a data item against the engines registered with `user_engine`,
+/// then against the engines in `pool`, if any.
+///
+/// Results from the local engines come first, followed by the pooled
+/// engines' results, each in invocation order.
+///
+/// ## Errors
+///
+/// Fails if an engine fails, or a scan engine service is no longer
+/// running.
+pub async fn scan_with_pool(
+    user_engine: &ActorRef<UserEngine>,
+    pool: Option<&EnginePool>,
+    content: Vec<u8>,
+    info: ItemInfo,
+) -> UserEngineResult<Vec<(String, Verdict)>> {
+    let Some(pool) = pool else {
+        return Ok(user_engine.ask(ScanBytes::with_info(content, info)).await?);
+    };
+    let mut results: Vec<(String, Verdict)> = user_engine
+        .ask(ScanBytes::with_info(content.clone(), info.clone()))
+        .await?;
+    results.extend(pool.scan(content, info).await?);
+    Ok(results)
+}
+
+impl Drop for EnginePool {
+    fn drop(&mut self) {
+        for (vm, _) in &self.workers {
+            vm.kill();
+        }
+    }
+}
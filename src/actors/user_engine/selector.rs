@@ -0,0 +1,285 @@
+//! # Declarative Data Item Filters for Userscript Scan Engines
+//!
+//! A userscript scan engine may be registered with a [`Selector`], which
+//! restricts the data items it is called on. Items that do not match the
+//! selector are skipped before their payload is handed to Lua:
+//!
+//! ```lua
+//! user_engines:register('ps_obf', fn, {
+//!     ext = {'ps1', 'psm1'},
+//!     max_size = '5MB',
+//!     path = '**/Downloads/**',
+//! })
+//! ```
+//!
+//! Every filter is optional, and an item must pass all of the filters
+//! given to be scanned by the engine.
+
+use crate::{
+    actors::user_engine::{item_info::ItemInfo, limits::lua_size},
+    userscript_api::include::{LuaError, LuaTable, LuaValue},
+};
+use globset::{GlobBuilder, GlobMatcher};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Restricts the data items a userscript scan engine is called on.
+#[derive(Debug, Clone, Default)]
+pub struct Selector {
+    /// Lowercase file extensions, without the leading dot.
+    pub extensions: Vec<String>,
+
+    /// Smallest payload size to scan, in bytes.
+    pub min_size: Option<u64>,
+
+    /// Largest payload size to scan, in bytes.
+    pub max_size: Option<u64>,
+
+    /// Glob the data item's path must match.
+    pub path: Option<GlobMatcher>,
+
+    /// File types, detected from the payload's leading bytes.
+    pub magic: Vec<Magic>,
+}
+
+/// File types recognized by their leading "magic" bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Magic {
+    /// Windows PE executable or DOS MZ executable.
+    Pe,
+
+    /// ELF executable or shared object.
+    Elf,
+
+    /// Mach-O executable, including universal binaries.
+    MachO,
+
+    /// Script beginning with a `#!` interpreter line.
+    Script,
+
+    /// ZIP archive, including JAR and Office Open XML documents.
+    Zip,
+
+    /// Gzip-compressed data.
+    Gzip,
+
+    /// PDF document.
+    Pdf,
+
+    /// OLE compound file, such as legacy Office documents.
+    Ole,
+
+    /// pcap or pcapng packet capture.
+    Pcap,
+}
+
+impl Magic {
+    /// Every recognized file type.
+    const ALL: [Self; 9] = [
+        Self::Pe,
+        Self::Elf,
+        Self::MachO,
+        Self::Script,
+        Self::Zip,
+        Self::Gzip,
+        Self::Pdf,
+        Self::Ole,
+        Self::Pcap,
+    ];
+
+    /// The name of the file type, as exposed to userscripts.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pe => "pe",
+            Self::Elf => "elf",
+            Self::MachO => "macho",
+            Self::Script => "script",
+            Self::Zip => "zip",
+            Self::Gzip => "gzip",
+            Self::Pdf => "pdf",
+            Self::Ole => "ole",
+            Self::Pcap => "pcap",
+        }
+    }
+
+    /// Whether `content` begins with this file type's magic bytes.
+    #[must_use]
+    pub fn matches(self, content: &[u8]) -> bool {
+        let prefixes: &[&[u8]] = match self {
+            Self::Pe => &[b"MZ"],
+            Self::Elf => &[b"\x7fELF"],
+            Self::MachO => &[
+                b"\xfe\xed\xfa\xce",
+                b"\xfe\xed\xfa\xcf",
+                b"\xce\xfa\xed\xfe",
+                b"\xcf\xfa\xed\xfe",
+                b"\xca\xfe\xba\xbe",
+            ],
+            Self::Script => &[b"#!"],
+            Self::Zip => &[b"PK\x03\x04", b"PK\x05\x06"],
+            Self::Gzip => &[b"\x1f\x8b"],
+            Self::Pdf => &[b"%PDF-"],
+            Self::Ole => &[b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1"],
+            Self::Pcap => &[
+                b"\xd4\xc3\xb2\xa1",
+                b"\xa1\xb2\xc3\xd4",
+                b"\x4d\x3c\xb2\xa1",
+                b"\xa1\xb2\x3c\x4d",
+                b"\x0a\x0d\x0d\x0a",
+            ],
+        };
+        prefixes
+            .iter()
+            .any(|prefix: &&[u8]| content.starts_with(prefix))
+    }
+}
+
+impl FromStr for Magic {
+    type Err = LuaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|magic: &Self| magic.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL
+                    .iter()
+                    .map(|magic: &Self| magic.as_str())
+                    .collect();
+                LuaError::runtime(format!(
+                    "unknown magic type `{s}`; expected one of {}",
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+impl Selector {
+    /// Parse the filters from a `user_engines:register()` options table.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if a filter has the wrong type, a size cannot be parsed, the
+    /// path glob is invalid, or a magic type is not recognized.
+    pub fn from_opts(opts: &LuaTable) -> Result<Self, LuaError> {
+        let extensions: Vec<String> = string_or_list(opts.get("ext")?, "ext")?
+            .into_iter()
+            .map(|ext: String| ext.trim_start_matches('.').to_lowercase())
+            .collect();
+        let path: Option<GlobMatcher> = opts
+            .get::<Option<String>>("path")?
+            .map(|glob: String| {
+                GlobBuilder::new(&glob)
+                    .literal_separator(true)
+                    .build()
+                    .map(|glob: globset::Glob| glob.compile_matcher())
+                    .map_err(|err: globset::Error| {
+                        LuaError::runtime(format!("invalid path glob `{glob}`: {err}"))
+                    })
+            })
+            .transpose()?;
+        let magic: Vec<Magic> = string_or_list(opts.get("magic")?, "magic")?
+            .iter()
+            .map(|magic: &String| magic.parse::<Magic>())
+            .collect::<Result<Vec<Magic>, LuaError>>()?;
+        Ok(Self {
+            extensions,
+            min_size: lua_size(opts.get("min_size")?, "min_size")?,
+            max_size: lua_size(opts.get("max_size")?, "max_size")?,
+            path,
+            magic,
+        })
+    }
+
+    /// Whether the selector restricts which items are scanned.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.extensions.is_empty()
+            && self.min_size.is_none()
+            && self.max_size.is_none()
+            && self.path.is_none()
+            && self.magic.is_empty()
+    }
+
+    /// Whether a data item passes every filter of the selector.
+    ///
+    /// Extensions are taken from the item's path, falling back to its
+    /// name. Items without a path never match a path glob.
+    #[must_use]
+    pub fn matches(&self, info: &ItemInfo, content: &[u8]) -> bool {
+        let size: u64 = content.len() as u64;
+        if self.min_size.is_some_and(|min: u64| size < min)
+            || self.max_size.is_some_and(|max: u64| size > max)
+        {
+            return false;
+        }
+        if !self.extensions.is_empty() {
+            let item_path: &Path = info.path.as_deref().unwrap_or(Path::new(&info.name));
+            let Some(extension) = item_path.extension() else {
+                return false;
+            };
+            let extension: String = extension.to_string_lossy().to_lowercase();
+            if !self.extensions.contains(&extension) {
+                return false;
+            }
+        }
+        if let Some(glob) = &self.path {
+            if !info
+                .path
+                .as_ref()
+                .is_some_and(|item: &PathBuf| glob.is_match(item))
+            {
+                return false;
+            }
+        }
+        self.magic.is_empty()
+            || self
+                .magic
+                .iter()
+                .any(|magic: &Magic| magic.matches(content))
+    }
+}
+
+impl Display for Selector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut filters: Vec<String> = Vec::new();
+        if !self.extensions.is_empty() {
+            filters.push(format!("ext={}", self.extensions.join(",")));
+        }
+        if let Some(min_size) = self.min_size {
+            filters.push(format!("min_size={min_size}"));
+        }
+        if let Some(max_size) = self.max_size {
+            filters.push(format!("max_size={max_size}"));
+        }
+        if let Some(path) = &self.path {
+            filters.push(format!("path={}", path.glob().glob()));
+        }
+        if !self.magic.is_empty() {
+            let names: Vec<&str> = self
+                .magic
+                .iter()
+                .map(|magic: &Magic| magic.as_str())
+                .collect();
+            filters.push(format!("magic={}", names.join(",")));
+        }
+        write!(f, "{}", filters.join(" "))
+    }
+}
+
+/// Parse an option given as either a single string or a list of strings.
+pub(crate) fn string_or_list(value: LuaValue, option: &str) -> Result<Vec<String>, LuaError> {
+    match value {
+        LuaValue::Nil => Ok(Vec::new()),
+        LuaValue::String(value) => Ok(vec![value.to_str()?.to_owned()]),
+        LuaValue::Table(values) => values.sequence_values::<String>().collect(),
+        other => Err(LuaError::runtime(format!(
+            "option `{option}` must be a string or list of strings, got {}",
+            other.type_name()
+        ))),
+    }
+}
@@ -0,0 +1,172 @@
+//! # Performance Profiles of Userscript Scan Engines
+//!
+//! The [`UserEngine`] times every engine invocation, so that slow
+//! engines can be found. Statistics are kept for the whole session, and
+//! separately for the most recent scan:
+//!
+//! ```lua
+//! print(user_engines:stats())
+//! ```
+//!
+//! [`UserEngine`]: super::UserEngine
+
+use crate::userscript_api::include::{IntoLua, Lua, LuaResult, LuaTable, LuaValue};
+use std::{fmt::Display, time::Duration};
+
+/// Performance statistics of a single userscript scan engine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EngineStats {
+    /// Number of times the engine was invoked.
+    pub invocations: u64,
+
+    /// Time spent in all invocations of the engine.
+    pub total: Duration,
+
+    /// Time spent in the engine's slowest invocation.
+    pub max: Duration,
+
+    /// Payload bytes passed to the engine across all invocations.
+    pub bytes: u64,
+
+    /// Number of invocations that failed or overran a limit.
+    pub failures: u64,
+}
+
+impl EngineStats {
+    /// Record an invocation taking `elapsed` on a `bytes` long payload,
+    /// and whether it `failed`.
+    pub fn record(&mut self, elapsed: Duration, bytes: usize, failed: bool) {
+        self.invocations += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+        self.bytes += bytes as u64;
+        self.failures += u64::from(failed);
+    }
+
+    /// Add the invocations recorded in `other`.
+    pub fn merge(&mut self, other: &Self) {
+        self.invocations += other.invocations;
+        self.total += other.total;
+        self.max = self.max.max(other.max);
+        self.bytes += other.bytes;
+        self.failures += other.failures;
+    }
+
+    /// Mean time spent per invocation, or zero if never invoked.
+    #[must_use]
+    pub fn mean(&self) -> Duration {
+        let invocations: u32 = u32::try_from(self.invocations).unwrap_or(u32::MAX);
+        self.total.checked_div(invocations).unwrap_or_default()
+    }
+}
+
+/// Performance statistics of a set of engines, by engine name.
+///
+/// Engines are listed in invocation order, with engines run by an
+/// engine pool after those registered locally.
+#[derive(Debug, Clone, Default)]
+pub struct EngineProfile {
+    /// Statistics of each engine, by name.
+    pub engines: Vec<(String, EngineStats)>,
+}
+
+impl EngineProfile {
+    /// Add the statistics in `other`, combining engines by name.
+    pub fn merge(&mut self, other: &Self) {
+        for (name, stats) in &other.engines {
+            match self
+                .engines
+                .iter_mut()
+                .find(|(existing, _): &&mut (String, EngineStats)| existing == name)
+            {
+                Some((_, existing)) => existing.merge(stats),
+                None => self.engines.push((name.clone(), *stats)),
+            }
+        }
+    }
+
+    /// Statistics of the named engine, if it is in the profile.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&EngineStats> {
+        self.engines
+            .iter()
+            .find(|(engine, _): &&(String, EngineStats)| engine == name)
+            .map(|(_, stats): &(String, EngineStats)| stats)
+    }
+
+    /// Time spent in all invocations of all engines.
+    #[must_use]
+    pub fn total(&self) -> Duration {
+        self.engines
+            .iter()
+            .map(|(_, stats): &(String, EngineStats)| stats.total)
+            .sum()
+    }
+}
+
+/// Format a duration in milliseconds, for profile tables.
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl Display for EngineProfile {
+    /// Formats the profile as a table, slowest engines first.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut engines: Vec<&(String, EngineStats)> = self.engines.iter().collect();
+        engines.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.total));
+        let width: usize = engines
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or_default()
+            .max("Engine".len());
+        write!(
+            f,
+            "{:<width$}  {:>8}  {:>12}  {:>10}  {:>10}  {:>12}  {:>8}",
+            "Engine", "Calls", "Total (ms)", "Mean (ms)", "Max (ms)", "Bytes", "Failed"
+        )?;
+        for (name, stats) in engines {
+            write!(
+                f,
+                "\n{:<width$}  {:>8}  {:>12.3}  {:>10.3}  {:>10.3}  {:>12}  {:>8}",
+                name,
+                stats.invocations,
+                millis(stats.total),
+                millis(stats.mean()),
+                millis(stats.max),
+                stats.bytes,
+                stats.failures,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl IntoLua for EngineProfile {
+    /// Converts the profile into an array of engine statistics, which
+    /// prints as a table in the REPL.
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let engines: LuaTable = lua.create_table_with_capacity(self.engines.len(), 0)?;
+        for (name, stats) in &self.engines {
+            let engine: LuaTable = lua.create_table()?;
+            engine.set("engine", name.as_str())?;
+            engine.set("invocations", stats.invocations)?;
+            engine.set("total_ms", millis(stats.total))?;
+            engine.set("mean_ms", millis(stats.mean()))?;
+            engine.set("max_ms", millis(stats.max))?;
+            engine.set("bytes", stats.bytes)?;
+            engine.set("failures", stats.failures)?;
+            engines.push(engine)?;
+        }
+
+        // Print the profile as a table through `__tostring`.
+        let summary: String = self.to_string();
+        let metatable: LuaTable = lua.create_table()?;
+        metatable.set(
+            "__tostring",
+            lua.create_function(move |_, _: LuaTable| Ok(summary.clone()))?,
+        )?;
+        engines.set_metatable(Some(metatable));
+        Ok(LuaValue::Table(engines))
+    }
+}
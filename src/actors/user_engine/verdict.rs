@@ -0,0 +1,150 @@
+//! # Rich Verdicts from Userscript Scan Engines
+//!
+//! A userscript scan engine may return a plain boolean, or a verdict
+//! table describing the match in more detail:
+//!
+//! ```lua
+//! return {match=true, score=70, severity='high', reason='...', offsets={12}, tags={'c2'}}
+//! ```
+//!
+//! This module defines the [`Verdict`] type that carries those details
+//! through to scan results, and the parsing of an engine's return value.
+//! Engines requiring other engines are passed their verdicts in the same
+//! table form.
+
+use crate::userscript_api::include::{IntoLua, Lua, LuaError, LuaResult, LuaTable, LuaValue};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Details of a userscript scan engine match.
+///
+/// Every field is optional; an engine returning plain `true` produces
+/// an empty [`Verdict`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Verdict {
+    /// Confidence or risk score assigned by the engine.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+
+    /// How serious the match is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+
+    /// Human-readable explanation of the match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    /// Byte offsets into the payload where the match was found.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub offsets: Vec<u64>,
+
+    /// Free-form labels for the match.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Severity of a userscript scan engine match.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Informational; not a problem in itself.
+    Info,
+
+    /// Low severity.
+    Low,
+
+    /// Medium severity.
+    Medium,
+
+    /// High severity.
+    High,
+
+    /// Critical severity.
+    Critical,
+}
+
+impl Severity {
+    /// The name of the severity, as exposed to userscripts.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = LuaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Self::Info),
+            "low" => Ok(Self::Low),
+            "medium" => Ok(Self::Medium),
+            "high" => Ok(Self::High),
+            "critical" => Ok(Self::Critical),
+            other => Err(LuaError::runtime(format!(
+                "unknown severity `{other}`; expected one of info, low, medium, high, or critical"
+            ))),
+        }
+    }
+}
+
+impl Verdict {
+    /// Parse the return value of a userscript scan engine.
+    ///
+    /// Returns `None` if the engine did not match. A verdict table
+    /// counts as a match unless its `match` field is `false`, and any
+    /// other value counts by Lua truthiness, so `nil` is a non-match.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if a verdict table field has the wrong type.
+    pub fn from_engine_return(value: LuaValue) -> Result<Option<Self>, LuaError> {
+        match value {
+            LuaValue::Nil | LuaValue::Boolean(false) => Ok(None),
+            LuaValue::Table(table) => Self::from_table(&table),
+            _ => Ok(Some(Self::default())),
+        }
+    }
+
+    /// Parse a verdict table.
+    fn from_table(table: &LuaTable) -> Result<Option<Self>, LuaError> {
+        if table.get::<Option<bool>>("match")? == Some(false) {
+            return Ok(None);
+        }
+        let severity: Option<Severity> = table
+            .get::<Option<String>>("severity")?
+            .map(|severity: String| severity.parse::<Severity>())
+            .transpose()?;
+        Ok(Some(Self {
+            score: table.get("score")?,
+            severity,
+            reason: table.get("reason")?,
+            offsets: table
+                .get::<Option<Vec<u64>>>("offsets")?
+                .unwrap_or_default(),
+            tags: table
+                .get::<Option<Vec<String>>>("tags")?
+                .unwrap_or_default(),
+        }))
+    }
+}
+
+impl IntoLua for Verdict {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let verdict: LuaTable = lua.create_table()?;
+        verdict.set("match", true)?;
+        verdict.set("score", self.score)?;
+        verdict.set("severity", self.severity.map(Severity::as_str))?;
+        verdict.set("reason", self.reason)?;
+        verdict.set("offsets", self.offsets)?;
+        verdict.set("tags", self.tags)?;
+        Ok(LuaValue::Table(verdict))
+    }
+}
@@ -1,6 +1,18 @@
 use clap::{Parser, Subcommand};
+use sscan::userscript_api::scanmgr_api::output::ResultFormat;
 use std::path::PathBuf;
 
+/// Parse a scan result format argument, such as `sarif`.
+fn parse_format(format: &str) -> Result<ResultFormat, String> {
+    format.parse().map_err(|err: mlua::Error| err.to_string())
+}
+
+/// Parse a size argument, such as `512MB`.
+fn parse_size(size: &str) -> Result<u64, String> {
+    sscan::actors::user_engine::limits::parse_size(size)
+        .ok_or_else(|| format!("invalid size `{size}`; expected bytes or a size such as 512MB"))
+}
+
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 #[command(propagate_version = true)]
@@ -22,6 +34,29 @@ pub struct Args {
     #[arg(short, long)]
     pub unsafe_mode: bool,
 
+    /// Limit the memory the Lua environment may use, such as `512MB`.
+    ///
+    /// Allocations past the limit fail the userscript or scan engine
+    /// making them, rather than exhausting system memory.
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_memory: Option<u64>,
+
+    /// Limit the Lua instructions each scan engine call may run.
+    ///
+    /// Scan engines running past the limit fail with an error, rather
+    /// than hanging the scan. Engines registered with their own
+    /// `max_instructions` are not affected.
+    #[arg(long, value_name = "COUNT")]
+    pub max_instructions: Option<u64>,
+
+    /// Print how long each scan engine took when sscan exits.
+    ///
+    /// The table lists each engine's invocations, total, mean, and
+    /// slowest time, and bytes scanned, slowest engines first. It is
+    /// printed to standard error.
+    #[arg(long)]
+    pub profile: bool,
+
     /// The runtime action to take.
     #[command(subcommand)]
     pub action: Action,
@@ -30,7 +65,27 @@ pub struct Args {
 #[derive(Subcommand, Debug)]
 pub enum Action {
     /// Run sscan with the specified userscript.
+    ///
+    /// Exits with status 1 if any scan matched, so that CI pipelines
+    /// fail on findings. A number returned by <SCRIPT> is used as the
+    /// exit status instead.
     Run {
+        /// Write the results of every scan to <OUTPUT>.
+        ///
+        /// The first scan replaces <OUTPUT>. Later scans in the same run
+        /// append to it in ndjson, csv, ecs, and hec, while json and sarif
+        /// files, which hold a single document, are rewritten with all
+        /// results so far.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Format of <OUTPUT>: json, ndjson, csv, sarif, ecs, or hec.
+        ///
+        /// Defaults to the format named by the extension of <OUTPUT>,
+        /// or else ndjson.
+        #[arg(short, long, requires = "output", value_parser = parse_format)]
+        format: Option<ResultFormat>,
+
         /// Path to the userscript sscan should run.
         script: PathBuf,
 
@@ -38,10 +93,56 @@ pub enum Action {
         ///
         /// All extra arguments are passed to Lua, and userscripts can
         /// access them through the global `arg` array.
+        ///
+        /// If one of the arguments is a lone `-`, standard input is
+        /// also enqueued for scanning before <SCRIPT> runs.
         #[arg(allow_hyphen_values(true), allow_negative_numbers(true))]
         args: Vec<String>,
     },
 
+    /// Compare two sets of saved scan results.
+    ///
+    /// Reports the findings added in <NEW>, removed since <OLD>, and
+    /// unchanged, matching findings on the engine name and the item's
+    /// path, or its name if it has no path. Both files may be JSON or
+    /// NDJSON, as written by `results:write()` or `--output`.
+    Diff {
+        /// Scan results from the earlier scan.
+        old: PathBuf,
+
+        /// Scan results from the later scan.
+        new: PathBuf,
+
+        /// Print the diff as JSON, rather than as a report.
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// List past scans and findings kept by `results:store()`.
+    ///
+    /// Lists the scans in <DATABASE>, or their findings if `--findings`,
+    /// `--scan`, or `--engine` is given.
+    Query {
+        /// Path to the scan result store.
+        database: PathBuf,
+
+        /// List findings, rather than scans.
+        #[arg(long)]
+        findings: bool,
+
+        /// Only list findings of the scan with this ID.
+        #[arg(short, long, value_name = "SCAN_ID")]
+        scan: Option<String>,
+
+        /// Only list findings of this scan engine.
+        #[arg(short, long)]
+        engine: Option<String>,
+
+        /// Print JSON, rather than a table.
+        #[arg(short, long)]
+        json: bool,
+    },
+
     /// Start sscan in interactive mode.
     Interactive {
         /// If specified, runs a userscript before launching the REPL.
@@ -1,21 +1,37 @@
 #![deny(clippy::pedantic)]
 
 pub mod cli;
+pub mod query;
 pub mod repl;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
 use cli::{
-    Action::{Interactive, Run},
+    Action::{Diff, Interactive, Query, Run},
     Args,
 };
 use kameo::actor::ActorRef;
 use sscan::{
-    actors::lua_vm::{
-        messages::{EvalChunk, ExecChunk, WaitStartup},
-        LuaVM,
+    actors::{
+        lua_vm::{
+            messages::{
+                EvalChunk, ExecChunk, GetScanMgr, GetUserEngine, SetMemoryLimit, WaitStartup,
+            },
+            LuaVM,
+        },
+        scanmgr::messages::{GetTotalMatches, SetScanOutput},
+        user_engine::{
+            limits::EngineLimits,
+            messages::{GetEngineProfile, SetDefaultLimits},
+            stats::EngineProfile,
+        },
     },
     userscript_api::include::LuaValue,
+    userscript_api::scanmgr_api::{
+        diff::{load_results, ResultDiff},
+        output::OutputOptions,
+        store::FindingFilter,
+    },
 };
 use std::{path::Path, process::ExitCode};
 
@@ -24,17 +40,42 @@ async fn main() -> Result<ExitCode> {
     // Parse commandline arguments
     let cli_args: Args = Args::parse();
 
-    let (vm, exit_code): (ActorRef<LuaVM>, ExitCode) = match cli_args.action {
-        Run { script, args } => {
-            let vm: ActorRef<LuaVM> = init_vm(cli_args.unsafe_mode, &args).await?;
+    let (vm, exit_code): (ActorRef<LuaVM>, ExitCode) = match &cli_args.action {
+        Run {
+            output,
+            format,
+            script,
+            args,
+        } => {
+            let vm: ActorRef<LuaVM> = init_vm(&cli_args, args).await?;
+
+            // Write scan results to the output, if one was given
+            if let Some(output) = output {
+                let options: OutputOptions = OutputOptions::for_path(output, *format);
+                if let Some(scanmgr) = vm.ask(GetScanMgr).await? {
+                    scanmgr
+                        .ask(SetScanOutput(Some((output.clone(), options))))
+                        .await?;
+                }
+            }
+
+            // A lone `-` argument means "scan whatever is piped to stdin"
+            if args.iter().any(|arg: &String| arg == "-") {
+                vm.ask(ExecChunk::from("queue:add_stdin()")).await?;
+            }
+
             let exec_request: EvalChunk = load_script(script)?.into();
             let return_val: LuaValue = vm.ask(exec_request).await?;
 
+            // A returned status wins, otherwise fail if any scan matched
             #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
             let exit_code: ExitCode = match return_val {
                 LuaValue::Integer(rc) => ExitCode::from(rc as u8),
                 LuaValue::Number(rc) => ExitCode::from(rc as u8),
-                _ => ExitCode::SUCCESS,
+                _ => match vm.ask(GetScanMgr).await? {
+                    Some(scanmgr) if scanmgr.ask(GetTotalMatches).await? > 0 => ExitCode::from(1),
+                    _ => ExitCode::SUCCESS,
+                },
             };
             (vm, exit_code)
         }
@@ -43,16 +84,37 @@ async fn main() -> Result<ExitCode> {
             nosplash,
             args,
         } => {
-            let vm: ActorRef<LuaVM> = init_vm(cli_args.unsafe_mode, &args).await?;
+            let vm: ActorRef<LuaVM> = init_vm(&cli_args, args).await?;
             if let Some(startup_script) = startup_script {
                 let exec_request: ExecChunk = load_script(startup_script)?.into();
                 vm.ask(exec_request).await?;
             }
-            repl::invoke(&vm, nosplash).await;
+            repl::invoke(&vm, *nosplash).await;
             (vm, ExitCode::SUCCESS)
         }
+        Diff { old, new, json } => return diff_results(old, new, *json),
+        Query {
+            database,
+            findings,
+            scan,
+            engine,
+            json,
+        } => {
+            let filter: FindingFilter = FindingFilter {
+                scan_id: scan.clone(),
+                engine: engine.clone(),
+            };
+            let findings: bool = *findings || scan.is_some() || engine.is_some();
+            query::invoke(database, &filter, findings, *json)?;
+            return Ok(ExitCode::SUCCESS);
+        }
     };
 
+    // Print engine statistics before the engines shut down
+    if cli_args.profile {
+        print_profile(&vm).await?;
+    }
+
     // Shut down all services
     vm.stop_gracefully().await?;
     vm.wait_for_stop().await;
@@ -60,17 +122,55 @@ async fn main() -> Result<ExitCode> {
 }
 
 /// Initialize the Lua virtual machine.
-async fn init_vm(unsafe_mode: bool, args: &[String]) -> Result<ActorRef<LuaVM>> {
-    let vm: ActorRef<LuaVM> = if unsafe_mode {
+async fn init_vm(cli_args: &Args, args: &[String]) -> Result<ActorRef<LuaVM>> {
+    let vm: ActorRef<LuaVM> = if cli_args.unsafe_mode {
         unsafe { LuaVM::spawn_unsafe(Some(args)) }
     } else {
         LuaVM::spawn(Some(args))
     };
     vm.wait_startup().await;
     vm.ask(WaitStartup).await?;
+
+    // Apply resource limits before any userscripts run
+    if let Some(max_memory) = cli_args.max_memory {
+        vm.ask(SetMemoryLimit(usize::try_from(max_memory)?)).await?;
+    }
+    if let Some(max_instructions) = cli_args.max_instructions {
+        if let Some(user_engine) = vm.ask(GetUserEngine).await? {
+            let limits: EngineLimits = EngineLimits {
+                max_instructions: Some(max_instructions),
+                ..EngineLimits::default()
+            };
+            user_engine.ask(SetDefaultLimits(limits)).await?;
+        }
+    }
     Ok(vm)
 }
 
+/// Print the performance statistics of every scan engine.
+async fn print_profile(vm: &ActorRef<LuaVM>) -> Result<()> {
+    if let Some(user_engine) = vm.ask(GetUserEngine).await? {
+        let profile: EngineProfile = user_engine.ask(GetEngineProfile).await?;
+        eprintln!("{profile}");
+    }
+    Ok(())
+}
+
+/// Print the differences between two sets of saved scan results.
+fn diff_results(old: &Path, new: &Path, json: bool) -> Result<ExitCode> {
+    let load = |path: &Path| {
+        load_results(path)
+            .with_context(|| format!("failed to load scan results from `{}`", path.display()))
+    };
+    let diff: ResultDiff = ResultDiff::new(load(old)?, load(new)?);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else {
+        println!("{diff}");
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
 /// Load a userscript from disk into a [`String`].
 fn load_script<P>(path: P) -> Result<String>
 where
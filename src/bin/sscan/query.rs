@@ -0,0 +1,119 @@
+use anyhow::{Context, Result};
+use sscan::{
+    actors::user_engine::verdict::Severity,
+    userscript_api::scanmgr_api::store::{FindingFilter, ResultStore, StoredFinding, StoredScan},
+};
+use std::path::Path;
+
+/// Lists the scans in the store at `database`, or the findings matching
+/// `filter` if `findings` is set.
+///
+/// ## Errors
+///
+/// Fails if there is no store at `database`, or it cannot be read.
+pub fn invoke(database: &Path, filter: &FindingFilter, findings: bool, json: bool) -> Result<()> {
+    // Opening a missing database would create an empty one
+    if !database.is_file() {
+        anyhow::bail!("no scan result store at `{}`", database.display());
+    }
+    let store: ResultStore = ResultStore::open(database)
+        .with_context(|| format!("failed to open `{}`", database.display()))?;
+
+    if findings {
+        let findings: Vec<StoredFinding> = store.findings(filter)?;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&findings)?);
+        } else {
+            print_findings(&findings);
+        }
+    } else {
+        let scans: Vec<StoredScan> = store.scans()?;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&scans)?);
+        } else {
+            print_scans(&scans);
+        }
+    }
+    Ok(())
+}
+
+/// Prints a table of stored scans.
+fn print_scans(scans: &[StoredScan]) {
+    let optional = |count: Option<u64>| count.map(|count: u64| count.to_string());
+    let rows: Vec<[String; 6]> = scans
+        .iter()
+        .map(|scan: &StoredScan| {
+            [
+                scan.scan_id.clone(),
+                scan.stored_at.clone(),
+                scan.host.clone().unwrap_or_default(),
+                optional(scan.items).unwrap_or_default(),
+                optional(scan.failed).unwrap_or_default(),
+                scan.matches.to_string(),
+            ]
+        })
+        .collect();
+    print_table(
+        [
+            "Scan ID",
+            "Stored At (UTC)",
+            "Host",
+            "Items",
+            "Failed",
+            "Matches",
+        ],
+        &rows,
+    );
+}
+
+/// Prints a table of stored findings.
+fn print_findings(findings: &[StoredFinding]) {
+    let rows: Vec<[String; 5]> = findings
+        .iter()
+        .map(|finding: &StoredFinding| {
+            let result = &finding.result;
+            [
+                finding.scan_id.clone(),
+                result.engine.clone(),
+                result.item.path.as_ref().map_or_else(
+                    || result.item.name.clone(),
+                    |path| path.0.display().to_string(),
+                ),
+                result
+                    .verdict
+                    .severity
+                    .map(Severity::as_str)
+                    .unwrap_or_default()
+                    .to_owned(),
+                result
+                    .verdict
+                    .score
+                    .map(|score: f64| score.to_string())
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect();
+    print_table(["Scan ID", "Engine", "Item", "Severity", "Score"], &rows);
+}
+
+/// Prints rows under a header, padding each column to its widest value.
+fn print_table<const N: usize>(headers: [&str; N], rows: &[[String; N]]) {
+    let mut widths: [usize; N] = headers.map(str::len);
+    for row in rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.len());
+        }
+    }
+    let print_row = |values: [&str; N]| {
+        let line: Vec<String> = values
+            .iter()
+            .zip(widths)
+            .map(|(value, width): (&&str, usize)| format!("{value:<width$}"))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+    print_row(headers);
+    for row in rows {
+        print_row(row.each_ref().map(String::as_str));
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use kameo::actor::ActorRef;
-use mlua::{ObjectLike, Value};
+use mlua::{ObjectLike, Table, Value};
 use sscan::actors::lua_vm::{messages::EvalChunk, LuaVM};
 use std::{
     backtrace::BacktraceStatus::Captured,
@@ -71,6 +71,11 @@ fn print_result(value: Value) {
         Value::Integer(i) => println!("{i}"),
         Value::Number(n) => println!("{n}"),
         Value::String(s) => println!("{}", s.to_string_lossy()),
+        Value::Table(t) if has_tostring(&t) => println!(
+            "{}",
+            t.to_string()
+                .unwrap_or(format!("<table@0x{:x}>", t.to_pointer() as usize))
+        ),
         Value::Table(t) => println!("<table@0x{:x}>", t.to_pointer() as usize),
         Value::Thread(t) => println!("<coroutine@0x{:x}>", t.to_pointer() as usize),
         Value::Function(f) => println!("<function@0x{:x}>", f.to_pointer() as usize),
@@ -85,6 +90,13 @@ fn print_result(value: Value) {
     }
 }
 
+/// Check if a table has a `__tostring` metamethod, like engine lists.
+fn has_tostring(table: &Table) -> bool {
+    table
+        .metatable()
+        .is_some_and(|metatable: Table| metatable.contains_key("__tostring").unwrap_or(false))
+}
+
 /// Evaluate the Lua expression and return a result.
 async fn evaluate(vm: &ActorRef<LuaVM>, chunk: &str) -> Result<Value> {
     let eval_request: EvalChunk = chunk.into();
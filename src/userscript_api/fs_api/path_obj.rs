@@ -17,11 +17,11 @@ use crate::userscript_api::{
         LuaUserDataRef, LuaValue,
     },
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{path::PathBuf, time::UNIX_EPOCH};
 
 /// Represents a Directory Entry
-#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct PathObj(pub PathBuf);
 
 impl LuaUserData for PathObj {
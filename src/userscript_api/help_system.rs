@@ -34,10 +34,12 @@ use std::collections::HashMap;
 topics! {
     use HelpTopic about for "Build, version, and license information.";
     use HelpTopic fs for "Filesystem and directory handling methods.";
+    use HelpTopic net for "List network sockets and probe TCP services.";
     use HelpTopic path for "Ergonomic file path maniuplation.";
+    use HelpTopic proc for "List and inspect running processes (Linux).";
     use HelpTopic queue for "Queue up files and other data for scanning.";
     use HelpTopic scanmgr for "Start a scan of all queued data items.";
-    use HelpTopic user_engines for "Register custom userscript scan engines.";
+    use HelpTopic user_engines for "Register and manage custom userscript scan engines.";
 }
 
 /// # A help topic for userscript APIs.
@@ -0,0 +1,200 @@
+//! # Network Socket Inspection APIs for Lua
+//!
+//! The [`NetApi`] provides userscript access to the network sockets
+//! open on the system, such as TCP listeners and established UDP
+//! flows. Each socket is returned as a [`ConnectionObj`], along with
+//! the processes holding it open, so userscripts can flag unexpected
+//! listeners or beaconing connections.
+//!
+//! Sockets are parsed from the Linux socket tables under `/proc/net`,
+//! so listing sockets is only functional on Linux.
+//!
+//! The [`NetApi`] can also probe TCP ports, enqueueing any service
+//! banners with the [global scan queue] so that scan engines can match
+//! service fingerprints.
+//!
+//! ## Userscript API
+//!
+//! This is a userscript API. The API's functionality is registered with
+//! the Lua virtual machine, where userscripts can call into it.
+//!
+//! For help, call `help 'net'` from Lua, or see [`topics::net`].
+//!
+//! [`ConnectionObj`]: connection_obj::ConnectionObj
+//! [global scan queue]: crate::actors::queue::Queue
+//! [`topics::net`]: crate::userscript_api::help_system::topics::net
+
+pub mod connection_obj;
+pub mod error;
+pub mod pcap;
+pub mod probe;
+
+use crate::{
+    actors::queue::{data_item::RawDatum, error::Error as QueueError, messages::Enqueue, Queue},
+    userscript_api::{
+        include::{
+            Lua, LuaEither, LuaExternalError, LuaResult, LuaTable, LuaUserData, LuaUserDataMethods,
+        },
+        net_api::{
+            connection_obj::{read_connections, resolve_owners, ConnectionObj, Protocol},
+            error::Error,
+            probe::{probe_port, ProbeResult},
+        },
+        ApiObject,
+    },
+};
+use kameo::actor::WeakActorRef;
+use std::{io::ErrorKind, net::SocketAddr, time::Duration};
+use tokio::task::JoinSet;
+
+/// The most ports probed at once by `net:probe()`.
+const MAX_CONCURRENT_PROBES: usize = 64;
+
+/// # The Network Socket Inspection API
+///
+/// The network APIs expose methods and objects to Lua for enumerating
+/// the sockets open on the system, and for probing TCP services.
+pub struct NetApi(WeakActorRef<Queue>);
+
+impl NetApi {
+    /// Create the API object for [registration] with [`LuaVM`].
+    ///
+    /// [registration]: crate::actors::lua_vm::messages::RegisterUserApi
+    /// [`LuaVM`]: crate::actors::lua_vm::LuaVM
+    #[must_use]
+    pub fn new(queue: WeakActorRef<Queue>) -> Self {
+        Self(queue)
+    }
+}
+
+impl LuaUserData for NetApi {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        // List open sockets, optionally for a single protocol.
+        //
+        // ## Return Value
+        // Vec<ConnectionObj> - Snapshots of every matching socket.
+        //
+        // ## Errors
+        // - An unknown protocol name was given.
+        // - A socket table under /proc/net is unreadable or malformed.
+        //   When listing every protocol, missing tables (such as tcp6
+        //   with IPv6 disabled) are skipped instead.
+        methods.add_async_method("connections", |_, _, protocol: Option<String>| async move {
+            let requested: bool = protocol.is_some();
+            let protocols: Vec<Protocol> = match protocol {
+                Some(protocol) => vec![protocol.parse::<Protocol>()?],
+                None => Protocol::ALL.to_vec(),
+            };
+
+            // Parse each socket table, then find each socket's owners.
+            let mut connections: Vec<ConnectionObj> = Vec::with_capacity(1024);
+            for protocol in protocols {
+                match read_connections(protocol) {
+                    Ok(table) => connections.extend(table),
+                    Err(Error::ReadError { source, .. })
+                        if !requested && source.kind() == ErrorKind::NotFound => {}
+                    Err(err) => return Err(err.into_lua_err()),
+                }
+            }
+            resolve_owners(&mut connections);
+            Ok(connections)
+        });
+
+        // Probe TCP ports on a host, enqueueing any captured banners.
+        //
+        // ## Return Value
+        // table - Array of tables describing each probed port.
+        //
+        // ## Errors
+        // - The host could not be resolved.
+        // - Unable to communicate with the global queue.
+        methods.add_async_method(
+            "probe",
+            |lua: Lua,
+             this,
+             (host, ports, opts): (String, LuaEither<u16, Vec<u16>>, Option<LuaTable>)| async move {
+                let Some(queue) = this.0.upgrade() else {
+                    return Err(QueueError::NoGlobalQueue.into_lua_err());
+                };
+                let ports: Vec<u16> = match ports {
+                    LuaEither::Left(port) => vec![port],
+                    LuaEither::Right(ports) => ports,
+                };
+                let (timeout_ms, banner_bytes): (Option<u64>, Option<usize>) = match opts {
+                    Some(opts) => (opts.get("timeout_ms")?, opts.get("banner_bytes")?),
+                    None => (None, None),
+                };
+                let timeout: Duration = Duration::from_millis(timeout_ms.unwrap_or(1000).max(1));
+                let banner_bytes: usize = banner_bytes.unwrap_or(0);
+
+                // Probe the ports concurrently, a bounded number at a time
+                let address: SocketAddr = resolve_host(&host).await?;
+                let mut probes: JoinSet<(usize, ProbeResult)> = JoinSet::new();
+                let mut results: Vec<(usize, ProbeResult)> = Vec::with_capacity(ports.len());
+                for (index, port) in ports.into_iter().enumerate() {
+                    while probes.len() >= MAX_CONCURRENT_PROBES {
+                        if let Some(result) = probes.join_next().await {
+                            results.push(result.map_err(LuaExternalError::into_lua_err)?);
+                        }
+                    }
+                    let host: String = host.clone();
+                    let address: SocketAddr = SocketAddr::new(address.ip(), port);
+                    probes.spawn(async move {
+                        (index, probe_port(&host, address, timeout, banner_bytes).await)
+                    });
+                }
+                while let Some(result) = probes.join_next().await {
+                    results.push(result.map_err(LuaExternalError::into_lua_err)?);
+                }
+                results.sort_unstable_by_key(|(index, _)| *index);
+
+                let results_table: LuaTable = lua.create_table_with_capacity(results.len(), 0)?;
+                for (_, result) in results {
+                    // Enqueue the banner so engines can fingerprint it.
+                    if let Some(banner) = &result.banner {
+                        let data_item: Box<RawDatum> =
+                            RawDatum::with_metadata(&result.name(), banner.clone(), result.metadata());
+                        if queue.ask(Enqueue::item(data_item)).await.is_err() {
+                            return Err(QueueError::SendError.into_lua_err());
+                        }
+                    }
+                    results_table.push(probe_result_table(&lua, result)?)?;
+                }
+                Ok(results_table)
+            },
+        );
+    }
+}
+
+impl ApiObject for NetApi {
+    fn name(&self) -> &'static str {
+        "net"
+    }
+}
+
+/// Resolve `host` to its first socket address, with port zero.
+async fn resolve_host(host: &str) -> LuaResult<SocketAddr> {
+    let resolve_error = |source: std::io::Error| Error::ResolveError {
+        host: host.to_string(),
+        source,
+    };
+    tokio::net::lookup_host((host, 0))
+        .await
+        .map_err(resolve_error)?
+        .next()
+        .ok_or_else(|| resolve_error(std::io::ErrorKind::NotFound.into()).into())
+}
+
+/// Convert a [`ProbeResult`] into a Lua table.
+fn probe_result_table(lua: &Lua, result: ProbeResult) -> LuaResult<LuaTable> {
+    let result_table: LuaTable = lua.create_table()?;
+    result_table.set("name", result.name())?;
+    result_table.set("host", result.host)?;
+    result_table.set("address", result.address.ip().to_string())?;
+    result_table.set("port", result.address.port())?;
+    result_table.set("open", result.open)?;
+    if let Some(banner) = result.banner {
+        result_table.set("banner", lua.create_string(banner)?)?;
+    }
+    Ok(result_table)
+}
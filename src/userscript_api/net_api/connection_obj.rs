@@ -0,0 +1,366 @@
+//! # A snapshot of a network socket.
+//!
+//! The [`ConnectionObj`] is a Lua userdata type that describes a single
+//! TCP, UDP, or UNIX domain socket, as parsed from the Linux socket
+//! tables under `/proc/net`. Like a [`ProcessObj`], it is a
+//! point-in-time snapshot.
+//!
+//! See [`topics::net`] to learn how to use [`ConnectionObj`].
+//!
+//! [`ProcessObj`]: crate::userscript_api::proc_api::process_obj::ProcessObj
+//! [`topics::net`]: crate::userscript_api::help_system::topics::net
+
+use crate::userscript_api::{
+    include::{LuaUserData, LuaUserDataFields, LuaUserDataMethods, LuaUserDataRef},
+    net_api::error::{Error, NetResult},
+    proc_api::{
+        open_file::{read_fds, OpenFileKind},
+        process_obj::list_pids,
+    },
+};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, Ipv6Addr},
+    path::PathBuf,
+    str::FromStr,
+};
+
+/// UNIX socket flag marking a listening socket (`__SO_ACCEPTCON`).
+const UNIX_ACCEPTCON: u32 = 0x0001_0000;
+
+/// A socket table under `/proc/net`.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// TCP over IPv4.
+    Tcp,
+
+    /// TCP over IPv6.
+    Tcp6,
+
+    /// UDP over IPv4.
+    Udp,
+
+    /// UDP over IPv6.
+    Udp6,
+
+    /// UNIX domain sockets.
+    Unix,
+}
+
+impl Protocol {
+    /// Every supported socket table.
+    pub const ALL: [Protocol; 5] = [Self::Tcp, Self::Tcp6, Self::Udp, Self::Udp6, Self::Unix];
+
+    /// The name of the protocol, as exposed to userscripts.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Tcp => "tcp",
+            Self::Tcp6 => "tcp6",
+            Self::Udp => "udp",
+            Self::Udp6 => "udp6",
+            Self::Unix => "unix",
+        }
+    }
+
+    /// Path to the protocol's socket table.
+    fn table_path(self) -> PathBuf {
+        PathBuf::from(format!("/proc/net/{}", self.as_str()))
+    }
+}
+
+impl FromStr for Protocol {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|protocol: &Protocol| protocol.as_str() == name)
+            .ok_or_else(|| Error::UnknownProtocol(name.to_string()))
+    }
+}
+
+/// Represents a Network Socket
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionObj {
+    /// The socket table this socket was listed in.
+    pub protocol: Protocol,
+
+    /// The socket type: `stream`, `dgram`, or `seqpacket`.
+    pub socket_type: String,
+
+    /// Local IP address, or the bound path for UNIX sockets.
+    pub local_address: Option<String>,
+
+    /// Local port, for TCP and UDP sockets.
+    pub local_port: Option<u16>,
+
+    /// Remote IP address, for TCP and UDP sockets.
+    pub remote_address: Option<String>,
+
+    /// Remote port, for TCP and UDP sockets.
+    pub remote_port: Option<u16>,
+
+    /// The socket state, such as `LISTEN` or `ESTABLISHED`.
+    pub state: String,
+
+    /// The socket inode, as seen in `/proc/<pid>/fd`.
+    pub inode: u64,
+
+    /// The user ID owning the socket, for TCP and UDP sockets.
+    pub uid: Option<u32>,
+
+    /// IDs of the processes holding the socket open, if resolved.
+    pub pids: Vec<u32>,
+}
+
+impl ConnectionObj {
+    /// Parse a line of a TCP or UDP socket table.
+    fn parse_inet(protocol: Protocol, line: &str) -> Option<Self> {
+        // sl local rem st tx:rx tr:tm retrnsmt uid timeout inode ...
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (local_address, local_port) = parse_inet_endpoint(fields.get(1)?)?;
+        let (remote_address, remote_port) = parse_inet_endpoint(fields.get(2)?)?;
+        let state: u8 = u8::from_str_radix(fields.get(3)?, 16).ok()?;
+        let uid: u32 = fields.get(7)?.parse::<u32>().ok()?;
+        let inode: u64 = fields.get(9)?.parse::<u64>().ok()?;
+
+        let socket_type: &str = match protocol {
+            Protocol::Tcp | Protocol::Tcp6 => "stream",
+            _ => "dgram",
+        };
+
+        Some(Self {
+            protocol,
+            socket_type: socket_type.to_string(),
+            local_address: Some(local_address),
+            local_port: Some(local_port),
+            remote_address: Some(remote_address),
+            remote_port: Some(remote_port),
+            state: inet_state_name(state).to_string(),
+            inode,
+            uid: Some(uid),
+            pids: Vec::new(),
+        })
+    }
+
+    /// Parse a line of the UNIX socket table.
+    fn parse_unix(line: &str) -> Option<Self> {
+        // Num RefCount Protocol Flags Type St Inode [Path]
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let flags: u32 = u32::from_str_radix(fields.get(3)?, 16).ok()?;
+        let socket_type: u16 = u16::from_str_radix(fields.get(4)?, 16).ok()?;
+        let state: u8 = u8::from_str_radix(fields.get(5)?, 16).ok()?;
+        let inode: u64 = fields.get(6)?.parse::<u64>().ok()?;
+        let path: Option<String> = fields
+            .get(7..)
+            .filter(|path: &&[&str]| !path.is_empty())
+            .map(|path: &[&str]| path.join(" "));
+
+        let socket_type: &str = match socket_type {
+            1 => "stream",
+            2 => "dgram",
+            5 => "seqpacket",
+            _ => "unknown",
+        };
+        let state: &str = if flags & UNIX_ACCEPTCON != 0 {
+            "LISTEN"
+        } else {
+            match state {
+                1 => "UNCONNECTED",
+                2 => "CONNECTING",
+                3 => "CONNECTED",
+                4 => "DISCONNECTING",
+                _ => "UNKNOWN",
+            }
+        };
+
+        Some(Self {
+            protocol: Protocol::Unix,
+            socket_type: socket_type.to_string(),
+            local_address: path,
+            local_port: None,
+            remote_address: None,
+            remote_port: None,
+            state: state.to_string(),
+            inode,
+            uid: None,
+            pids: Vec::new(),
+        })
+    }
+}
+
+impl LuaUserData for ConnectionObj {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("protocol", |_, this: &ConnectionObj| {
+            Ok(this.protocol.as_str())
+        });
+        fields.add_field_method_get("type", |_, this: &ConnectionObj| {
+            Ok(this.socket_type.clone())
+        });
+        fields.add_field_method_get("local_address", |_, this: &ConnectionObj| {
+            Ok(this.local_address.clone())
+        });
+        fields.add_field_method_get("local_port", |_, this: &ConnectionObj| Ok(this.local_port));
+        fields.add_field_method_get("remote_address", |_, this: &ConnectionObj| {
+            Ok(this.remote_address.clone())
+        });
+        fields.add_field_method_get(
+            "remote_port",
+            |_, this: &ConnectionObj| Ok(this.remote_port),
+        );
+        fields.add_field_method_get("state", |_, this: &ConnectionObj| Ok(this.state.clone()));
+        fields.add_field_method_get("inode", |_, this: &ConnectionObj| Ok(this.inode));
+        fields.add_field_method_get("uid", |_, this: &ConnectionObj| Ok(this.uid));
+        fields.add_field_method_get("pid", |_, this: &ConnectionObj| {
+            Ok(this.pids.first().copied())
+        });
+        fields.add_field_method_get("pids", |_, this: &ConnectionObj| Ok(this.pids.clone()));
+    }
+
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        // Converts the ConnectionObj to a netstat-style summary.
+        methods.add_async_meta_method(
+            "__tostring",
+            |_, this: LuaUserDataRef<ConnectionObj>, ()| async move {
+                let local: String = endpoint_string(this.local_address.as_ref(), this.local_port);
+                let summary: String = if this.protocol == Protocol::Unix {
+                    format!("{} {} {}", this.protocol.as_str(), local, this.state)
+                } else {
+                    let remote: String =
+                        endpoint_string(this.remote_address.as_ref(), this.remote_port);
+                    format!(
+                        "{} {} -> {} {}",
+                        this.protocol.as_str(),
+                        local,
+                        remote,
+                        this.state
+                    )
+                };
+                Ok(summary)
+            },
+        );
+    }
+}
+
+/// Read and parse the socket table for `protocol`.
+///
+/// The owning processes of each socket are not resolved; see
+/// [`resolve_owners()`].
+///
+/// ## Errors
+///
+/// Returns [`Error::ReadError`] if the table is unreadable, or
+/// [`Error::ParseError`] if it is malformed.
+pub fn read_connections(protocol: Protocol) -> NetResult<Vec<ConnectionObj>> {
+    let path: PathBuf = protocol.table_path();
+    let table: String = std::fs::read_to_string(&path).map_err(|source| Error::ReadError {
+        path: path.clone(),
+        source,
+    })?;
+
+    // Skip the header line, then parse each socket.
+    table
+        .lines()
+        .skip(1)
+        .filter(|line: &&str| !line.trim().is_empty())
+        .map(|line: &str| {
+            let connection: Option<ConnectionObj> = match protocol {
+                Protocol::Unix => ConnectionObj::parse_unix(line),
+                _ => ConnectionObj::parse_inet(protocol, line),
+            };
+            connection.ok_or_else(|| Error::ParseError { path: path.clone() })
+        })
+        .collect()
+}
+
+/// Resolve the processes holding each connection's socket open.
+///
+/// Sockets are matched to processes by inode, through the file
+/// descriptors in `/proc/<pid>/fd`. Processes whose descriptors are
+/// inaccessible (such as other users' processes, when not root) are
+/// skipped, so their sockets will have no owning pids.
+pub fn resolve_owners(connections: &mut [ConnectionObj]) {
+    let Ok(pids) = list_pids() else {
+        return;
+    };
+
+    // Map each socket inode to the processes holding it.
+    let mut owners: HashMap<u64, Vec<u32>> = HashMap::with_capacity(connections.len());
+    for pid in pids {
+        let Ok(fds) = read_fds(pid) else {
+            continue;
+        };
+        for file in fds {
+            if let (OpenFileKind::Socket, Some(inode)) = (file.kind, file.inode) {
+                let pids: &mut Vec<u32> = owners.entry(inode).or_default();
+                if !pids.contains(&pid) {
+                    pids.push(pid);
+                }
+            }
+        }
+    }
+
+    for connection in connections {
+        if let Some(pids) = owners.get(&connection.inode) {
+            connection.pids.clone_from(pids);
+        }
+    }
+}
+
+/// Parse a hex-encoded `ADDRESS:PORT` pair from a socket table.
+///
+/// Addresses are printed as 32-bit words in host byte order, while the
+/// port is printed in network byte order.
+fn parse_inet_endpoint(endpoint: &str) -> Option<(String, u16)> {
+    let (address, port) = endpoint.split_once(':')?;
+    let port: u16 = u16::from_str_radix(port, 16).ok()?;
+
+    // Decode each 32-bit word back into its in-memory bytes.
+    let mut bytes: Vec<u8> = Vec::with_capacity(16);
+    for word in address.as_bytes().chunks(8) {
+        let word: &str = std::str::from_utf8(word).ok()?;
+        bytes.extend(u32::from_str_radix(word, 16).ok()?.to_ne_bytes());
+    }
+
+    let address: String = match bytes.len() {
+        4 => Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?).to_string(),
+        16 => Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?).to_string(),
+        _ => return None,
+    };
+    Some((address, port))
+}
+
+/// Get the name of a TCP (or UDP) socket state code.
+///
+/// UDP sockets reuse the TCP codes: `ESTABLISHED` for connected
+/// sockets, and `CLOSE` for unconnected (typically listening) ones.
+fn inet_state_name(state: u8) -> &'static str {
+    match state {
+        0x01 => "ESTABLISHED",
+        0x02 => "SYN_SENT",
+        0x03 => "SYN_RECV",
+        0x04 => "FIN_WAIT1",
+        0x05 => "FIN_WAIT2",
+        0x06 => "TIME_WAIT",
+        0x07 => "CLOSE",
+        0x08 => "CLOSE_WAIT",
+        0x09 => "LAST_ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        0x0C => "NEW_SYN_RECV",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Format an address and optional port as `address:port`.
+fn endpoint_string(address: Option<&String>, port: Option<u16>) -> String {
+    let address: &str = address.map_or("*", String::as_str);
+    match port {
+        Some(port) if address.contains(':') => format!("[{address}]:{port}"),
+        Some(port) => format!("{address}:{port}"),
+        None => address.to_string(),
+    }
+}
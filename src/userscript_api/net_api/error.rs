@@ -0,0 +1,80 @@
+//! # Error type definitions for [`NetApi`]
+//!
+//! This module defines the comprehensive error type for the sscan
+//! network APIs. Any errors returned from the [`NetApi`] or a
+//! [`ConnectionObj`] will be of this type, as will errors reading
+//! packet captures.
+//!
+//! [`NetApi`]: super::NetApi
+//! [`ConnectionObj`]: super::connection_obj::ConnectionObj
+
+use crate::userscript_api::include::{LuaError, LuaExternalError};
+use std::path::{Path, PathBuf};
+use thiserror::Error as ThisError;
+
+/// Type alias for results that may return [`Error`].
+pub type NetResult<T> = Result<T, Error>;
+
+/// Comprehensive error type for [`NetApi`]
+///
+/// [`NetApi`]: super::NetApi
+#[derive(ThisError, Debug)]
+pub enum Error {
+    /// An unknown protocol name was requested.
+    #[error("unknown protocol `{0}`; expected one of tcp, tcp6, udp, udp6, or unix")]
+    UnknownProtocol(String),
+
+    /// Unable to read a socket table under `/proc/net`.
+    #[error("failed to read {}: {source}", path.to_string_lossy())]
+    ReadError {
+        /// Path to the socket table.
+        path: PathBuf,
+
+        /// Inner IO error that occurred.
+        source: std::io::Error,
+    },
+
+    /// A socket table under `/proc/net` did not have the expected format.
+    #[error("failed to parse {}", path.to_string_lossy())]
+    ParseError {
+        /// Path to the socket table.
+        path: PathBuf,
+    },
+
+    /// Unable to resolve a hostname to probe.
+    #[error("failed to resolve host `{host}`: {source}")]
+    ResolveError {
+        /// The hostname that could not be resolved.
+        host: String,
+
+        /// Inner IO error that occurred.
+        source: std::io::Error,
+    },
+
+    /// A file is not a valid pcap or pcapng packet capture.
+    #[error("invalid packet capture {}: {reason}", path.to_string_lossy())]
+    InvalidCapture {
+        /// Path to the capture file.
+        path: PathBuf,
+
+        /// Why the capture could not be read.
+        reason: &'static str,
+    },
+}
+
+impl Error {
+    /// Creates a new [`Error::InvalidCapture`].
+    #[must_use]
+    pub fn invalid_capture(path: &Path, reason: &'static str) -> Self {
+        Self::InvalidCapture {
+            path: path.to_path_buf(),
+            reason,
+        }
+    }
+}
+
+impl From<Error> for LuaError {
+    fn from(value: Error) -> Self {
+        value.into_lua_err()
+    }
+}
@@ -0,0 +1,53 @@
+//! # Packet capture ingestion.
+//!
+//! This module reads packet captures in the classic pcap and the newer
+//! pcapng formats, and groups their TCP and UDP payloads into [`Flow`]s
+//! so that network traffic captured elsewhere can be scanned like any
+//! other data item.
+//!
+//! Reading a capture happens in three stages:
+//!
+//! 1. [`capture`] splits the file into timestamped link-layer frames.
+//! 2. [`decode`] peels each frame down to its TCP or UDP payload.
+//! 3. [`flow`] groups payloads by 5-tuple and reassembles TCP streams.
+//!
+//! [`Flow`]: flow::Flow
+
+pub mod capture;
+pub mod decode;
+pub mod flow;
+
+use crate::userscript_api::net_api::{
+    error::{Error, NetResult},
+    pcap::{
+        capture::{read_frames, Frame},
+        flow::{Flow, FlowTable},
+    },
+};
+use std::path::Path;
+
+/// Read the capture at `path`, returning every flow that carried data.
+///
+/// Flows are returned in the order they first appear in the capture.
+/// Frames that are not TCP or UDP over IPv4 or IPv6 are ignored.
+///
+/// ## Errors
+///
+/// Returns [`Error::ReadError`] if the capture cannot be read, or
+/// [`Error::InvalidCapture`] if it is not a pcap or pcapng file.
+pub fn read_flows(path: &Path) -> NetResult<Vec<Flow>> {
+    let bytes: Vec<u8> = std::fs::read(path).map_err(|source| Error::ReadError {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let frames: Vec<Frame> =
+        read_frames(&bytes).map_err(|reason| Error::invalid_capture(path, reason))?;
+
+    let mut flows: FlowTable = FlowTable::default();
+    for frame in frames {
+        if let Some(segment) = decode::decode(frame.linktype, frame.data) {
+            flows.add(frame.timestamp, segment);
+        }
+    }
+    Ok(flows.finish())
+}
@@ -0,0 +1,309 @@
+//! # Capture file parsing.
+//!
+//! Splits pcap and pcapng files into link-layer [`Frame`]s. Both byte
+//! orders are supported, as are microsecond and nanosecond pcap files
+//! and per-interface timestamp resolutions in pcapng.
+//!
+//! Captures are often cut short when the capturing process is killed,
+//! so a truncated final record ends parsing rather than failing it.
+
+/// Classic pcap magic number, microsecond timestamps.
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+
+/// Classic pcap magic number, nanosecond timestamps.
+const PCAP_MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+
+/// pcapng Section Header Block type; also the file's magic number.
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d_0d0a;
+
+/// pcapng byte-order magic, found in each Section Header Block.
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+
+/// pcapng Interface Description Block type.
+const PCAPNG_INTERFACE: u32 = 0x0000_0001;
+
+/// pcapng (obsolete) Packet Block type.
+const PCAPNG_PACKET: u32 = 0x0000_0002;
+
+/// pcapng Simple Packet Block type.
+const PCAPNG_SIMPLE_PACKET: u32 = 0x0000_0003;
+
+/// pcapng Enhanced Packet Block type.
+const PCAPNG_ENHANCED_PACKET: u32 = 0x0000_0006;
+
+/// pcapng `if_tsresol` interface option code.
+const PCAPNG_OPTION_TSRESOL: u16 = 9;
+
+/// A single captured link-layer frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame<'a> {
+    /// Capture time, in seconds since the UNIX epoch, if recorded.
+    pub timestamp: Option<f64>,
+
+    /// The link-layer header type (`LINKTYPE_*`) of the frame.
+    pub linktype: u32,
+
+    /// The captured bytes of the frame.
+    pub data: &'a [u8],
+}
+
+/// Split a pcap or pcapng capture into its frames.
+///
+/// ## Errors
+///
+/// Returns a short reason if the capture format is not recognized.
+pub fn read_frames(bytes: &[u8]) -> Result<Vec<Frame<'_>>, &'static str> {
+    let Some(&magic) = bytes.first_chunk::<4>() else {
+        return Err("file is too short to be a packet capture");
+    };
+    let (le_magic, be_magic): (u32, u32) = (u32::from_le_bytes(magic), u32::from_be_bytes(magic));
+
+    if le_magic == PCAPNG_SECTION_HEADER {
+        Ok(read_pcapng(bytes))
+    } else if le_magic == PCAP_MAGIC_MICROS || le_magic == PCAP_MAGIC_NANOS {
+        read_pcap(Reader::new(bytes, false), le_magic == PCAP_MAGIC_NANOS)
+    } else if be_magic == PCAP_MAGIC_MICROS || be_magic == PCAP_MAGIC_NANOS {
+        read_pcap(Reader::new(bytes, true), be_magic == PCAP_MAGIC_NANOS)
+    } else {
+        Err("unrecognized file format; expected pcap or pcapng")
+    }
+}
+
+/// Split a classic pcap file into its frames.
+fn read_pcap(mut reader: Reader<'_>, nanos: bool) -> Result<Vec<Frame<'_>>, &'static str> {
+    // magic, version, thiszone, sigfigs, snaplen, then network.
+    let linktype: u32 = reader
+        .skip(20)
+        .and_then(|()| reader.u32())
+        .ok_or("truncated pcap file header")?;
+    let units_per_second: f64 = if nanos { 1e9 } else { 1e6 };
+
+    let mut frames: Vec<Frame> = Vec::new();
+    while let Some(frame) = read_pcap_record(&mut reader, linktype, units_per_second) {
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// Read a single pcap record, or `None` at the end of the capture.
+fn read_pcap_record<'a>(
+    reader: &mut Reader<'a>,
+    linktype: u32,
+    units_per_second: f64,
+) -> Option<Frame<'a>> {
+    let seconds: u32 = reader.u32()?;
+    let fraction: u32 = reader.u32()?;
+    let captured_length: u32 = reader.u32()?;
+    let _original_length: u32 = reader.u32()?;
+    let data: &[u8] = reader.take(captured_length as usize)?;
+    Some(Frame {
+        timestamp: Some(f64::from(seconds) + f64::from(fraction) / units_per_second),
+        linktype,
+        data,
+    })
+}
+
+/// An interface described by a pcapng Interface Description Block.
+#[derive(Debug, Clone, Copy)]
+struct Interface {
+    /// The link-layer header type of the interface.
+    linktype: u32,
+
+    /// Timestamp units per second; microseconds unless overridden.
+    units_per_second: u64,
+}
+
+/// Split a pcapng file into its frames.
+fn read_pcapng(bytes: &[u8]) -> Vec<Frame<'_>> {
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut big_endian: bool = false;
+    let mut offset: usize = 0;
+
+    while let Some(header) = bytes.get(offset..offset + 12) {
+        // Each section declares its own byte order.
+        let mut reader: Reader = Reader::new(header, big_endian);
+        let block_type: u32 = reader.u32().expect("header should be 12 bytes");
+        if block_type == PCAPNG_SECTION_HEADER {
+            big_endian = header[8..12] == PCAPNG_BYTE_ORDER_MAGIC.to_be_bytes();
+            reader = Reader::new(&header[4..], big_endian);
+            interfaces.clear();
+        }
+
+        // Stop at a malformed or truncated block.
+        let block_length: usize = reader.u32().expect("header should be 12 bytes") as usize;
+        if block_length < 12 || !block_length.is_multiple_of(4) {
+            break;
+        }
+        let Some(body) = bytes.get(offset + 8..offset + block_length - 4) else {
+            break;
+        };
+        offset += block_length;
+
+        let mut body: Reader = Reader::new(body, big_endian);
+        match block_type {
+            PCAPNG_INTERFACE => {
+                if let Some(interface) = read_interface(&mut body) {
+                    interfaces.push(interface);
+                }
+            }
+            PCAPNG_ENHANCED_PACKET => {
+                if let Some(frame) = read_enhanced_packet(&mut body, &interfaces) {
+                    frames.push(frame);
+                }
+            }
+            PCAPNG_PACKET => {
+                if let Some(frame) = read_obsolete_packet(&mut body, &interfaces) {
+                    frames.push(frame);
+                }
+            }
+            PCAPNG_SIMPLE_PACKET => {
+                if let Some(frame) = read_simple_packet(&mut body, &interfaces) {
+                    frames.push(frame);
+                }
+            }
+            _ => {}
+        }
+    }
+    frames
+}
+
+/// Read the body of an Interface Description Block.
+fn read_interface(body: &mut Reader<'_>) -> Option<Interface> {
+    let linktype: u32 = u32::from(body.u16()?);
+    body.skip(6)?;
+
+    // Look through the options for a timestamp resolution.
+    let mut units_per_second: u64 = 1_000_000;
+    while let (Some(code), Some(length)) = (body.u16(), body.u16()) {
+        let length: usize = usize::from(length);
+        let Some(value) = body.take(length) else {
+            break;
+        };
+        let _padding: Option<()> = body.skip(length.next_multiple_of(4) - length);
+        match (code, value) {
+            (0, _) => break,
+            (PCAPNG_OPTION_TSRESOL, [resolution]) => {
+                // The high bit selects a power of two, else of ten.
+                let exponent: u32 = u32::from(resolution & 0x7f);
+                let base: u64 = if resolution & 0x80 == 0 { 10 } else { 2 };
+                if let Some(units) = base.checked_pow(exponent) {
+                    units_per_second = units;
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(Interface {
+        linktype,
+        units_per_second,
+    })
+}
+
+/// Read the body of an Enhanced Packet Block.
+fn read_enhanced_packet<'a>(body: &mut Reader<'a>, interfaces: &[Interface]) -> Option<Frame<'a>> {
+    let interface: &Interface = interfaces.get(body.u32()? as usize)?;
+    let timestamp: u64 = (u64::from(body.u32()?) << 32) | u64::from(body.u32()?);
+    let captured_length: u32 = body.u32()?;
+    let _original_length: u32 = body.u32()?;
+    Some(Frame {
+        timestamp: Some(interface.timestamp(timestamp)),
+        linktype: interface.linktype,
+        data: body.take(captured_length as usize)?,
+    })
+}
+
+/// Read the body of an obsolete Packet Block.
+fn read_obsolete_packet<'a>(body: &mut Reader<'a>, interfaces: &[Interface]) -> Option<Frame<'a>> {
+    let interface: &Interface = interfaces.get(usize::from(body.u16()?))?;
+    let _drops: u16 = body.u16()?;
+    let timestamp: u64 = (u64::from(body.u32()?) << 32) | u64::from(body.u32()?);
+    let captured_length: u32 = body.u32()?;
+    let _original_length: u32 = body.u32()?;
+    Some(Frame {
+        timestamp: Some(interface.timestamp(timestamp)),
+        linktype: interface.linktype,
+        data: body.take(captured_length as usize)?,
+    })
+}
+
+/// Read the body of a Simple Packet Block, which has no timestamp.
+fn read_simple_packet<'a>(body: &mut Reader<'a>, interfaces: &[Interface]) -> Option<Frame<'a>> {
+    let interface: &Interface = interfaces.first()?;
+    let original_length: usize = body.u32()? as usize;
+    let data: &[u8] = body.rest();
+    Some(Frame {
+        timestamp: None,
+        linktype: interface.linktype,
+        data: &data[..original_length.min(data.len())],
+    })
+}
+
+impl Interface {
+    /// Convert a raw pcapng timestamp into seconds since the epoch.
+    ///
+    /// Whole seconds and the fraction are converted separately, as
+    /// nanosecond timestamps exceed the precision of an [`f64`].
+    #[allow(clippy::cast_precision_loss)]
+    fn timestamp(&self, raw: u64) -> f64 {
+        let seconds: u64 = raw / self.units_per_second;
+        let fraction: u64 = raw % self.units_per_second;
+        seconds as f64 + fraction as f64 / self.units_per_second as f64
+    }
+}
+
+/// A bounds-checked cursor over bytes of a known byte order.
+struct Reader<'a> {
+    /// The bytes remaining to be read.
+    bytes: &'a [u8],
+
+    /// Whether multi-byte integers are big-endian.
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    /// Create a reader over `bytes`.
+    fn new(bytes: &'a [u8], big_endian: bool) -> Self {
+        Self { bytes, big_endian }
+    }
+
+    /// Take the next `count` bytes.
+    fn take(&mut self, count: usize) -> Option<&'a [u8]> {
+        if count > self.bytes.len() {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    /// Skip over the next `count` bytes.
+    fn skip(&mut self, count: usize) -> Option<()> {
+        self.take(count).map(|_| ())
+    }
+
+    /// Take all remaining bytes.
+    fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.bytes)
+    }
+
+    /// Read the next 16-bit integer.
+    fn u16(&mut self) -> Option<u16> {
+        let bytes: [u8; 2] = self.take(2)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    /// Read the next 32-bit integer.
+    fn u32(&mut self) -> Option<u32> {
+        let bytes: [u8; 4] = self.take(4)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+}
@@ -0,0 +1,261 @@
+//! # Packet decoding.
+//!
+//! Peels a captured frame down through its link, network, and transport
+//! layers to a TCP or UDP [`Segment`].
+//!
+//! Supported link layers are Ethernet (with VLAN tags), BSD loopback,
+//! raw IP, and Linux cooked captures (SLL and SLL2). IP fragments are
+//! not reassembled, and are skipped.
+
+use serde::Serialize;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// `LINKTYPE_NULL`: BSD loopback, address family in host byte order.
+const LINKTYPE_NULL: u32 = 0;
+
+/// `LINKTYPE_ETHERNET`: IEEE 802.3 Ethernet.
+const LINKTYPE_ETHERNET: u32 = 1;
+
+/// `LINKTYPE_RAW`: raw IPv4 or IPv6, with no link-layer header.
+const LINKTYPE_RAW: u32 = 101;
+
+/// `LINKTYPE_LOOP`: OpenBSD loopback, address family in network order.
+const LINKTYPE_LOOP: u32 = 108;
+
+/// `LINKTYPE_LINUX_SLL`: Linux cooked capture, version 1.
+const LINKTYPE_LINUX_SLL: u32 = 113;
+
+/// `LINKTYPE_IPV4`: raw IPv4, with no link-layer header.
+const LINKTYPE_IPV4: u32 = 228;
+
+/// `LINKTYPE_IPV6`: raw IPv6, with no link-layer header.
+const LINKTYPE_IPV6: u32 = 229;
+
+/// `LINKTYPE_LINUX_SLL2`: Linux cooked capture, version 2.
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+/// Ethertype of IPv4.
+const ETHERTYPE_IPV4: u16 = 0x0800;
+
+/// Ethertype of IPv6.
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+
+/// Ethertypes of 802.1Q and 802.1ad VLAN tags.
+const ETHERTYPE_VLAN: [u16; 3] = [0x8100, 0x88a8, 0x9100];
+
+/// IP protocol number of TCP.
+const IPPROTO_TCP: u8 = 6;
+
+/// IP protocol number of UDP.
+const IPPROTO_UDP: u8 = 17;
+
+/// TCP header SYN flag.
+const TCP_SYN: u8 = 0x02;
+
+/// A transport-layer protocol carried in a capture.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    /// Transmission Control Protocol.
+    Tcp,
+
+    /// User Datagram Protocol.
+    Udp,
+}
+
+impl Transport {
+    /// The name of the protocol, as exposed to userscripts.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Tcp => "tcp",
+            Self::Udp => "udp",
+        }
+    }
+}
+
+/// A decoded TCP segment or UDP datagram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment<'a> {
+    /// The transport-layer protocol.
+    pub transport: Transport,
+
+    /// The sending address and port.
+    pub source: SocketAddr,
+
+    /// The receiving address and port.
+    pub destination: SocketAddr,
+
+    /// For TCP, the sequence number of the first payload byte.
+    pub sequence: Option<u32>,
+
+    /// The transport-layer payload.
+    pub payload: &'a [u8],
+}
+
+/// Decode a frame of the given link type, if it carries TCP or UDP.
+#[must_use]
+pub fn decode(linktype: u32, frame: &[u8]) -> Option<Segment<'_>> {
+    match linktype {
+        LINKTYPE_ETHERNET => decode_ethernet(frame),
+        LINKTYPE_NULL | LINKTYPE_LOOP => decode_loopback(frame),
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => decode_ip(frame),
+        LINKTYPE_LINUX_SLL => decode_ethertype(u16_at(frame, 14)?, frame.get(16..)?),
+        LINKTYPE_LINUX_SLL2 => decode_ethertype(u16_at(frame, 0)?, frame.get(20..)?),
+        _ => None,
+    }
+}
+
+/// Decode an Ethernet frame, skipping any VLAN tags.
+fn decode_ethernet(frame: &[u8]) -> Option<Segment<'_>> {
+    let mut offset: usize = 12;
+    let mut ethertype: u16 = u16_at(frame, offset)?;
+    while ETHERTYPE_VLAN.contains(&ethertype) {
+        offset += 4;
+        ethertype = u16_at(frame, offset)?;
+    }
+    decode_ethertype(ethertype, frame.get(offset + 2..)?)
+}
+
+/// Decode a loopback frame, whose header is an address family.
+fn decode_loopback(frame: &[u8]) -> Option<Segment<'_>> {
+    // The family is in the capturing host's byte order, and its value
+    // for IPv6 varies by OS; the IP version nibble is simpler to trust.
+    decode_ip(frame.get(4..)?)
+}
+
+/// Decode a packet of the given ethertype.
+fn decode_ethertype(ethertype: u16, packet: &[u8]) -> Option<Segment<'_>> {
+    match ethertype {
+        ETHERTYPE_IPV4 | ETHERTYPE_IPV6 => decode_ip(packet),
+        _ => None,
+    }
+}
+
+/// Decode an IPv4 or IPv6 packet, by its version nibble.
+fn decode_ip(packet: &[u8]) -> Option<Segment<'_>> {
+    match packet.first()? >> 4 {
+        4 => decode_ipv4(packet),
+        6 => decode_ipv6(packet),
+        _ => None,
+    }
+}
+
+/// Decode an IPv4 packet.
+fn decode_ipv4(packet: &[u8]) -> Option<Segment<'_>> {
+    let header_length: usize = usize::from(packet.first()? & 0x0f) * 4;
+    let total_length: usize = usize::from(u16_at(packet, 2)?);
+    if header_length < 20 || total_length < header_length {
+        return None;
+    }
+
+    // Skip fragments: either more fragments follow, or this isn't first.
+    let fragment: u16 = u16_at(packet, 6)?;
+    if fragment & 0x3fff != 0 {
+        return None;
+    }
+
+    let protocol: u8 = *packet.get(9)?;
+    let source: [u8; 4] = packet.get(12..16)?.try_into().ok()?;
+    let destination: [u8; 4] = packet.get(16..20)?.try_into().ok()?;
+    let payload: &[u8] = packet.get(header_length..total_length.min(packet.len()))?;
+    decode_transport(
+        protocol,
+        IpAddr::V4(Ipv4Addr::from(source)),
+        IpAddr::V4(Ipv4Addr::from(destination)),
+        payload,
+    )
+}
+
+/// Decode an IPv6 packet, skipping any extension headers.
+fn decode_ipv6(packet: &[u8]) -> Option<Segment<'_>> {
+    let payload_length: usize = usize::from(u16_at(packet, 4)?);
+    let source: [u8; 16] = packet.get(8..24)?.try_into().ok()?;
+    let destination: [u8; 16] = packet.get(24..40)?.try_into().ok()?;
+    let mut protocol: u8 = *packet.get(6)?;
+    let mut payload: &[u8] = packet.get(40..(40 + payload_length).min(packet.len()))?;
+
+    loop {
+        let header_length: usize = match protocol {
+            // Hop-by-hop, routing, and destination options.
+            0 | 43 | 60 => (usize::from(*payload.get(1)?) + 1) * 8,
+            // Fragment; skip all but unfragmented packets.
+            44 => {
+                if u16_at(payload, 2)? & 0xfff9 != 0 {
+                    return None;
+                }
+                8
+            }
+            // Authentication header.
+            51 => (usize::from(*payload.get(1)?) + 2) * 4,
+            _ => break,
+        };
+        protocol = *payload.first()?;
+        payload = payload.get(header_length..)?;
+    }
+
+    decode_transport(
+        protocol,
+        IpAddr::V6(Ipv6Addr::from(source)),
+        IpAddr::V6(Ipv6Addr::from(destination)),
+        payload,
+    )
+}
+
+/// Decode a TCP segment or UDP datagram.
+fn decode_transport(
+    protocol: u8,
+    source: IpAddr,
+    destination: IpAddr,
+    datagram: &[u8],
+) -> Option<Segment<'_>> {
+    let source_port: u16 = u16_at(datagram, 0)?;
+    let destination_port: u16 = u16_at(datagram, 2)?;
+    let (transport, sequence, payload) = match protocol {
+        IPPROTO_TCP => {
+            let header_length: usize = usize::from(datagram.get(12)? >> 4) * 4;
+            let sequence: u32 = u32::from_be_bytes(datagram.get(4..8)?.try_into().ok()?);
+            // A SYN occupies the first sequence number.
+            let sequence: u32 = if datagram.get(13)? & TCP_SYN == 0 {
+                sequence
+            } else {
+                sequence.wrapping_add(1)
+            };
+            if header_length < 20 {
+                return None;
+            }
+            (
+                Transport::Tcp,
+                Some(sequence),
+                datagram.get(header_length..)?,
+            )
+        }
+        IPPROTO_UDP => {
+            let length: usize = usize::from(u16_at(datagram, 4)?);
+            if length < 8 {
+                return None;
+            }
+            (
+                Transport::Udp,
+                None,
+                datagram.get(8..length.min(datagram.len()))?,
+            )
+        }
+        _ => return None,
+    };
+
+    Some(Segment {
+        transport,
+        source: SocketAddr::new(source, source_port),
+        destination: SocketAddr::new(destination, destination_port),
+        sequence,
+        payload,
+    })
+}
+
+/// Read a big-endian 16-bit integer at `offset`.
+fn u16_at(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
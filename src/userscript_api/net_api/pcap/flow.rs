@@ -0,0 +1,219 @@
+//! # Flow reassembly.
+//!
+//! Groups decoded [`Segment`]s into unidirectional [`Flow`]s by their
+//! 5-tuple, so each side of a TCP connection or UDP exchange becomes
+//! its own data item.
+//!
+//! TCP payloads are reassembled in sequence order, dropping
+//! retransmitted bytes. Missing segments are skipped over, so the
+//! stream after a gap follows directly on from before it. UDP payloads
+//! are concatenated in capture order.
+
+use crate::{
+    actors::queue::data_item::Metadata,
+    userscript_api::net_api::pcap::decode::{Segment, Transport},
+};
+use std::{collections::HashMap, net::SocketAddr};
+
+/// Identifies a unidirectional flow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FlowKey {
+    /// The transport-layer protocol.
+    pub transport: Transport,
+
+    /// The sending address and port.
+    pub source: SocketAddr,
+
+    /// The receiving address and port.
+    pub destination: SocketAddr,
+}
+
+/// The reassembled payload of a unidirectional flow.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flow {
+    /// The flow's 5-tuple.
+    pub key: FlowKey,
+
+    /// Capture time of the first packet, in seconds since the epoch.
+    pub first_timestamp: Option<f64>,
+
+    /// Capture time of the last packet, in seconds since the epoch.
+    pub last_timestamp: Option<f64>,
+
+    /// Number of packets seen in the flow.
+    pub packets: usize,
+
+    /// The reassembled payload.
+    pub payload: Vec<u8>,
+}
+
+impl Flow {
+    /// The data item name for the flow, such as
+    /// `tcp 10.0.0.1:51000 -> 10.0.0.2:80`.
+    #[must_use]
+    pub fn name(&self) -> String {
+        format!(
+            "{} {} -> {}",
+            self.key.transport.as_str(),
+            self.key.source,
+            self.key.destination
+        )
+    }
+
+    /// Metadata describing the flow.
+    #[must_use]
+    pub fn metadata(&self) -> Metadata {
+        let mut metadata: Metadata = Metadata::new();
+        metadata.insert("protocol".into(), self.key.transport.as_str().into());
+        metadata.insert("src_ip".into(), self.key.source.ip().to_string().into());
+        metadata.insert("src_port".into(), self.key.source.port().into());
+        metadata.insert(
+            "dst_ip".into(),
+            self.key.destination.ip().to_string().into(),
+        );
+        metadata.insert("dst_port".into(), self.key.destination.port().into());
+        if let Some(first_timestamp) = self.first_timestamp {
+            metadata.insert("first_timestamp".into(), first_timestamp.into());
+        }
+        if let Some(last_timestamp) = self.last_timestamp {
+            metadata.insert("last_timestamp".into(), last_timestamp.into());
+        }
+        metadata.insert("packets".into(), self.packets.into());
+        metadata
+    }
+}
+
+/// Accumulates segments into flows.
+#[derive(Default)]
+pub struct FlowTable {
+    /// Flows in order of first appearance.
+    flows: Vec<PartialFlow>,
+
+    /// Index into `flows` for each 5-tuple.
+    index: HashMap<FlowKey, usize>,
+}
+
+impl FlowTable {
+    /// Add a captured segment to its flow.
+    pub fn add(&mut self, timestamp: Option<f64>, segment: Segment<'_>) {
+        let key: FlowKey = FlowKey {
+            transport: segment.transport,
+            source: segment.source,
+            destination: segment.destination,
+        };
+        let index: usize = *self.index.entry(key).or_insert_with(|| {
+            self.flows.push(PartialFlow::new(key));
+            self.flows.len() - 1
+        });
+        self.flows[index].add(timestamp, &segment);
+    }
+
+    /// Reassemble every flow, dropping those that carried no payload.
+    #[must_use]
+    pub fn finish(self) -> Vec<Flow> {
+        self.flows
+            .into_iter()
+            .map(PartialFlow::finish)
+            .filter(|flow: &Flow| !flow.payload.is_empty())
+            .collect()
+    }
+}
+
+/// A flow whose segments are still being collected.
+struct PartialFlow {
+    /// The flow's 5-tuple.
+    key: FlowKey,
+
+    /// Earliest packet timestamp seen.
+    first_timestamp: Option<f64>,
+
+    /// Latest packet timestamp seen.
+    last_timestamp: Option<f64>,
+
+    /// Number of packets seen.
+    packets: usize,
+
+    /// TCP sequence number that payload offsets are relative to.
+    base_sequence: Option<u32>,
+
+    /// Payloads, with their offset into the TCP stream if any.
+    payloads: Vec<(i64, Vec<u8>)>,
+}
+
+impl PartialFlow {
+    /// Start a new, empty flow.
+    fn new(key: FlowKey) -> Self {
+        Self {
+            key,
+            first_timestamp: None,
+            last_timestamp: None,
+            packets: 0,
+            base_sequence: None,
+            payloads: Vec::new(),
+        }
+    }
+
+    /// Add a segment to the flow.
+    fn add(&mut self, timestamp: Option<f64>, segment: &Segment<'_>) {
+        self.packets += 1;
+        if let Some(timestamp) = timestamp {
+            self.first_timestamp =
+                Some(self.first_timestamp.map_or(timestamp, |t| t.min(timestamp)));
+            self.last_timestamp = Some(self.last_timestamp.map_or(timestamp, |t| t.max(timestamp)));
+        }
+
+        // Offsets are relative to the first sequence number seen, and
+        // may be negative if segments were captured out of order.
+        let offset: i64 = match segment.sequence {
+            Some(sequence) => {
+                let base: u32 = *self.base_sequence.get_or_insert(sequence);
+                i64::from(sequence.wrapping_sub(base).cast_signed())
+            }
+            None => 0,
+        };
+        if !segment.payload.is_empty() {
+            self.payloads.push((offset, segment.payload.to_vec()));
+        }
+    }
+
+    /// Reassemble the flow's payload.
+    fn finish(mut self) -> Flow {
+        let payload: Vec<u8> = match self.key.transport {
+            Transport::Tcp => reassemble(&mut self.payloads),
+            Transport::Udp => self
+                .payloads
+                .into_iter()
+                .flat_map(|(_, payload)| payload)
+                .collect(),
+        };
+        Flow {
+            key: self.key,
+            first_timestamp: self.first_timestamp,
+            last_timestamp: self.last_timestamp,
+            packets: self.packets,
+            payload,
+        }
+    }
+}
+
+/// Reassemble TCP segments into a stream, by their offsets.
+///
+/// Bytes already covered by an earlier segment are dropped, and gaps
+/// are closed up.
+fn reassemble(segments: &mut [(i64, Vec<u8>)]) -> Vec<u8> {
+    segments.sort_by_key(|(offset, _)| *offset);
+    let mut stream: Vec<u8> = Vec::new();
+    let mut end: i64 = segments.first().map_or(0, |(offset, _)| *offset);
+    for (offset, payload) in segments.iter() {
+        let Ok(length) = i64::try_from(payload.len()) else {
+            continue;
+        };
+        if offset + length <= end {
+            continue;
+        }
+        let overlap: usize = usize::try_from(end - offset).unwrap_or(0);
+        stream.extend_from_slice(&payload[overlap..]);
+        end = offset + length;
+    }
+    stream
+}
@@ -0,0 +1,97 @@
+//! # TCP port probing and banner capture.
+//!
+//! The [`probe_port()`] function performs a TCP connect check against a
+//! single address, and optionally reads the first bytes the service
+//! sends back. Many services (SSH, SMTP, FTP, ...) announce themselves
+//! with a banner on connect, which makes for a cheap fingerprint.
+
+use crate::actors::queue::data_item::Metadata;
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+use tokio::{io::AsyncReadExt, net::TcpStream, time::timeout as with_timeout};
+
+/// The most banner bytes captured from a single port, 64 KiB.
+pub const MAX_BANNER_BYTES: usize = 64 * 1024;
+
+/// The result of probing a single TCP port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeResult {
+    /// The host as given by the userscript.
+    pub host: String,
+
+    /// The resolved address that was probed.
+    pub address: SocketAddr,
+
+    /// Whether the TCP connection was accepted.
+    pub open: bool,
+
+    /// The first bytes sent by the service, if any were captured.
+    pub banner: Option<Vec<u8>>,
+}
+
+impl ProbeResult {
+    /// The data item name for the captured banner, `host:port`.
+    #[must_use]
+    pub fn name(&self) -> String {
+        format!("{}:{}", self.host, self.address.port())
+    }
+
+    /// Metadata describing where the banner was captured from.
+    #[must_use]
+    pub fn metadata(&self) -> Metadata {
+        let mut metadata: Metadata = Metadata::new();
+        metadata.insert("host".into(), self.host.clone().into());
+        metadata.insert("address".into(), self.address.ip().to_string().into());
+        metadata.insert("port".into(), self.address.port().into());
+        metadata
+    }
+}
+
+/// Probe a single TCP `address`, capturing up to `banner_bytes` bytes.
+///
+/// Both the connect and the banner read are bounded by `timeout`. Ports
+/// that refuse the connection or do not answer in time are reported as
+/// closed. If the service sends nothing before the timeout, or
+/// `banner_bytes` is zero, no banner is captured. `banner_bytes` is
+/// capped at [`MAX_BANNER_BYTES`].
+pub async fn probe_port(
+    host: &str,
+    address: SocketAddr,
+    timeout: Duration,
+    banner_bytes: usize,
+) -> ProbeResult {
+    let mut result: ProbeResult = ProbeResult {
+        host: host.to_string(),
+        address,
+        open: false,
+        banner: None,
+    };
+    let Ok(Ok(stream)) = with_timeout(timeout, TcpStream::connect(address)).await else {
+        return result;
+    };
+    result.open = true;
+    if banner_bytes > 0 {
+        let limit: usize = banner_bytes.min(MAX_BANNER_BYTES);
+        let banner: Vec<u8> = read_banner(stream, timeout, limit).await;
+        result.banner = (!banner.is_empty()).then_some(banner);
+    }
+    result
+}
+
+/// Read up to `limit` bytes from `stream` until EOF or `timeout`.
+async fn read_banner(mut stream: TcpStream, timeout: Duration, limit: usize) -> Vec<u8> {
+    let deadline: Instant = Instant::now() + timeout;
+    let mut banner: Vec<u8> = vec![0; limit];
+    let mut filled: usize = 0;
+    while filled < limit {
+        let remaining: Duration = deadline.saturating_duration_since(Instant::now());
+        match with_timeout(remaining, stream.read(&mut banner[filled..])).await {
+            Ok(Ok(0) | Err(_)) | Err(_) => break,
+            Ok(Ok(count)) => filled += count,
+        }
+    }
+    banner.truncate(filled);
+    banner
+}
@@ -0,0 +1,224 @@
+//! # Running Process Inspection APIs for Lua
+//!
+//! The [`ProcApi`] provides userscript access to the list of processes
+//! running on the system. Each process is returned as a [`ProcessObj`],
+//! an ergonomic snapshot of the process's identity, ownership, and
+//! state.
+//!
+//! Process information is parsed from the Linux `/proc` filesystem, so
+//! this API is only functional on Linux.
+//!
+//! ## Userscript API
+//!
+//! This is a userscript API. The API's functionality is registered with
+//! the Lua virtual machine, where userscripts can call into it.
+//!
+//! For help, call `help 'proc'` from Lua, or see [`topics::proc`].
+//!
+//! [`ProcessObj`]: process_obj::ProcessObj
+//! [`topics::proc`]: crate::userscript_api::help_system::topics::proc
+
+pub mod error;
+pub mod memory_region;
+pub mod open_file;
+pub mod process_obj;
+
+use crate::userscript_api::{
+    fs_api::path_obj::PathObj,
+    include::{
+        Lua, LuaEither, LuaResult, LuaTable, LuaUserData, LuaUserDataMethods, LuaUserDataRef,
+    },
+    proc_api::{
+        error::Error,
+        memory_region::{read_maps, MemoryRegion},
+        open_file::{read_fds, OpenFile},
+        process_obj::{
+            boot_time, list_pids, read_cgroups, read_environ, read_namespaces, CgroupEntry,
+            ProcessObj,
+        },
+    },
+    ApiObject,
+};
+
+/// # The Process Inspection API
+///
+/// The process APIs expose methods and objects to Lua for enumerating
+/// and inspecting running processes.
+pub struct ProcApi;
+
+impl LuaUserData for ProcApi {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        // List all running processes.
+        //
+        // Processes that exit or become unreadable while the list is
+        // being built are silently skipped.
+        //
+        // ## Return Value
+        // Vec<ProcessObj> - Snapshots of every visible process.
+        //
+        // ## Errors
+        // - Cannot list the /proc directory.
+        methods.add_async_method("list", |_, _, ()| async move {
+            let pids: Vec<u32> = list_pids()?;
+            let boot_time: Option<u64> = boot_time();
+            let processes: Vec<ProcessObj> = pids
+                .into_iter()
+                .filter_map(|pid: u32| ProcessObj::from_pid(pid, boot_time).ok())
+                .collect();
+            Ok(processes)
+        });
+
+        // Get a single process by its pid.
+        //
+        // ## Return Value
+        // ProcessObj? - The process, or nil if it does not exist.
+        //
+        // ## Errors
+        // - The process's /proc entries are unreadable or malformed.
+        methods.add_async_method("get", |_, _, pid: u32| async move {
+            match ProcessObj::from_pid(pid, boot_time()) {
+                Ok(process) => Ok(Some(process)),
+                Err(Error::NoSuchProcess { .. }) => Ok(None),
+                Err(err) => Err(err.into()),
+            }
+        });
+
+        // Get the environment variables a process was started with.
+        //
+        // ## Return Value
+        // table - Maps each variable name to its value.
+        //
+        // ## Errors
+        // - The process does not exist, or its environment is unreadable.
+        methods.add_async_method(
+            "environ",
+            |lua: Lua, _, pid: LuaEither<u32, LuaUserDataRef<ProcessObj>>| async move {
+                let environ: Vec<(String, String)> = read_environ(resolve_pid(&pid))?;
+                lua.create_table_from(environ)
+            },
+        );
+
+        // List the file descriptors a process holds open.
+        //
+        // ## Return Value
+        // table - Array of tables describing each open descriptor.
+        //
+        // ## Errors
+        // - The process does not exist, or its descriptors are unreadable.
+        methods.add_async_method(
+            "fds",
+            |lua: Lua, _, pid: LuaEither<u32, LuaUserDataRef<ProcessObj>>| async move {
+                let fds: Vec<OpenFile> = read_fds(resolve_pid(&pid))?;
+                let fds_table: LuaTable = lua.create_table_with_capacity(fds.len(), 0)?;
+                for file in fds {
+                    fds_table.push(open_file_table(&lua, file)?)?;
+                }
+                Ok(fds_table)
+            },
+        );
+
+        // List the memory regions mapped into a process.
+        //
+        // ## Return Value
+        // table - Array of tables describing each mapped region.
+        //
+        // ## Errors
+        // - The process does not exist, or its memory map is unreadable.
+        methods.add_async_method(
+            "maps",
+            |lua: Lua, _, pid: LuaEither<u32, LuaUserDataRef<ProcessObj>>| async move {
+                let regions: Vec<MemoryRegion> = read_maps(resolve_pid(&pid))?;
+                let maps_table: LuaTable = lua.create_table_with_capacity(regions.len(), 0)?;
+                for region in regions {
+                    maps_table.push(memory_region_table(&lua, region)?)?;
+                }
+                Ok(maps_table)
+            },
+        );
+
+        // Get the namespaces a process belongs to.
+        //
+        // ## Return Value
+        // table - Maps each namespace type (mnt, net, pid...) to its inode.
+        //
+        // ## Errors
+        // - The process does not exist, or its namespaces are unreadable.
+        methods.add_async_method(
+            "namespaces",
+            |lua: Lua, _, pid: LuaEither<u32, LuaUserDataRef<ProcessObj>>| async move {
+                let namespaces: Vec<(String, u64)> = read_namespaces(resolve_pid(&pid))?;
+                lua.create_table_from(namespaces)
+            },
+        );
+
+        // Get the cgroups a process belongs to.
+        //
+        // ## Return Value
+        // table - Array of tables describing each cgroup membership.
+        //
+        // ## Errors
+        // - The process does not exist, or its cgroups are unreadable.
+        methods.add_async_method(
+            "cgroups",
+            |lua: Lua, _, pid: LuaEither<u32, LuaUserDataRef<ProcessObj>>| async move {
+                let cgroups: Vec<CgroupEntry> = read_cgroups(resolve_pid(&pid))?;
+                let cgroups_table: LuaTable = lua.create_table_with_capacity(cgroups.len(), 0)?;
+                for cgroup in cgroups {
+                    cgroups_table.push(cgroup_entry_table(&lua, cgroup)?)?;
+                }
+                Ok(cgroups_table)
+            },
+        );
+    }
+}
+
+impl ApiObject for ProcApi {
+    fn name(&self) -> &'static str {
+        "proc"
+    }
+}
+
+/// Get the pid from a userscript argument that is a pid or [`ProcessObj`].
+pub(crate) fn resolve_pid(pid: &LuaEither<u32, LuaUserDataRef<ProcessObj>>) -> u32 {
+    match pid {
+        LuaEither::Left(pid) => *pid,
+        LuaEither::Right(process) => process.pid,
+    }
+}
+
+/// Convert an [`OpenFile`] into a Lua table.
+fn open_file_table(lua: &Lua, file: OpenFile) -> LuaResult<LuaTable> {
+    let file_table: LuaTable = lua.create_table()?;
+    file_table.set("fd", file.fd)?;
+    file_table.set("type", file.kind.as_str())?;
+    file_table.set("path", file.path().map(PathObj))?;
+    file_table.set("inode", file.inode)?;
+    file_table.set("target", file.target)?;
+    Ok(file_table)
+}
+
+/// Convert a [`MemoryRegion`] into a Lua table.
+fn memory_region_table(lua: &Lua, region: MemoryRegion) -> LuaResult<LuaTable> {
+    let region_table: LuaTable = lua.create_table()?;
+    region_table.set("start", region.start)?;
+    region_table.set("end", region.end)?;
+    region_table.set("size", region.size())?;
+    region_table.set("perms", region.perms.as_str())?;
+    region_table.set("offset", region.offset)?;
+    region_table.set("inode", region.inode)?;
+    if region.is_file_backed() {
+        let path: Option<PathObj> = region.pathname.as_ref().map(|path| PathObj(path.into()));
+        region_table.set("path", path)?;
+    }
+    region_table.set("pathname", region.pathname)?;
+    Ok(region_table)
+}
+
+/// Convert a [`CgroupEntry`] into a Lua table.
+fn cgroup_entry_table(lua: &Lua, cgroup: CgroupEntry) -> LuaResult<LuaTable> {
+    let cgroup_table: LuaTable = lua.create_table()?;
+    cgroup_table.set("hierarchy", cgroup.hierarchy)?;
+    cgroup_table.set("controllers", cgroup.controllers)?;
+    cgroup_table.set("path", cgroup.path)?;
+    Ok(cgroup_table)
+}
@@ -0,0 +1,59 @@
+//! # Error type definitions for [`ProcApi`]
+//!
+//! This module defines the comprehensive error type for the sscan
+//! process APIs. Any errors returned from the [`ProcApi`] or a
+//! [`ProcessObj`] will be of this type.
+//!
+//! [`ProcApi`]: super::ProcApi
+//! [`ProcessObj`]: super::process_obj::ProcessObj
+
+use crate::userscript_api::include::{LuaError, LuaExternalError};
+use std::path::PathBuf;
+use thiserror::Error as ThisError;
+
+/// Type alias for results that may return [`Error`].
+pub type ProcResult<T> = Result<T, Error>;
+
+/// Comprehensive error type for [`ProcApi`]
+///
+/// [`ProcApi`]: super::ProcApi
+#[derive(ThisError, Debug)]
+pub enum Error {
+    /// The requested process does not exist (or has exited).
+    #[error("no such process with pid {pid}")]
+    NoSuchProcess {
+        /// The process ID that was requested.
+        pid: u32,
+    },
+
+    /// Unable to read a file under `/proc`.
+    #[error("failed to read {}: {source}", path.to_string_lossy())]
+    ReadError {
+        /// Path to the `/proc` file.
+        path: PathBuf,
+
+        /// Inner IO error that occurred.
+        source: std::io::Error,
+    },
+
+    /// A file under `/proc` did not have the expected format.
+    #[error("failed to parse {}", path.to_string_lossy())]
+    ParseError {
+        /// Path to the `/proc` file.
+        path: PathBuf,
+    },
+}
+
+impl Error {
+    /// Create a new [`Error::ParseError`].
+    #[must_use]
+    pub fn parse_error(path: PathBuf) -> Self {
+        Self::ParseError { path }
+    }
+}
+
+impl From<Error> for LuaError {
+    fn from(value: Error) -> Self {
+        value.into_lua_err()
+    }
+}
@@ -0,0 +1,26 @@
+//! # Memory maps of processes.
+//!
+//! [`read_maps`] parses a process's `/proc/<pid>/maps` file into
+//! [`MemoryRegion`]s, which describe each mapped region of its memory.
+
+pub use crate::actors::queue::memory_region::MemoryRegion;
+use crate::userscript_api::proc_api::{
+    error::{Error, ProcResult},
+    process_obj::{proc_path, read_proc_file},
+};
+
+/// Read and parse the memory map of the process with the given `pid`.
+///
+/// ## Errors
+///
+/// Returns [`Error::NoSuchProcess`] if the process does not exist,
+/// [`Error::ReadError`] if the map is unreadable, or
+/// [`Error::ParseError`] if it is malformed.
+pub fn read_maps(pid: u32) -> ProcResult<Vec<MemoryRegion>> {
+    let maps: String = read_proc_file(pid, "maps")?;
+    maps.lines()
+        .map(|line: &str| {
+            MemoryRegion::parse(line).ok_or_else(|| Error::parse_error(proc_path(pid, "maps")))
+        })
+        .collect()
+}
@@ -0,0 +1,130 @@
+//! # A file descriptor held open by a process.
+//!
+//! The [`OpenFile`] type describes one entry of a process's
+//! `/proc/<pid>/fd` directory. Each entry is a symlink whose target
+//! identifies what the descriptor refers to: a path on disk, or a
+//! pseudo-target such as `socket:[12345]` or `pipe:[12345]`.
+
+use crate::userscript_api::proc_api::{
+    error::{Error, ProcResult},
+    process_obj::proc_path,
+};
+use std::{io::ErrorKind, path::PathBuf};
+
+/// Represents an Open File Descriptor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenFile {
+    /// The file descriptor number.
+    pub fd: u32,
+
+    /// What the descriptor refers to.
+    pub kind: OpenFileKind,
+
+    /// The raw symlink target, such as `/etc/passwd` or `pipe:[123]`.
+    pub target: String,
+
+    /// The inode number, for sockets, pipes, and other pseudo-files.
+    pub inode: Option<u64>,
+}
+
+/// The type of object a file descriptor refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenFileKind {
+    /// A path on the filesystem.
+    File,
+
+    /// A socket; its inode can be matched against `/proc/net`.
+    Socket,
+
+    /// An anonymous or named pipe.
+    Pipe,
+
+    /// An anonymous inode, such as an eventfd or epoll instance.
+    AnonInode,
+
+    /// Any other kind of pseudo-file.
+    Other,
+}
+
+impl OpenFileKind {
+    /// The name of the kind, as exposed to userscripts.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Socket => "socket",
+            Self::Pipe => "pipe",
+            Self::AnonInode => "anon_inode",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl OpenFile {
+    /// Returns the path on disk, if this descriptor refers to a file.
+    #[must_use]
+    pub fn path(&self) -> Option<PathBuf> {
+        (self.kind == OpenFileKind::File).then(|| PathBuf::from(&self.target))
+    }
+
+    /// Build an [`OpenFile`] from a descriptor number and link target.
+    fn parse(fd: u32, target: String) -> Self {
+        let kind: OpenFileKind = if target.starts_with('/') {
+            OpenFileKind::File
+        } else if target.starts_with("socket:") {
+            OpenFileKind::Socket
+        } else if target.starts_with("pipe:") {
+            OpenFileKind::Pipe
+        } else if target.starts_with("anon_inode:") {
+            OpenFileKind::AnonInode
+        } else {
+            OpenFileKind::Other
+        };
+
+        // Sockets and pipes look like `socket:[12345]`.
+        let inode: Option<u64> = target
+            .split_once(":[")
+            .and_then(|(_, inode): (&str, &str)| inode.strip_suffix(']'))
+            .and_then(|inode: &str| inode.parse::<u64>().ok());
+
+        Self {
+            fd,
+            kind,
+            target,
+            inode,
+        }
+    }
+}
+
+/// List the file descriptors held open by the process with `pid`.
+///
+/// Descriptors closed while the list is being built are skipped.
+///
+/// ## Errors
+///
+/// Returns [`Error::NoSuchProcess`] if the process does not exist, or
+/// [`Error::ReadError`] if its descriptors are inaccessible.
+pub fn read_fds(pid: u32) -> ProcResult<Vec<OpenFile>> {
+    let fd_dir: PathBuf = proc_path(pid, "fd");
+    let entries = fd_dir.read_dir().map_err(|source: std::io::Error| {
+        if source.kind() == ErrorKind::NotFound {
+            Error::NoSuchProcess { pid }
+        } else {
+            Error::ReadError {
+                path: fd_dir.clone(),
+                source,
+            }
+        }
+    })?;
+
+    let mut fds: Vec<OpenFile> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let fd: u32 = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            let target: PathBuf = std::fs::read_link(entry.path()).ok()?;
+            Some(OpenFile::parse(fd, target.to_string_lossy().to_string()))
+        })
+        .collect();
+    fds.sort_by_key(|file: &OpenFile| file.fd);
+    Ok(fds)
+}
@@ -0,0 +1,398 @@
+//! # A snapshot of a running process.
+//!
+//! The [`ProcessObj`] is a Lua userdata type that describes a single
+//! running process, as parsed from the Linux `/proc` filesystem. It is
+//! a point-in-time snapshot: its fields do not change if the process
+//! changes or exits after the snapshot was taken.
+//!
+//! See [`topics::proc`] to learn how to use [`ProcessObj`].
+//!
+//! [`topics::proc`]: crate::userscript_api::help_system::topics::proc
+
+use crate::userscript_api::{
+    fs_api::path_obj::PathObj,
+    include::{LuaUserData, LuaUserDataFields, LuaUserDataMethods, LuaUserDataRef},
+    proc_api::{
+        error::{Error, ProcResult},
+        memory_region::read_maps,
+    },
+};
+use serde::Serialize;
+use std::{collections::HashSet, io::ErrorKind, path::PathBuf};
+
+/// Clock ticks per second used by `/proc/<pid>/stat` timestamps.
+///
+/// The kernel always reports process times to userspace in `USER_HZ`,
+/// which is fixed at 100 on every architecture Linux supports.
+const USER_HZ: u64 = 100;
+
+/// Represents a Running Process
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProcessObj {
+    /// The process ID.
+    pub pid: u32,
+
+    /// The parent process ID.
+    pub ppid: u32,
+
+    /// The process name (the kernel's `comm` value).
+    pub name: String,
+
+    /// Path to the process executable, if readable.
+    pub exe: Option<PathObj>,
+
+    /// The process command line, split into arguments.
+    pub cmdline: Vec<String>,
+
+    /// The real user ID of the process.
+    pub uid: u32,
+
+    /// The real group ID of the process.
+    pub gid: u32,
+
+    /// Process start time, in seconds since the UNIX epoch.
+    pub start_time: Option<u64>,
+
+    /// The process state, such as `running` or `sleeping`.
+    pub state: String,
+}
+
+impl ProcessObj {
+    /// Take a snapshot of the process with the given `pid`.
+    ///
+    /// `boot_time` is the system boot time from [`boot_time()`], read by
+    /// the caller so that listing many processes reads it only once.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`Error::NoSuchProcess`] if the process does not exist,
+    /// or another [`Error`] if its `/proc` entries cannot be parsed.
+    pub fn from_pid(pid: u32, boot_time: Option<u64>) -> ProcResult<Self> {
+        let status: String = read_proc_file(pid, "status")?;
+        let stat: String = read_proc_file(pid, "stat")?;
+        let status_path = || proc_path(pid, "status");
+
+        // Parse the human-readable status file.
+        let name: String = status_field(&status, "Name")
+            .ok_or_else(|| Error::parse_error(status_path()))?
+            .to_string();
+        let state: String = status_field(&status, "State")
+            .and_then(|state: &str| state.split_once('('))
+            .map(|(_, state): (&str, &str)| state.trim_end_matches(')').to_string())
+            .ok_or_else(|| Error::parse_error(status_path()))?;
+        let parent_pid: u32 = status_field(&status, "PPid")
+            .and_then(|ppid: &str| ppid.parse::<u32>().ok())
+            .ok_or_else(|| Error::parse_error(status_path()))?;
+        let uid: u32 =
+            status_id(&status, "Uid").ok_or_else(|| Error::parse_error(status_path()))?;
+        let gid: u32 =
+            status_id(&status, "Gid").ok_or_else(|| Error::parse_error(status_path()))?;
+
+        // The start time lives in the stat file, in ticks since boot.
+        let start_time: Option<u64> = stat_field(&stat, 22)
+            .and_then(|ticks: &str| ticks.parse::<u64>().ok())
+            .and_then(|ticks: u64| boot_time.map(|btime: u64| btime + ticks / USER_HZ));
+
+        // The command line is a NUL-separated list of arguments.
+        // Kernel threads have no command line, so this may be empty.
+        let cmdline: Vec<String> = std::fs::read(proc_path(pid, "cmdline"))
+            .map(|raw: Vec<u8>| {
+                raw.split(|byte: &u8| *byte == 0)
+                    .filter(|arg: &&[u8]| !arg.is_empty())
+                    .map(|arg: &[u8]| String::from_utf8_lossy(arg).to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // The exe link is unreadable for other users' processes.
+        let exe: Option<PathObj> = std::fs::read_link(proc_path(pid, "exe")).ok().map(PathObj);
+
+        Ok(Self {
+            pid,
+            ppid: parent_pid,
+            name,
+            exe,
+            cmdline,
+            uid,
+            gid,
+            start_time,
+            state,
+        })
+    }
+}
+
+impl LuaUserData for ProcessObj {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("pid", |_, this: &ProcessObj| Ok(this.pid));
+        fields.add_field_method_get("ppid", |_, this: &ProcessObj| Ok(this.ppid));
+        fields.add_field_method_get("name", |_, this: &ProcessObj| Ok(this.name.clone()));
+        fields.add_field_method_get("exe", |_, this: &ProcessObj| Ok(this.exe.clone()));
+        fields.add_field_method_get("cmdline", |_, this: &ProcessObj| Ok(this.cmdline.clone()));
+        fields.add_field_method_get("uid", |_, this: &ProcessObj| Ok(this.uid));
+        fields.add_field_method_get("gid", |_, this: &ProcessObj| Ok(this.gid));
+        fields.add_field_method_get("start_time", |_, this: &ProcessObj| Ok(this.start_time));
+        fields.add_field_method_get("state", |_, this: &ProcessObj| Ok(this.state.clone()));
+    }
+
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        // Returns true if both snapshots describe the same process.
+        methods.add_async_meta_method(
+            "__eq",
+            |_, this: LuaUserDataRef<ProcessObj>, other: LuaUserDataRef<ProcessObj>| async move {
+                Ok(this.pid == other.pid && this.start_time == other.start_time)
+            },
+        );
+
+        // Converts the ProcessObj to a short `pid (name)` string.
+        methods.add_async_meta_method(
+            "__tostring",
+            |_, this: LuaUserDataRef<ProcessObj>, ()| async move {
+                Ok(format!("{} ({})", this.pid, this.name))
+            },
+        );
+    }
+}
+
+/// List the IDs of all processes currently visible in `/proc`.
+///
+/// ## Errors
+///
+/// Returns [`Error::ReadError`] if `/proc` cannot be listed.
+pub fn list_pids() -> ProcResult<Vec<u32>> {
+    let proc_dir: PathBuf = PathBuf::from("/proc");
+    let entries = proc_dir.read_dir().map_err(|source| Error::ReadError {
+        path: proc_dir.clone(),
+        source,
+    })?;
+
+    // Every numeric directory under /proc is a process.
+    let mut pids: Vec<u32> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .collect();
+    pids.sort_unstable();
+    Ok(pids)
+}
+
+/// List the executable and file-backed mappings of a process.
+///
+/// The process executable (`/proc/<pid>/exe`) is listed first, followed
+/// by every distinct file mapped into the process, such as shared
+/// libraries. Files deleted since being mapped are skipped, since they
+/// can no longer be loaded from disk.
+///
+/// Paths are as seen from the process's root directory, which differs
+/// from the host's for processes in other mount namespaces, such as
+/// containers. Read them through `/proc/<pid>/root`.
+///
+/// ## Errors
+///
+/// Returns [`Error::NoSuchProcess`] if the process does not exist, or
+/// another [`Error`] if its memory map is unreadable or malformed.
+pub fn image_paths(pid: u32) -> ProcResult<Vec<PathBuf>> {
+    let mut images: Vec<PathBuf> = Vec::with_capacity(64);
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+
+    // The exe link is unreadable for other users' processes.
+    if let Ok(exe) = std::fs::read_link(proc_path(pid, "exe")) {
+        seen.insert(exe.clone());
+        images.push(exe);
+    }
+
+    // Add every distinct file-backed mapping.
+    for region in read_maps(pid)? {
+        if !region.is_file_backed() {
+            continue;
+        }
+        let Some(pathname) = region.pathname else {
+            continue;
+        };
+        let path: PathBuf = PathBuf::from(pathname);
+        if seen.insert(path.clone()) {
+            images.push(path);
+        }
+    }
+
+    // Deleted files show up with a ` (deleted)` suffix.
+    images.retain(|path: &PathBuf| !path.to_string_lossy().ends_with(" (deleted)"));
+    Ok(images)
+}
+
+/// Read the environment variables of a process.
+///
+/// This is the environment the process was started with; changes the
+/// process makes to its own environment afterwards are not reflected.
+///
+/// ## Errors
+///
+/// Returns [`Error::NoSuchProcess`] if the process does not exist, or
+/// [`Error::ReadError`] if its environment is inaccessible.
+pub fn read_environ(pid: u32) -> ProcResult<Vec<(String, String)>> {
+    let raw: Vec<u8> = read_proc_bytes(pid, "environ")?;
+
+    // The environment is a NUL-separated list of `KEY=VALUE` pairs.
+    let environ: Vec<(String, String)> = raw
+        .split(|byte: &u8| *byte == 0)
+        .filter(|var: &&[u8]| !var.is_empty())
+        .filter_map(|var: &[u8]| {
+            let var: String = String::from_utf8_lossy(var).to_string();
+            let (key, value) = var.split_once('=')?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect();
+    Ok(environ)
+}
+
+/// Read the namespaces of a process, as `(type, inode)` pairs.
+///
+/// Two processes share a namespace if their inodes for that namespace
+/// type are equal.
+///
+/// ## Errors
+///
+/// Returns [`Error::NoSuchProcess`] if the process does not exist, or
+/// [`Error::ReadError`] if its namespaces are inaccessible.
+pub fn read_namespaces(pid: u32) -> ProcResult<Vec<(String, u64)>> {
+    let ns_dir: PathBuf = proc_path(pid, "ns");
+    let entries = ns_dir.read_dir().map_err(|source: std::io::Error| {
+        if source.kind() == ErrorKind::NotFound {
+            Error::NoSuchProcess { pid }
+        } else {
+            Error::ReadError {
+                path: ns_dir.clone(),
+                source,
+            }
+        }
+    })?;
+
+    // Each entry links to a target like `mnt:[4026531841]`.
+    let mut namespaces: Vec<(String, u64)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name: String = entry.file_name().to_str()?.to_string();
+            let target: PathBuf = std::fs::read_link(entry.path()).ok()?;
+            let inode: u64 = target
+                .to_str()?
+                .split_once(":[")?
+                .1
+                .strip_suffix(']')?
+                .parse::<u64>()
+                .ok()?;
+            Some((name, inode))
+        })
+        .collect();
+    namespaces.sort();
+    Ok(namespaces)
+}
+
+/// Membership of a process in one cgroup hierarchy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CgroupEntry {
+    /// The hierarchy ID; always zero for the unified (v2) hierarchy.
+    pub hierarchy: u32,
+
+    /// Controllers bound to the hierarchy; empty for cgroup v2.
+    pub controllers: Vec<String>,
+
+    /// Path of the cgroup within the hierarchy.
+    pub path: String,
+}
+
+/// Read the cgroup memberships of a process.
+///
+/// ## Errors
+///
+/// Returns [`Error::NoSuchProcess`] if the process does not exist,
+/// [`Error::ReadError`] if its cgroups are inaccessible, or
+/// [`Error::ParseError`] if they are malformed.
+pub fn read_cgroups(pid: u32) -> ProcResult<Vec<CgroupEntry>> {
+    let cgroup: String = read_proc_file(pid, "cgroup")?;
+
+    // Each line looks like `hierarchy-ID:controller-list:cgroup-path`.
+    cgroup
+        .lines()
+        .map(|line: &str| {
+            let mut fields = line.splitn(3, ':');
+            let hierarchy: u32 = fields.next()?.parse::<u32>().ok()?;
+            let controllers: Vec<String> = fields
+                .next()?
+                .split(',')
+                .filter(|controller: &&str| !controller.is_empty())
+                .map(ToString::to_string)
+                .collect();
+            let path: String = fields.next()?.to_string();
+            Some(CgroupEntry {
+                hierarchy,
+                controllers,
+                path,
+            })
+        })
+        .collect::<Option<Vec<CgroupEntry>>>()
+        .ok_or_else(|| Error::parse_error(proc_path(pid, "cgroup")))
+}
+
+/// Build the path `/proc/<pid>/<file>`.
+pub(crate) fn proc_path(pid: u32, file: &str) -> PathBuf {
+    PathBuf::from(format!("/proc/{pid}/{file}"))
+}
+
+/// Read `/proc/<pid>/<file>` into a string.
+///
+/// A missing file is reported as [`Error::NoSuchProcess`], as it means
+/// the process has exited or never existed.
+pub(crate) fn read_proc_file(pid: u32, file: &str) -> ProcResult<String> {
+    let raw: Vec<u8> = read_proc_bytes(pid, file)?;
+    Ok(String::from_utf8_lossy(&raw).to_string())
+}
+
+/// Read `/proc/<pid>/<file>` into a byte vector.
+///
+/// A missing file is reported as [`Error::NoSuchProcess`], as it means
+/// the process has exited or never existed.
+pub(crate) fn read_proc_bytes(pid: u32, file: &str) -> ProcResult<Vec<u8>> {
+    let path: PathBuf = proc_path(pid, file);
+    std::fs::read(&path).map_err(|source: std::io::Error| {
+        if source.kind() == ErrorKind::NotFound {
+            Error::NoSuchProcess { pid }
+        } else {
+            Error::ReadError { path, source }
+        }
+    })
+}
+
+/// Get the value of a `Key:\tvalue` line from `/proc/<pid>/status`.
+fn status_field<'a>(status: &'a str, key: &str) -> Option<&'a str> {
+    status.lines().find_map(|line: &str| {
+        let (line_key, value) = line.split_once(':')?;
+        (line_key == key).then_some(value.trim())
+    })
+}
+
+/// Get the real ID from a `Uid:` or `Gid:` line of the status file.
+fn status_id(status: &str, key: &str) -> Option<u32> {
+    status_field(status, key)?
+        .split_whitespace()
+        .next()?
+        .parse::<u32>()
+        .ok()
+}
+
+/// Get the 1-indexed `field` from `/proc/<pid>/stat`.
+///
+/// Field 2, the process name, may itself contain spaces and
+/// parentheses, so fields after it are located from the last `)`.
+fn stat_field(stat: &str, field: usize) -> Option<&str> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(field.checked_sub(3)?)
+}
+
+/// Get the system boot time, in seconds since the UNIX epoch.
+#[must_use]
+pub fn boot_time() -> Option<u64> {
+    let stat: String = std::fs::read_to_string("/proc/stat").ok()?;
+    stat.lines()
+        .find_map(|line: &str| line.strip_prefix("btime "))?
+        .trim()
+        .parse::<u64>()
+        .ok()
+}
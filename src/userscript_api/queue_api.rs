@@ -20,20 +20,26 @@
 
 use crate::{
     actors::queue::{
-        data_item::{FileDatum, RawDatum},
+        data_item::{FileDatum, Metadata, ProcessMemoryDatum, RawDatum, StdinDatum},
         error::Error as QueueError,
         messages::{Dequeue, Enqueue, GetLength},
         Queue,
     },
     userscript_api::{
         fs_api::path_obj::PathObj,
-        include::{LuaEither, LuaUserDataRef},
+        include::{LuaEither, LuaSerdeExt, LuaTable, LuaUserDataRef, LuaValue},
+        net_api::pcap::{flow::Flow, read_flows},
+        proc_api::{
+            memory_region::{read_maps, MemoryRegion},
+            process_obj::{boot_time, image_paths, list_pids, ProcessObj},
+            resolve_pid,
+        },
         ApiObject,
     },
 };
-use kameo::actor::WeakActorRef;
+use kameo::actor::{ActorRef, WeakActorRef};
 use mlua::{ExternalError, Lua, UserData, UserDataRef};
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 /// # Global Scan Queue Userscript API
 ///
@@ -65,6 +71,11 @@ impl UserData for QueueApi {
     fn add_methods<M: mlua::UserDataMethods<Self>>(methods: &mut M) {
         methods.add_async_method("add_raw", queue_add_raw);
         methods.add_async_method("add_file", queue_add_file);
+        methods.add_async_method("add_stdin", queue_add_stdin);
+        methods.add_async_method("add_process", queue_add_process);
+        methods.add_async_method("add_process_images", queue_add_process_images);
+        methods.add_async_method("add_all_process_images", queue_add_all_process_images);
+        methods.add_async_method("add_pcap", queue_add_pcap);
         methods.add_async_method("dequeue", queue_dequeue);
         methods.add_async_method("len", queue_len);
         methods.add_async_meta_method("__len", queue_len);
@@ -117,17 +128,202 @@ async fn queue_add_file(
     }
 }
 
+/// Userscript function `queue:add_stdin(name)`
+async fn queue_add_stdin(
+    _: Lua,
+    this: UserDataRef<QueueApi>,
+    name: Option<String>,
+) -> mlua::Result<()> {
+    if let Some(queue) = this.0.upgrade() {
+        let name: String = name.unwrap_or_else(|| "<stdin>".to_string());
+        let data_item: Box<StdinDatum> = StdinDatum::new(&name);
+        if queue.ask(Enqueue::item(data_item)).await.is_err() {
+            Err(QueueError::SendError.into_lua_err())
+        } else {
+            Ok(())
+        }
+    } else {
+        Err(QueueError::NoGlobalQueue.into_lua_err())
+    }
+}
+
+/// Userscript function `queue:add_process(pid, opts)`
+///
+/// Enqueues one [`ProcessMemoryDatum`] per selected memory region,
+/// returning the number of regions enqueued.
+async fn queue_add_process(
+    _: Lua,
+    this: UserDataRef<QueueApi>,
+    (pid, opts): (LuaEither<u32, LuaUserDataRef<ProcessObj>>, Option<LuaTable>),
+) -> mlua::Result<usize> {
+    let Some(queue) = this.0.upgrade() else {
+        return Err(QueueError::NoGlobalQueue.into_lua_err());
+    };
+    let pid: u32 = resolve_pid(&pid);
+
+    // Select which regions to read; defaults to all readable regions.
+    let regions: Option<String> = match opts {
+        Some(opts) => opts.get("regions")?,
+        None => None,
+    };
+    let selector: fn(&MemoryRegion) -> bool = match regions.as_deref() {
+        None | Some("all") => |_| true,
+        Some("private") => MemoryRegion::is_private,
+        Some("anonymous") => MemoryRegion::is_anonymous,
+        Some(other) => return Err(QueueError::invalid_option("regions", other).into_lua_err()),
+    };
+
+    // Snapshot the process and its memory map.
+    let process: ProcessObj = ProcessObj::from_pid(pid, boot_time())?;
+    let mut count: usize = 0;
+    for region in read_maps(pid)? {
+        if !region.is_readable() || region.is_kernel_special() || !selector(&region) {
+            continue;
+        }
+        let data_item: Box<ProcessMemoryDatum> =
+            ProcessMemoryDatum::new(pid, &process.name, region);
+        if queue.ask(Enqueue::item(data_item)).await.is_err() {
+            return Err(QueueError::SendError.into_lua_err());
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Userscript function `queue:add_process_images(pid)`
+///
+/// Enqueues the executable and mapped files of a single process,
+/// returning the number of files enqueued.
+async fn queue_add_process_images(
+    _: Lua,
+    this: UserDataRef<QueueApi>,
+    pid: LuaEither<u32, LuaUserDataRef<ProcessObj>>,
+) -> mlua::Result<usize> {
+    let Some(queue) = this.0.upgrade() else {
+        return Err(QueueError::NoGlobalQueue.into_lua_err());
+    };
+    let pid: u32 = resolve_pid(&pid);
+
+    // Map each image path to the process using it.
+    let mut images: BTreeMap<(String, PathBuf), Vec<u32>> = BTreeMap::new();
+    for path in image_paths(pid)? {
+        images
+            .entry((mount_namespace(pid), path))
+            .or_default()
+            .push(pid);
+    }
+    enqueue_images(&queue, images).await
+}
+
+/// Userscript function `queue:add_all_process_images()`
+///
+/// Enqueues the executables and mapped files of every process, each
+/// file only once, returning the number of files enqueued.
+async fn queue_add_all_process_images(
+    _: Lua,
+    this: UserDataRef<QueueApi>,
+    (): (),
+) -> mlua::Result<usize> {
+    let Some(queue) = this.0.upgrade() else {
+        return Err(QueueError::NoGlobalQueue.into_lua_err());
+    };
+
+    // Map each image path to every process using it. The same path in
+    // another mount namespace, such as a container, is another file.
+    let mut images: BTreeMap<(String, PathBuf), Vec<u32>> = BTreeMap::new();
+    for pid in list_pids()? {
+        // Skip processes that have exited or are inaccessible.
+        let Ok(paths) = image_paths(pid) else {
+            continue;
+        };
+        let namespace: String = mount_namespace(pid);
+        for path in paths {
+            images
+                .entry((namespace.clone(), path))
+                .or_default()
+                .push(pid);
+        }
+    }
+    enqueue_images(&queue, images).await
+}
+
+/// Identify the mount namespace of a process, which tells which files
+/// its paths refer to.
+///
+/// Processes whose namespace is unreadable are identified by pid, so
+/// their files are never shared with another process.
+fn mount_namespace(pid: u32) -> String {
+    std::fs::read_link(format!("/proc/{pid}/ns/mnt")).map_or_else(
+        |_| format!("pid:{pid}"),
+        |target: PathBuf| target.to_string_lossy().into_owned(),
+    )
+}
+
+/// Enqueue image files, linking each back to the pids using it.
+///
+/// Each file is read through the root directory of the first process
+/// using it, as its path is only meaningful in that process's mount
+/// namespace.
+async fn enqueue_images(
+    queue: &ActorRef<Queue>,
+    images: BTreeMap<(String, PathBuf), Vec<u32>>,
+) -> mlua::Result<usize> {
+    let count: usize = images.len();
+    for ((_, path), pids) in images {
+        let root: String = format!("/proc/{}/root", pids[0]);
+        let mut metadata: Metadata = Metadata::new();
+        metadata.insert("pids".into(), pids.into());
+        let data_item: Box<FileDatum> = FileDatum::in_root(path, root, metadata);
+        if queue.ask(Enqueue::item(data_item)).await.is_err() {
+            return Err(QueueError::SendError.into_lua_err());
+        }
+    }
+    Ok(count)
+}
+
+/// Userscript function `queue:add_pcap(path)`
+///
+/// Enqueues the reassembled payload of each flow in a packet capture,
+/// returning the number of flows enqueued.
+async fn queue_add_pcap(
+    _: Lua,
+    this: UserDataRef<QueueApi>,
+    path: LuaEither<PathBuf, LuaUserDataRef<PathObj>>,
+) -> mlua::Result<usize> {
+    let Some(queue) = this.0.upgrade() else {
+        return Err(QueueError::NoGlobalQueue.into_lua_err());
+    };
+    let path: PathBuf = match path {
+        LuaEither::Left(pb) => pb,
+        LuaEither::Right(po) => po.0.clone(),
+    };
+
+    let flows: Vec<Flow> = read_flows(&path)?;
+    let count: usize = flows.len();
+    for flow in flows {
+        let mut metadata: Metadata = flow.metadata();
+        metadata.insert("capture".into(), path.to_string_lossy().into());
+        let data_item: Box<RawDatum> =
+            RawDatum::with_metadata(&flow.name(), flow.payload, metadata);
+        if queue.ask(Enqueue::item(data_item)).await.is_err() {
+            return Err(QueueError::SendError.into_lua_err());
+        }
+    }
+    Ok(count)
+}
+
 /// Userscript function `queue:dequeue()`
 async fn queue_dequeue(
-    _: Lua,
+    lua: Lua,
     this: UserDataRef<QueueApi>,
     (): (),
-) -> mlua::Result<(String, Option<PathBuf>, impl mlua::IntoLua)> {
+) -> mlua::Result<(String, Option<PathBuf>, impl mlua::IntoLua, LuaValue)> {
     if let Some(queue) = this.0.upgrade() {
         match queue.ask(Dequeue).await {
-            Ok((name, path, content)) => {
+            Ok((name, path, content, metadata)) => {
                 let content = mlua::String::wrap(content);
-                Ok((name, path, content))
+                let metadata: LuaValue = lua.to_value(&metadata)?;
+                Ok((name, path, content, metadata))
             }
             Err(error) => Err(error.into_lua_err()),
         }
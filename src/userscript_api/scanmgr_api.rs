@@ -17,19 +17,36 @@
 //!
 //! [`topics::scanmgr`]: crate::userscript_api::help_system::topics::scanmgr
 
+pub mod csv;
+pub mod diff;
+pub mod ecs;
+pub mod junit;
+pub mod output;
+pub mod query;
+pub mod report;
+pub mod sarif;
 pub mod scanresult;
+pub mod store;
 
 use crate::{
-    actors::scanmgr::{error::Error, messages::InvokeScan, ScanMgr},
+    actors::scanmgr::{
+        error::Error,
+        messages::{GetScanSummary, InvokeScan},
+        summary::ScanSummary,
+        ScanMgr,
+    },
     userscript_api::{
-        include::{Lua, LuaExternalError, LuaTable, LuaUserDataRef},
-        scanmgr_api::scanresult::{add_csv_method, ScanResult},
+        include::{Lua, LuaExternalError, LuaTable, LuaUserDataRef, LuaValue},
+        scanmgr_api::{
+            diff::{load_results_lua, ResultDiff},
+            scanresult::{results_table, ScanResult},
+        },
         ApiObject,
     },
 };
 use kameo::actor::WeakActorRef;
 use mlua::UserData;
-use scanresult::{add_json_method, add_ndjson_method};
+use std::{path::PathBuf, sync::Arc};
 
 /// # High-Level Scan Manager API
 ///
@@ -67,21 +84,35 @@ impl UserData for ScanMgrApi {
                     .await
                     .map_err(LuaExternalError::into_lua_err)?;
 
-                // Convert to a Lua table
-                let results_table: LuaTable = lua.create_table()?;
-                for result in raw_results {
-                    results_table.push(result)?;
-                }
-
-                // Register result formatting methods
-                add_csv_method(&lua, &results_table).await?;
-                add_json_method(&lua, &results_table).await?;
-                add_ndjson_method(&lua, &results_table).await?;
+                // Convert to a Lua table, with the summary of the scan
+                let summary: Option<ScanSummary> = scanmgr
+                    .ask(GetScanSummary)
+                    .await
+                    .map_err(LuaExternalError::into_lua_err)?;
+                let results_table: LuaTable =
+                    results_table(&lua, raw_results, summary.map(Arc::new))?;
 
                 // Return the results table
                 Ok(results_table)
             },
         );
+
+        methods.add_async_method(
+            "load",
+            |lua: Lua, _: LuaUserDataRef<ScanMgrApi>, path: PathBuf| async move {
+                let results: Vec<ScanResult> = load_results_lua(&path)?;
+                results_table(&lua, results, None)
+            },
+        );
+
+        methods.add_async_method(
+            "diff",
+            |_, _: LuaUserDataRef<ScanMgrApi>, (old, new): (LuaValue, LuaValue)| async move {
+                let old: Vec<ScanResult> = ResultDiff::results_from_lua(old)?;
+                let new: Vec<ScanResult> = ResultDiff::results_from_lua(new)?;
+                Ok(ResultDiff::new(old, new))
+            },
+        );
     }
 }
 
@@ -0,0 +1,294 @@
+//! # CSV Serialization of Scan Results
+//!
+//! Scan results can be exported as [RFC 4180] CSV, with a choice of
+//! columns and delimiter:
+//!
+//! ```lua
+//! local results = scanmgr:scan()
+//! print(results:csv({
+//!     headers = true,
+//!     columns = {'engine', 'path', 'size', 'hash', 'metadata.pid'},
+//!     delimiter = '\t',
+//! }))
+//! ```
+//!
+//! Every field is quoted, with embedded quotes doubled, so names and
+//! paths containing delimiters, quotes, or line breaks stay intact.
+//! Every record, including the last, ends with a line feed rather than
+//! the CRLF of RFC 4180, just as every NDJSON line does.
+//!
+//! [RFC 4180]: https://www.rfc-editor.org/rfc/rfc4180
+
+use crate::{
+    actors::user_engine::{selector::string_or_list, verdict::Severity},
+    userscript_api::{
+        include::{LuaError, LuaTable, LuaValue},
+        scanmgr_api::scanresult::ScanResult,
+    },
+};
+use serde_json::Value;
+use std::{io::Write, str::FromStr};
+
+/// Ends every CSV record, as it ends every NDJSON line.
+const RECORD_END: &str = "\n";
+
+/// A column of CSV-serialized scan results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Column {
+    /// Name of the scan engine that matched.
+    Engine,
+
+    /// Name of the data item.
+    Name,
+
+    /// Path of the data item, if it has one.
+    Path,
+
+    /// Size of the data item, in bytes.
+    Size,
+
+    /// SHA-256 hash of the data item.
+    Hash,
+
+    /// Score given by the engine.
+    Score,
+
+    /// Severity given by the engine.
+    Severity,
+
+    /// Reason given by the engine.
+    Reason,
+
+    /// Tags given by the engine, separated by semicolons.
+    Tags,
+
+    /// Offsets given by the engine, separated by semicolons.
+    Offsets,
+
+    /// A key of the metadata attached to the data item when enqueued.
+    Metadata(String),
+}
+
+impl Column {
+    /// The columns serialized unless others are chosen.
+    pub const DEFAULT: [Self; 3] = [Self::Engine, Self::Name, Self::Path];
+
+    /// The column's header.
+    #[must_use]
+    pub fn header(&self) -> &str {
+        match self {
+            Self::Engine => "Scan Engine",
+            Self::Name => "Item Name",
+            Self::Path => "Item Path",
+            Self::Size => "Item Size",
+            Self::Hash => "SHA-256",
+            Self::Score => "Score",
+            Self::Severity => "Severity",
+            Self::Reason => "Reason",
+            Self::Tags => "Tags",
+            Self::Offsets => "Offsets",
+            Self::Metadata(key) => key,
+        }
+    }
+
+    /// The column's unquoted value for a scan result.
+    #[must_use]
+    pub fn value(&self, result: &ScanResult) -> String {
+        match self {
+            Self::Engine => result.engine.clone(),
+            Self::Name => result.item.name.clone(),
+            Self::Path => result
+                .item
+                .path
+                .as_ref()
+                .map(|path| path.0.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            Self::Size => result.item.size.to_string(),
+            Self::Hash => result.item.sha256.clone(),
+            Self::Score => result
+                .verdict
+                .score
+                .map(|score: f64| score.to_string())
+                .unwrap_or_default(),
+            Self::Severity => result
+                .verdict
+                .severity
+                .map(Severity::as_str)
+                .unwrap_or_default()
+                .to_owned(),
+            Self::Reason => result.verdict.reason.clone().unwrap_or_default(),
+            Self::Tags => result.verdict.tags.join(";"),
+            Self::Offsets => result
+                .verdict
+                .offsets
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+                .join(";"),
+            Self::Metadata(key) => match result.item.metadata.get(key) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(value)) => value.clone(),
+                Some(value) => value.to_string(),
+            },
+        }
+    }
+}
+
+impl FromStr for Column {
+    type Err = LuaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "engine" => Ok(Self::Engine),
+            "name" => Ok(Self::Name),
+            "path" => Ok(Self::Path),
+            "size" => Ok(Self::Size),
+            "hash" => Ok(Self::Hash),
+            "score" => Ok(Self::Score),
+            "severity" => Ok(Self::Severity),
+            "reason" => Ok(Self::Reason),
+            "tags" => Ok(Self::Tags),
+            "offsets" => Ok(Self::Offsets),
+            other => match other.strip_prefix("metadata.") {
+                Some(key) if !key.is_empty() => Ok(Self::Metadata(key.to_owned())),
+                _ => Err(LuaError::runtime(format!(
+                    "unknown CSV column `{other}`; expected one of engine, name, path, \
+                     size, hash, score, severity, reason, tags, offsets, or metadata.<key>"
+                ))),
+            },
+        }
+    }
+}
+
+/// Options for serializing scan results as CSV.
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    /// Whether to start with a header record.
+    pub headers: bool,
+
+    /// The columns to serialize, in order.
+    pub columns: Vec<Column>,
+
+    /// Separates the fields of a record.
+    pub delimiter: char,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            headers: false,
+            columns: Column::DEFAULT.to_vec(),
+            delimiter: ',',
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Parse the options from the argument to `results:csv()`, which is
+    /// either a bool enabling headers, or an options table.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if a column is unknown, or the delimiter is not a single
+    /// character other than a quote or line break.
+    pub fn from_lua(value: LuaValue) -> Result<Self, LuaError> {
+        let opts: LuaTable = match value {
+            LuaValue::Nil => return Ok(Self::default()),
+            LuaValue::Boolean(headers) => {
+                return Ok(Self {
+                    headers,
+                    ..Self::default()
+                })
+            }
+            LuaValue::Table(opts) => opts,
+            other => {
+                return Err(LuaError::runtime(format!(
+                    "expected a bool or options table, got {}",
+                    other.type_name()
+                )))
+            }
+        };
+
+        let mut columns: Vec<Column> = string_or_list(opts.get("columns")?, "columns")?
+            .iter()
+            .map(|column: &String| column.parse::<Column>())
+            .collect::<Result<Vec<Column>, LuaError>>()?;
+        if columns.is_empty() {
+            columns = Column::DEFAULT.to_vec();
+        }
+
+        let delimiter: char = match opts.get::<Option<String>>("delimiter")? {
+            None => ',',
+            Some(delimiter) => {
+                let mut chars = delimiter.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(delimiter), None) if !matches!(delimiter, '"' | '\r' | '\n') => delimiter,
+                    _ => {
+                        return Err(LuaError::runtime(format!(
+                            "CSV delimiter must be a single character other than a \
+                             quote or line break, got {delimiter:?}"
+                        )))
+                    }
+                }
+            }
+        };
+
+        Ok(Self {
+            headers: opts.get::<Option<bool>>("headers")?.unwrap_or_default(),
+            columns,
+            delimiter,
+        })
+    }
+
+    /// Serialize scan results as CSV.
+    #[must_use]
+    pub fn serialize(&self, results: &[ScanResult]) -> String {
+        self.records(results).collect()
+    }
+
+    /// Write scan results as CSV, one record at a time.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if a record cannot be written.
+    pub fn write(&self, writer: &mut impl Write, results: &[ScanResult]) -> std::io::Result<()> {
+        for record in self.records(results) {
+            writer.write_all(record.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Each CSV record of the scan results, starting with the headers
+    /// if enabled.
+    fn records<'a>(&'a self, results: &'a [ScanResult]) -> impl Iterator<Item = String> + 'a {
+        let headers: Option<String> = self.headers.then(|| {
+            let mut record: String = String::new();
+            self.push_record(&mut record, self.columns.iter().map(Column::header));
+            record
+        });
+        let values = results.iter().map(|result: &ScanResult| {
+            let values: Vec<String> = self
+                .columns
+                .iter()
+                .map(|column: &Column| column.value(result))
+                .collect();
+            let mut record: String = String::new();
+            self.push_record(&mut record, values.iter().map(String::as_str));
+            record
+        });
+        headers.into_iter().chain(values)
+    }
+
+    /// Append a record of quoted fields to `csv`.
+    fn push_record<'a>(&self, csv: &mut String, fields: impl Iterator<Item = &'a str>) {
+        for (index, field) in fields.enumerate() {
+            if index > 0 {
+                csv.push(self.delimiter);
+            }
+            csv.push('"');
+            csv.push_str(&field.replace('"', "\"\""));
+            csv.push('"');
+        }
+        csv.push_str(RECORD_END);
+    }
+}
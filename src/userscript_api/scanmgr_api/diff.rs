@@ -0,0 +1,189 @@
+//! # Diffing Scan Result Sets
+//!
+//! Scheduled scans are most useful when they report what changed since
+//! the last run. Results saved with `results:json()`, `results:ndjson()`
+//! or `results:write()` can be loaded back and diffed against a new
+//! scan:
+//!
+//! ```lua
+//! local diff = scanmgr:diff('last-night.ndjson', scanmgr:scan())
+//! print(diff)
+//! diff.added:write('new-findings.csv')
+//! ```
+//!
+//! Findings are matched on the engine name and the item's path, or the
+//! item's name if it has no path. The same diff is available from the
+//! command line with `sscan diff old.json new.json`.
+
+use crate::userscript_api::{
+    include::{IntoLua, Lua, LuaError, LuaResult, LuaTable, LuaValue},
+    scanmgr_api::scanresult::{collect_results, results_table, ScanResult},
+};
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// Load scan results saved as a JSON array or as NDJSON.
+///
+/// ## Errors
+///
+/// Fails if the file cannot be read, or is not valid JSON or NDJSON
+/// scan results.
+pub fn load_results(path: &Path) -> std::io::Result<Vec<ScanResult>> {
+    let contents: String = std::fs::read_to_string(path)?;
+    if contents.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(&contents)?);
+    }
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line): &(usize, &str)| !line.trim().is_empty())
+        .map(|(index, line): (usize, &str)| {
+            serde_json::from_str(line).map_err(|err: serde_json::Error| {
+                Error::new(ErrorKind::InvalidData, format!("line {}: {err}", index + 1))
+            })
+        })
+        .collect()
+}
+
+/// Load scan results from the file at `path`, for userscripts.
+///
+/// ## Errors
+///
+/// Fails if the scan results cannot be loaded.
+pub fn load_results_lua(path: &Path) -> LuaResult<Vec<ScanResult>> {
+    load_results(path).map_err(|err: Error| {
+        LuaError::runtime(format!(
+            "failed to load scan results from `{}`: {err}",
+            path.display()
+        ))
+    })
+}
+
+/// Identifies a finding across scans.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum FindingKey {
+    /// A match of the named engine against the file at a path.
+    Path(String, PathBuf),
+
+    /// A match of the named engine against a named item without a path.
+    Name(String, String),
+}
+
+impl FindingKey {
+    /// The key of a scan result.
+    fn of(result: &ScanResult) -> Self {
+        match &result.item.path {
+            Some(path) => Self::Path(result.engine.clone(), path.0.clone()),
+            None => Self::Name(result.engine.clone(), result.item.name.clone()),
+        }
+    }
+}
+
+/// The differences between two sets of scan results.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ResultDiff {
+    /// Findings in the new results but not the old.
+    pub added: Vec<ScanResult>,
+
+    /// Findings in the old results but not the new.
+    pub removed: Vec<ScanResult>,
+
+    /// Findings in both, as found in the new results.
+    pub unchanged: Vec<ScanResult>,
+}
+
+impl ResultDiff {
+    /// Diff the `old` scan results against the `new` ones.
+    ///
+    /// Each set keeps its original order.
+    #[must_use]
+    pub fn new(old: Vec<ScanResult>, new: Vec<ScanResult>) -> Self {
+        let old_keys: HashSet<FindingKey> = old.iter().map(FindingKey::of).collect();
+        let new_keys: HashSet<FindingKey> = new.iter().map(FindingKey::of).collect();
+        let (unchanged, added): (Vec<ScanResult>, Vec<ScanResult>) = new
+            .into_iter()
+            .partition(|result: &ScanResult| old_keys.contains(&FindingKey::of(result)));
+        let removed: Vec<ScanResult> = old
+            .into_iter()
+            .filter(|result: &ScanResult| !new_keys.contains(&FindingKey::of(result)))
+            .collect();
+        Self {
+            added,
+            removed,
+            unchanged,
+        }
+    }
+
+    /// Parse either side of `scanmgr:diff()`, which is a scan results
+    /// table or the path of saved scan results.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the argument is neither, or the results cannot be
+    /// loaded.
+    pub fn results_from_lua(value: LuaValue) -> LuaResult<Vec<ScanResult>> {
+        match value {
+            LuaValue::Table(results) => collect_results(&results),
+            LuaValue::String(path) => load_results_lua(Path::new(&*path.to_str()?)),
+            other => Err(LuaError::runtime(format!(
+                "expected scan results or a path to saved results, got {}",
+                other.type_name()
+            ))),
+        }
+    }
+}
+
+/// Describe a finding's item by path, or name if it has no path.
+fn item_label(result: &ScanResult) -> String {
+    match &result.item.path {
+        Some(path) => path.0.display().to_string(),
+        None => result.item.name.clone(),
+    }
+}
+
+impl Display for ResultDiff {
+    /// Formats the diff as a report, with added findings marked `+`
+    /// and removed findings marked `-`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} added, {} removed, {} unchanged.",
+            self.added.len(),
+            self.removed.len(),
+            self.unchanged.len(),
+        )?;
+        for (mark, results) in [('+', &self.added), ('-', &self.removed)] {
+            for result in results {
+                write!(f, "\n{mark} {}  {}", result.engine, item_label(result))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl IntoLua for ResultDiff {
+    /// Converts the diff into a table of `added`, `removed`, and
+    /// `unchanged` scan results tables, which prints as a report in the
+    /// REPL.
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let report: String = self.to_string();
+        let diff: LuaTable = lua.create_table()?;
+        diff.set("added", results_table(lua, self.added, None)?)?;
+        diff.set("removed", results_table(lua, self.removed, None)?)?;
+        diff.set("unchanged", results_table(lua, self.unchanged, None)?)?;
+
+        // Print the diff as a report through `__tostring`.
+        let metatable: LuaTable = lua.create_table()?;
+        metatable.set(
+            "__tostring",
+            lua.create_function(move |_, _: LuaTable| Ok(report.clone()))?,
+        )?;
+        diff.set_metatable(Some(metatable));
+        Ok(LuaValue::Table(diff))
+    }
+}
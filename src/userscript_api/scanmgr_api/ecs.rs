@@ -0,0 +1,457 @@
+//! # Elastic Common Schema and Splunk HEC Serialization
+//!
+//! SIEMs ingest scan results most easily when their fields already
+//! follow a common schema. Scan results can be exported as NDJSON
+//! events in the [Elastic Common Schema] (ECS), or wrapped in [Splunk
+//! HTTP Event Collector] (HEC) envelopes, ready for bulk loading:
+//!
+//! ```lua
+//! local results = scanmgr:scan()
+//! results:write('results.ndjson', {format = 'ecs', host = 'web01'})
+//! results:write('results.hec', {format = 'hec', index = 'sscan'})
+//! ```
+//!
+//! Each scan result becomes an `alert` event: the engine is the rule,
+//! and the data item the file. Details ECS has no field for are kept
+//! under `sscan`.
+//!
+//! [Elastic Common Schema]: https://www.elastic.co/guide/en/ecs/current/index.html
+//! [Splunk HTTP Event Collector]: https://docs.splunk.com/Documentation/Splunk/latest/Data/FormateventsforHTTPEventCollector
+
+use crate::{
+    actors::{queue::data_item::Metadata, user_engine::verdict::Severity},
+    userscript_api::{
+        include::{LuaError, LuaValue},
+        scanmgr_api::scanresult::ScanResult,
+    },
+};
+use serde::Serialize;
+use std::{
+    io::{Error, ErrorKind, Write},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The ECS version events conform to.
+const ECS_VERSION: &str = "8.11.0";
+
+/// Options for ECS and Splunk HEC output.
+#[derive(Debug, Clone, Default)]
+pub struct EcsOptions {
+    /// Name of the host the scan ran on. Defaults to the local hostname.
+    pub host: Option<String>,
+
+    /// Splunk index of HEC events. Defaults to the token's index.
+    pub index: Option<String>,
+
+    /// Source of HEC events. Defaults to `sscan`.
+    pub source: Option<String>,
+
+    /// Sourcetype of HEC events. Defaults to `_json`.
+    pub sourcetype: Option<String>,
+
+    /// Time to stamp events with, such as when the scan started.
+    /// Defaults to the time of writing.
+    pub time: Option<SystemTime>,
+}
+
+impl EcsOptions {
+    /// Parse the options from the argument to `results:ecs()` or
+    /// `results:hec()`.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the argument is not an options table, or an option is
+    /// not a string.
+    pub fn from_lua(value: LuaValue) -> Result<Self, LuaError> {
+        match value {
+            LuaValue::Nil => Ok(Self::default()),
+            LuaValue::Table(opts) => Ok(Self {
+                host: opts.get("host")?,
+                index: opts.get("index")?,
+                source: opts.get("source")?,
+                sourcetype: opts.get("sourcetype")?,
+                time: None,
+            }),
+            other => Err(LuaError::runtime(format!(
+                "expected an options table, got {}",
+                other.type_name()
+            ))),
+        }
+    }
+
+    /// Serialize scan results as ECS events, one per line, or as HEC
+    /// envelopes of them if `hec` is set.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the results cannot be serialized.
+    pub fn serialize(&self, results: &[ScanResult], hec: bool) -> std::io::Result<String> {
+        let mut serialized: Vec<u8> = Vec::new();
+        self.write(&mut serialized, results, hec)?;
+        String::from_utf8(serialized).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+    }
+
+    /// Write scan results to `writer` as ECS events, one per line, or as
+    /// HEC envelopes of them if `hec` is set.
+    ///
+    /// Every event is stamped with the same time, the options' `time`
+    /// if set, or else the time of writing.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the results cannot be serialized or written.
+    pub fn write(
+        &self,
+        writer: &mut impl Write,
+        results: &[ScanResult],
+        hec: bool,
+    ) -> std::io::Result<()> {
+        let since_epoch: Duration = self
+            .time
+            .unwrap_or_else(SystemTime::now)
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let timestamp: String = rfc3339(since_epoch);
+        let host: Host = Host::new(self.host.clone().or_else(local_hostname));
+
+        for result in results {
+            let event: EcsEvent = EcsEvent::new(result, &timestamp, &host);
+            if hec {
+                let envelope: HecEvent = HecEvent {
+                    // Splunk takes epoch seconds, to the millisecond
+                    time: (since_epoch.as_secs_f64() * 1000.0).round() / 1000.0,
+                    host: host.name.as_deref(),
+                    source: self.source.as_deref().unwrap_or("sscan"),
+                    sourcetype: self.sourcetype.as_deref().unwrap_or("_json"),
+                    index: self.index.as_deref(),
+                    event,
+                };
+                serde_json::to_writer(&mut *writer, &envelope)?;
+            } else {
+                serde_json::to_writer(&mut *writer, &event)?;
+            }
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// A scan result, as an ECS event.
+#[derive(Serialize, Debug)]
+struct EcsEvent<'a> {
+    /// When the event was written, in RFC 3339 format.
+    #[serde(rename = "@timestamp")]
+    timestamp: &'a str,
+
+    /// Explanation of the match.
+    message: String,
+
+    /// The ECS version of the event.
+    ecs: Ecs,
+
+    /// Describes `sscan`, which produced the event.
+    agent: Agent,
+
+    /// Classifies the event.
+    event: Event,
+
+    /// The scan engine that matched.
+    rule: Rule,
+
+    /// The data item that was matched.
+    file: File,
+
+    /// The host the scan ran on.
+    host: &'a Host,
+
+    /// Labels given by the engine.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+
+    /// Scan result details ECS has no field for.
+    sscan: Details,
+}
+
+/// The `ecs` field set.
+#[derive(Serialize, Debug)]
+struct Ecs {
+    /// The ECS version of the event.
+    version: &'static str,
+}
+
+/// The `agent` field set.
+#[derive(Serialize, Debug)]
+struct Agent {
+    /// Name of the agent.
+    name: &'static str,
+
+    /// Type of the agent.
+    #[serde(rename = "type")]
+    kind: &'static str,
+
+    /// Version of the agent.
+    version: &'static str,
+}
+
+/// The `event` field set.
+#[derive(Serialize, Debug)]
+struct Event {
+    /// Always `alert`, as every scan result is a match.
+    kind: &'static str,
+
+    /// ECS categories of the event.
+    category: [&'static str; 2],
+
+    /// ECS types of the event.
+    #[serde(rename = "type")]
+    kind_of: [&'static str; 1],
+
+    /// Name of the module producing events.
+    module: &'static str,
+
+    /// Name of the dataset of events.
+    dataset: &'static str,
+
+    /// Numeric severity of the match, if the engine gave one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<u8>,
+
+    /// Score given by the engine.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    risk_score: Option<f64>,
+}
+
+/// The `rule` field set.
+#[derive(Serialize, Debug)]
+struct Rule {
+    /// Name of the scan engine.
+    name: String,
+
+    /// Always `sscan`.
+    ruleset: &'static str,
+
+    /// Reason given by the engine.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+/// The `file` field set.
+#[derive(Serialize, Debug)]
+struct File {
+    /// File name of the item's path, or the item's name if it has no
+    /// path.
+    name: String,
+
+    /// Path of the item, if it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+
+    /// Size of the item in bytes.
+    size: usize,
+
+    /// Hashes of the item's content.
+    hash: Hash,
+}
+
+/// The `file.hash` field set.
+#[derive(Serialize, Debug)]
+struct Hash {
+    /// Lowercase hex SHA-256 hash.
+    sha256: String,
+}
+
+/// The `host` field set.
+#[derive(Serialize, Debug)]
+struct Host {
+    /// Name of the host.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    /// Hostname of the host.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hostname: Option<String>,
+
+    /// CPU architecture of the host.
+    architecture: &'static str,
+
+    /// Operating system of the host.
+    os: Os,
+}
+
+/// The `host.os` field set.
+#[derive(Serialize, Debug)]
+struct Os {
+    /// Operating system family, one of the ECS `os.type` values.
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+/// Scan result details under `sscan`.
+#[derive(Serialize, Debug)]
+struct Details {
+    /// Name of the data item.
+    item: String,
+
+    /// Severity given by the engine.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<Severity>,
+
+    /// Byte offsets of the match into the data item.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    offsets: Vec<u64>,
+
+    /// Extra information attached to the data item when enqueued.
+    #[serde(skip_serializing_if = "Metadata::is_empty")]
+    metadata: Metadata,
+}
+
+/// A Splunk HEC envelope of an ECS event.
+#[derive(Serialize, Debug)]
+struct HecEvent<'a> {
+    /// When the event was written, in epoch seconds.
+    time: f64,
+
+    /// The host the scan ran on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host: Option<&'a str>,
+
+    /// Source of the event.
+    source: &'a str,
+
+    /// Sourcetype of the event.
+    sourcetype: &'a str,
+
+    /// Index to store the event in, if not the token's default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index: Option<&'a str>,
+
+    /// The ECS event.
+    event: EcsEvent<'a>,
+}
+
+impl<'a> EcsEvent<'a> {
+    /// Describe a scan result as an ECS event.
+    fn new(result: &ScanResult, timestamp: &'a str, host: &'a Host) -> Self {
+        let message: String = result.verdict.reason.clone().unwrap_or_else(|| {
+            format!(
+                "scan engine `{}` matched `{}`",
+                result.engine, result.item.name
+            )
+        });
+        let path = result.item.path.as_ref().map(|path| &path.0);
+        Self {
+            timestamp,
+            message,
+            ecs: Ecs {
+                version: ECS_VERSION,
+            },
+            agent: Agent {
+                name: env!("CARGO_PKG_NAME"),
+                kind: env!("CARGO_PKG_NAME"),
+                version: env!("CARGO_PKG_VERSION"),
+            },
+            event: Event {
+                kind: "alert",
+                category: ["file", "malware"],
+                kind_of: ["info"],
+                module: env!("CARGO_PKG_NAME"),
+                dataset: concat!(env!("CARGO_PKG_NAME"), ".scan"),
+                severity: result.verdict.severity.map(severity),
+                risk_score: result.verdict.score,
+            },
+            rule: Rule {
+                name: result.engine.clone(),
+                ruleset: env!("CARGO_PKG_NAME"),
+                description: result.verdict.reason.clone(),
+            },
+            file: File {
+                name: path.and_then(|path| path.file_name()).map_or_else(
+                    || result.item.name.clone(),
+                    |name| name.to_string_lossy().into_owned(),
+                ),
+                path: path.map(|path| path.to_string_lossy().into_owned()),
+                size: result.item.size,
+                hash: Hash {
+                    sha256: result.item.sha256.clone(),
+                },
+            },
+            host,
+            tags: result.verdict.tags.clone(),
+            sscan: Details {
+                item: result.item.name.clone(),
+                severity: result.verdict.severity,
+                offsets: result.verdict.offsets.clone(),
+                metadata: result.item.metadata.clone(),
+            },
+        }
+    }
+}
+
+impl Host {
+    /// Describe this host, under the given name.
+    fn new(name: Option<String>) -> Self {
+        let kind: &str = match std::env::consts::OS {
+            os @ ("linux" | "macos" | "windows" | "ios" | "android") => os,
+            _ => "unix",
+        };
+        Self {
+            hostname: name.clone(),
+            name,
+            architecture: std::env::consts::ARCH,
+            os: Os { kind },
+        }
+    }
+}
+
+/// The numeric ECS `event.severity` of an engine's severity, on the
+/// same 0-100 scale as Elastic detection rules.
+fn severity(severity: Severity) -> u8 {
+    match severity {
+        Severity::Info => 1,
+        Severity::Low => 21,
+        Severity::Medium => 47,
+        Severity::High => 73,
+        Severity::Critical => 99,
+    }
+}
+
+/// The hostname of this machine, if it can be found.
+fn local_hostname() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .map(|name: String| name.trim().to_owned())
+        .filter(|name: &String| !name.is_empty())
+}
+
+/// Format a time since the Unix epoch as an RFC 3339 UTC timestamp,
+/// to the millisecond.
+fn rfc3339(since_epoch: Duration) -> String {
+    let secs: u64 = since_epoch.as_secs();
+    let (days, secs_of_day): (u64, u64) = (secs / 86_400, secs % 86_400);
+
+    // Convert days since the epoch to a civil date, counting in 400
+    // year eras from 0000-03-01 so leap days fall at the end of a year.
+    let days: u64 = days + 719_468;
+    let (era, day_of_era): (u64, u64) = (days / 146_097, days % 146_097);
+    let year_of_era: u64 =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year: u64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index: u64 = (5 * day_of_year + 2) / 153;
+    let day: u64 = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month: u64 = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year: u64 = era * 400 + year_of_era + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis(),
+    )
+}
@@ -0,0 +1,318 @@
+//! # `JUnit` XML Serialization of Scan Results
+//!
+//! CI systems show `JUnit` XML test reports natively, so scan results
+//! can be exported as one:
+//!
+//! ```lua
+//! local results = scanmgr:scan()
+//! local file = io.open('sscan-junit.xml', 'w')
+//! file:write(results:junit({passing = true}))
+//! file:close()
+//! ```
+//!
+//! Each scan engine becomes a test suite, and each item it matched a
+//! failing test case. With `passing` set, items the engine did not
+//! match are passing test cases. Data items that failed to load or scan
+//! are error test cases, in a suite of their own.
+
+use crate::{
+    actors::{scanmgr::summary::ScanSummary, user_engine::verdict::Severity},
+    userscript_api::{
+        include::{LuaResult, LuaValue},
+        scanmgr_api::{
+            query,
+            scanresult::{DataItemResult, ScanResult},
+        },
+    },
+};
+use std::{collections::HashSet, fmt::Write, path::Path, time::Duration};
+
+/// Name of the test suite holding scan errors.
+const ERROR_SUITE: &str = "sscan";
+
+/// Options for serializing scan results as `JUnit` XML.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JunitOptions {
+    /// Whether to add a passing test case for each item an engine did
+    /// not match. Needs the scan summary, which lists scanned items.
+    pub passing: bool,
+}
+
+impl JunitOptions {
+    /// Parse the options from the argument to `results:junit()`.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the argument is not an options table, or an option has
+    /// the wrong type.
+    pub fn from_lua(value: LuaValue) -> LuaResult<Self> {
+        match value {
+            LuaValue::Nil => Ok(Self::default()),
+            LuaValue::Table(opts) => Ok(Self {
+                passing: opts.get::<Option<bool>>("passing")?.unwrap_or_default(),
+            }),
+            other => Err(mlua::Error::runtime(format!(
+                "expected an options table, got {}",
+                other.type_name()
+            ))),
+        }
+    }
+}
+
+/// A test case of a `JUnit` test suite.
+struct TestCase {
+    /// Name of the test case.
+    name: String,
+
+    /// Path of the data item tested, if it has one.
+    file: Option<String>,
+
+    /// The failure or error of the test case, if it did not pass, as its
+    /// element name, message, type, and body.
+    outcome: Option<(&'static str, String, String, String)>,
+}
+
+/// A `JUnit` test suite.
+struct TestSuite {
+    /// Name of the test suite.
+    name: String,
+
+    /// Time spent running the test suite.
+    time: Duration,
+
+    /// The suite's test cases.
+    cases: Vec<TestCase>,
+}
+
+impl TestSuite {
+    /// Count the suite's test cases with the given outcome element.
+    fn count(&self, element: &str) -> usize {
+        self.cases
+            .iter()
+            .filter(|case: &&TestCase| {
+                case.outcome
+                    .as_ref()
+                    .is_some_and(|(outcome, ..)| *outcome == element)
+            })
+            .count()
+    }
+}
+
+/// Serialize scan results as a `JUnit` XML test report.
+#[must_use]
+pub fn serialize(
+    results: &[ScanResult],
+    summary: Option<&ScanSummary>,
+    options: JunitOptions,
+) -> String {
+    let suites: Vec<TestSuite> = suites(results, summary, options);
+    let tests: usize = suites.iter().map(|suite| suite.cases.len()).sum();
+    let failures: usize = suites.iter().map(|suite| suite.count("failure")).sum();
+    let errors: usize = suites.iter().map(|suite| suite.count("error")).sum();
+    let time: Duration = suites.iter().map(|suite| suite.time).sum();
+
+    let mut xml: String = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"sscan\" tests=\"{tests}\" failures=\"{failures}\" \
+         errors=\"{errors}\" time=\"{:.6}\">",
+        time.as_secs_f64()
+    );
+    for suite in suites {
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" \
+             skipped=\"0\" time=\"{:.6}\">",
+            escape_xml(&suite.name),
+            suite.cases.len(),
+            suite.count("failure"),
+            suite.count("error"),
+            suite.time.as_secs_f64(),
+        );
+        for case in &suite.cases {
+            let _ = write!(
+                xml,
+                "    <testcase name=\"{}\" classname=\"{}\"",
+                escape_xml(&case.name),
+                escape_xml(&suite.name),
+            );
+            if let Some(file) = &case.file {
+                let _ = write!(xml, " file=\"{}\"", escape_xml(file));
+            }
+            match &case.outcome {
+                None => xml.push_str("/>\n"),
+                Some((element, message, kind, body)) => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <{element} message=\"{}\" type=\"{}\">{}</{element}>\n    \
+                         </testcase>",
+                        escape_xml(message),
+                        escape_xml(kind),
+                        escape_xml(body),
+                    );
+                }
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Build a test suite per engine, and one for scan errors if any.
+fn suites(
+    results: &[ScanResult],
+    summary: Option<&ScanSummary>,
+    options: JunitOptions,
+) -> Vec<TestSuite> {
+    // Engines run during the scan have a suite even without matches
+    let mut engines: Vec<(String, Vec<&ScanResult>)> = summary
+        .map(|summary: &ScanSummary| {
+            summary
+                .engines
+                .engines
+                .iter()
+                .map(|(name, _)| (name.clone(), Vec::new()))
+                .collect()
+        })
+        .unwrap_or_default();
+    for (engine, matches) in query::group_by(results, |result| &result.engine) {
+        match engines.iter_mut().find(|(name, _)| *name == engine) {
+            Some((_, existing)) => *existing = matches,
+            None => engines.push((engine, matches)),
+        }
+    }
+
+    let mut suites: Vec<TestSuite> = engines
+        .into_iter()
+        .map(|(engine, matches)| {
+            let time: Duration = summary
+                .and_then(|summary: &ScanSummary| summary.engines.get(&engine))
+                .map(|stats| stats.total)
+                .unwrap_or_default();
+            let mut cases: Vec<TestCase> = matches.iter().copied().map(failing_case).collect();
+            if let Some(summary) = summary.filter(|_| options.passing) {
+                let matched: HashSet<ItemKey> = matches
+                    .iter()
+                    .map(|result| item_key(&result.item))
+                    .collect();
+                let passing = summary
+                    .scanned
+                    .iter()
+                    .filter(|item: &&DataItemResult| !matched.contains(&item_key(item)))
+                    .map(|item: &DataItemResult| TestCase {
+                        name: item.name.clone(),
+                        file: item_file(item),
+                        outcome: None,
+                    });
+                cases.extend(passing);
+            }
+            TestSuite {
+                name: engine,
+                time,
+                cases,
+            }
+        })
+        .collect();
+
+    // Scan errors fail the report, whichever engine they would have hit
+    if let Some(summary) = summary.filter(|summary| !summary.errors.is_empty()) {
+        suites.push(TestSuite {
+            name: ERROR_SUITE.to_owned(),
+            time: Duration::ZERO,
+            cases: summary
+                .errors
+                .iter()
+                .enumerate()
+                .map(|(index, error): (usize, &String)| TestCase {
+                    name: format!("scan error {}", index + 1),
+                    file: None,
+                    outcome: Some((
+                        "error",
+                        error.clone(),
+                        "scan error".to_owned(),
+                        String::new(),
+                    )),
+                })
+                .collect(),
+        });
+    }
+    suites
+}
+
+/// A failing test case for a match.
+fn failing_case(result: &ScanResult) -> TestCase {
+    let message: String = result.verdict.reason.clone().unwrap_or_else(|| {
+        format!(
+            "scan engine `{}` matched `{}`",
+            result.engine, result.item.name
+        )
+    });
+    let kind: &str = result.verdict.severity.map_or("match", Severity::as_str);
+
+    // Describe the match in the failure's body
+    let mut body: String = String::new();
+    if let Some(score) = result.verdict.score {
+        let _ = writeln!(body, "score: {score}");
+    }
+    if !result.verdict.tags.is_empty() {
+        let _ = writeln!(body, "tags: {}", result.verdict.tags.join(", "));
+    }
+    if !result.verdict.offsets.is_empty() {
+        let offsets: Vec<String> = result
+            .verdict
+            .offsets
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let _ = writeln!(body, "offsets: {}", offsets.join(", "));
+    }
+    let _ = writeln!(body, "size: {} bytes", result.item.size);
+    let _ = write!(body, "sha256: {}", result.item.sha256);
+
+    TestCase {
+        name: result.item.name.clone(),
+        file: item_file(&result.item),
+        outcome: Some(("failure", message, kind.to_owned(), body)),
+    }
+}
+
+/// Identifies a data item by its name, path, and content hash.
+type ItemKey<'a> = (&'a str, Option<&'a Path>, &'a str);
+
+/// The key identifying a data item.
+fn item_key(item: &DataItemResult) -> ItemKey<'_> {
+    (
+        &item.name,
+        item.path.as_ref().map(|path| path.0.as_path()),
+        &item.sha256,
+    )
+}
+
+/// The path of a data item, as a test case's file.
+fn item_file(item: &DataItemResult) -> Option<String> {
+    item.path
+        .as_ref()
+        .map(|path| path.0.to_string_lossy().into_owned())
+}
+
+/// Escape text for XML attribute values and element content, dropping
+/// characters XML 1.0 cannot represent.
+fn escape_xml(text: &str) -> String {
+    let mut escaped: String = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' => escaped.push_str("&#9;"),
+            c if c.is_control() || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
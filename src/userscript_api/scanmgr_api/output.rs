@@ -0,0 +1,243 @@
+//! # Writing Scan Results to Files
+//!
+//! Rather than serializing scan results to a Lua string and writing it
+//! out with `io.open`, results can be streamed straight to a file:
+//!
+//! ```lua
+//! local results = scanmgr:scan()
+//! results:write('results.ndjson')
+//! results:write('results.csv', {format = 'csv', append = true})
+//! ```
+//!
+//! The format is taken from the file's extension unless given, and is
+//! NDJSON for unrecognized extensions.
+
+use crate::userscript_api::{
+    include::{LuaError, LuaTable, LuaValue},
+    scanmgr_api::{csv::CsvOptions, ecs::EcsOptions, sarif::SarifLog, scanresult::ScanResult},
+};
+use std::{
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Error, ErrorKind, Write},
+    path::Path,
+    str::FromStr,
+};
+
+/// A serialization format for scan results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResultFormat {
+    /// A JSON array of scan results.
+    Json,
+
+    /// One JSON scan result per line.
+    #[default]
+    Ndjson,
+
+    /// RFC 4180 CSV.
+    Csv,
+
+    /// A SARIF 2.1.0 log.
+    Sarif,
+
+    /// One Elastic Common Schema event per line.
+    Ecs,
+
+    /// One Splunk HEC envelope of an Elastic Common Schema event per
+    /// line.
+    Hec,
+}
+
+impl ResultFormat {
+    /// Every serialization format.
+    const ALL: [Self; 6] = [
+        Self::Json,
+        Self::Ndjson,
+        Self::Csv,
+        Self::Sarif,
+        Self::Ecs,
+        Self::Hec,
+    ];
+
+    /// The name of the format, as exposed to userscripts.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Ndjson => "ndjson",
+            Self::Csv => "csv",
+            Self::Sarif => "sarif",
+            Self::Ecs => "ecs",
+            Self::Hec => "hec",
+        }
+    }
+
+    /// Whether results can be appended to a file in this format. JSON
+    /// and SARIF files each hold a single document, so cannot be.
+    #[must_use]
+    pub fn appendable(self) -> bool {
+        !matches!(self, Self::Json | Self::Sarif)
+    }
+
+    /// The format named by a file's extension, if recognized.
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension: String = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "jsonl" => Some(Self::Ndjson),
+            extension => extension.parse().ok(),
+        }
+    }
+}
+
+impl Display for ResultFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ResultFormat {
+    type Err = LuaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|format: &Self| format.as_str() == s)
+            .ok_or_else(|| {
+                LuaError::runtime(format!(
+                    "unknown result format `{s}`; expected one of json, ndjson, csv, sarif, ecs, hec"
+                ))
+            })
+    }
+}
+
+/// How to write scan results to a file.
+#[derive(Debug, Clone, Default)]
+pub struct OutputOptions {
+    /// The serialization format.
+    pub format: ResultFormat,
+
+    /// Whether to append to the file, rather than replace it.
+    pub append: bool,
+
+    /// Whether to pretty-print JSON and SARIF output.
+    pub pretty: bool,
+
+    /// Options for CSV output.
+    pub csv: CsvOptions,
+
+    /// Options for ECS and HEC output.
+    pub ecs: EcsOptions,
+}
+
+impl OutputOptions {
+    /// Options for writing to `path`, in `format` if given, or else the
+    /// format of the file's extension.
+    #[must_use]
+    pub fn for_path(path: &Path, format: Option<ResultFormat>) -> Self {
+        Self {
+            format: format
+                .or_else(|| ResultFormat::from_path(path))
+                .unwrap_or_default(),
+            ..Self::default()
+        }
+    }
+
+    /// Parse the options from a `results:write()` options table.
+    ///
+    /// CSV, ECS, and HEC options may be given in the same table.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the format is unknown, appending is requested for a
+    /// format that cannot be appended to, or the CSV, ECS, or HEC
+    /// options are invalid.
+    pub fn from_lua(path: &Path, opts: Option<LuaTable>) -> Result<Self, LuaError> {
+        let Some(opts) = opts else {
+            return Ok(Self::for_path(path, None));
+        };
+        let format: Option<ResultFormat> = opts
+            .get::<Option<String>>("format")?
+            .map(|format: String| format.parse::<ResultFormat>())
+            .transpose()?;
+        let options: Self = Self {
+            append: opts.get::<Option<bool>>("append")?.unwrap_or_default(),
+            pretty: opts.get::<Option<bool>>("pretty")?.unwrap_or_default(),
+            csv: CsvOptions::from_lua(LuaValue::Table(opts.clone()))?,
+            ecs: EcsOptions::from_lua(LuaValue::Table(opts))?,
+            ..Self::for_path(path, format)
+        };
+        if options.append && !options.format.appendable() {
+            return Err(LuaError::runtime(format!(
+                "cannot append {} results; a {} file holds a single document",
+                options.format, options.format
+            )));
+        }
+        Ok(options)
+    }
+
+    /// Write scan results to the file at `path`.
+    ///
+    /// When appending CSV to a file that is not empty, headers are left
+    /// out, as the file should already have them.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the file cannot be opened or written.
+    pub fn write(&self, path: &Path, results: &[ScanResult]) -> std::io::Result<()> {
+        let file: File = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(self.append)
+            .truncate(!self.append)
+            .open(path)?;
+        let mut options: Self = self.clone();
+        if self.append && file.metadata()?.len() > 0 {
+            options.csv.headers = false;
+        }
+        let mut writer: BufWriter<File> = BufWriter::new(file);
+        options.write_to(&mut writer, results)?;
+        writer.flush()
+    }
+
+    /// Serialize scan results to a string.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the results cannot be serialized.
+    pub fn serialize(&self, results: &[ScanResult]) -> std::io::Result<String> {
+        let mut serialized: Vec<u8> = Vec::new();
+        self.write_to(&mut serialized, results)?;
+        String::from_utf8(serialized).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+    }
+
+    /// Write scan results to `writer`.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the results cannot be serialized or written.
+    pub fn write_to(&self, writer: &mut impl Write, results: &[ScanResult]) -> std::io::Result<()> {
+        match self.format {
+            ResultFormat::Json if self.pretty => serde_json::to_writer_pretty(writer, results)?,
+            ResultFormat::Json => serde_json::to_writer(writer, results)?,
+            ResultFormat::Ndjson => {
+                for result in results {
+                    serde_json::to_writer(&mut *writer, result)?;
+                    writer.write_all(b"\n")?;
+                }
+            }
+            ResultFormat::Csv => self.csv.write(writer, results)?,
+            ResultFormat::Sarif => {
+                let log: SarifLog = SarifLog::from_results(results);
+                if self.pretty {
+                    serde_json::to_writer_pretty(writer, &log)?;
+                } else {
+                    serde_json::to_writer(writer, &log)?;
+                }
+            }
+            ResultFormat::Ecs => self.ecs.write(writer, results, false)?,
+            ResultFormat::Hec => self.ecs.write(writer, results, true)?,
+        }
+        Ok(())
+    }
+}
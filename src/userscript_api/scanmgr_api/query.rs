@@ -0,0 +1,168 @@
+//! # Querying Scan Results
+//!
+//! Scan results tables can be filtered, grouped, sorted, and deduped
+//! without hand-written loops. Each query returns a new results table,
+//! which keeps the serializer and query methods:
+//!
+//! ```lua
+//! local results = scanmgr:scan()
+//! local serious = results:filter(function(result)
+//!     return result.severity == 'high' or result.severity == 'critical'
+//! end)
+//! print(serious:sort('score', true):csv(true))
+//! for engine, matches in pairs(results:by_engine()) do
+//!     print(engine, matches:count())
+//! end
+//! ```
+
+use crate::{
+    actors::user_engine::verdict::Severity,
+    userscript_api::{
+        fs_api::path_obj::PathObj, include::LuaError, scanmgr_api::scanresult::ScanResult,
+    },
+};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+/// A key to sort scan results by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Name of the scan engine that matched.
+    Engine,
+
+    /// Name of the data item.
+    Name,
+
+    /// Path of the data item.
+    Path,
+
+    /// Size of the data item, in bytes.
+    Size,
+
+    /// SHA-256 hash of the data item.
+    Hash,
+
+    /// Score given by the engine.
+    Score,
+
+    /// Severity given by the engine.
+    Severity,
+}
+
+impl SortKey {
+    /// Compare two scan results by this key.
+    ///
+    /// Results missing the key, such as those without a score, sort
+    /// after those that have it.
+    #[must_use]
+    pub fn compare(self, a: &ScanResult, b: &ScanResult) -> Ordering {
+        match self {
+            Self::Engine => a.engine.cmp(&b.engine),
+            Self::Name => a.item.name.cmp(&b.item.name),
+            Self::Path => {
+                let (a, b) = (a.item.path.as_ref(), b.item.path.as_ref());
+                missing_last(a, b, |a: &PathObj, b: &PathObj| a.0.cmp(&b.0))
+            }
+            Self::Size => a.item.size.cmp(&b.item.size),
+            Self::Hash => a.item.sha256.cmp(&b.item.sha256),
+            Self::Score => missing_last(a.verdict.score, b.verdict.score, |a: f64, b: f64| {
+                a.total_cmp(&b)
+            }),
+            Self::Severity => missing_last(
+                a.verdict.severity,
+                b.verdict.severity,
+                |a: Severity, b: Severity| a.cmp(&b),
+            ),
+        }
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = LuaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "engine" => Ok(Self::Engine),
+            "name" => Ok(Self::Name),
+            "path" => Ok(Self::Path),
+            "size" => Ok(Self::Size),
+            "hash" => Ok(Self::Hash),
+            "score" => Ok(Self::Score),
+            "severity" => Ok(Self::Severity),
+            other => Err(LuaError::runtime(format!(
+                "unknown sort key `{other}`; expected one of engine, name, path, size, \
+                 hash, score, or severity"
+            ))),
+        }
+    }
+}
+
+/// Compare optional values, ordering missing values last.
+fn missing_last<T>(a: Option<T>, b: Option<T>, cmp: impl Fn(T, T) -> Ordering) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => cmp(a, b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Stably sort scan results by `key`, optionally in descending order.
+///
+/// Results missing the key sort last either way.
+pub fn sort(results: &mut [ScanResult], key: SortKey, descending: bool) {
+    results.sort_by(|a: &ScanResult, b: &ScanResult| {
+        let ordering: Ordering = key.compare(a, b);
+        let missing = |result: &ScanResult| match key {
+            SortKey::Path => result.item.path.is_none(),
+            SortKey::Score => result.verdict.score.is_none(),
+            SortKey::Severity => result.verdict.severity.is_none(),
+            _ => false,
+        };
+        if descending && !missing(a) && !missing(b) {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+/// Group scan results by a key, keeping the order of first appearance
+/// of each group and of the results within it.
+///
+/// The results may be owned or borrowed, such as from a slice, and are
+/// grouped as given.
+#[must_use]
+pub fn group_by<T>(
+    results: impl IntoIterator<Item = T>,
+    key: impl Fn(&T) -> &str,
+) -> Vec<(String, Vec<T>)> {
+    let mut groups: Vec<(String, Vec<T>)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for result in results {
+        let name: &str = key(&result);
+        if let Some(&position) = positions.get(name) {
+            groups[position].1.push(result);
+        } else {
+            positions.insert(name.to_owned(), groups.len());
+            groups.push((name.to_owned(), vec![result]));
+        }
+    }
+    groups
+}
+
+/// Keep the first scan result for each data item, identified by its
+/// name and content hash.
+#[must_use]
+pub fn unique_items(results: Vec<ScanResult>) -> Vec<ScanResult> {
+    let mut seen: HashSet<(String, String)> = HashSet::new();
+    results
+        .into_iter()
+        .filter(|result: &ScanResult| {
+            seen.insert((result.item.name.clone(), result.item.sha256.clone()))
+        })
+        .collect()
+}
@@ -0,0 +1,410 @@
+//! # HTML and Markdown Reports of Scan Results
+//!
+//! Scan results can be rendered as a readable report, for people rather
+//! than tools:
+//!
+//! ```lua
+//! local results = scanmgr:scan()
+//! results:html('report.html')
+//! print(results:markdown())
+//! ```
+//!
+//! Both formats hold the same sections: summary counts, a table of
+//! matches per engine, details of each matched item, scan errors, and
+//! engine performance. HTML reports are a single self-contained page,
+//! with their styles inline.
+
+use crate::{
+    actors::{scanmgr::summary::ScanSummary, user_engine::verdict::Severity},
+    userscript_api::scanmgr_api::{query, scanresult::ScanResult},
+};
+use std::fmt::Write;
+
+/// Styles of HTML reports.
+const HTML_STYLE: &str = "
+body { font-family: system-ui, sans-serif; margin: 2em auto; max-width: 72em; color: #222; }
+h1, h2, h3 { color: #124; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { background: #eef; }
+";
+
+/// A block of report content, rendered as HTML or Markdown.
+enum Block {
+    /// A section heading, of level 1 to 3.
+    Heading(u8, String),
+
+    /// A paragraph of text.
+    Paragraph(String),
+
+    /// A bulleted list.
+    List(Vec<String>),
+
+    /// A table, with a header row.
+    Table(Vec<&'static str>, Vec<Vec<String>>),
+}
+
+/// A report of scan results, and the summary of their scan, if known.
+pub struct Report<'a> {
+    /// The scan results to report on.
+    results: &'a [ScanResult],
+
+    /// Summary of the scan, if the results came from one.
+    summary: Option<&'a ScanSummary>,
+}
+
+impl<'a> Report<'a> {
+    /// Report on scan results.
+    #[must_use]
+    pub fn new(results: &'a [ScanResult], summary: Option<&'a ScanSummary>) -> Self {
+        Self { results, summary }
+    }
+
+    /// Render the report as a self-contained HTML page.
+    #[must_use]
+    pub fn html(&self) -> String {
+        let mut html: String = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>sscan Scan Report</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n"
+        );
+        for block in self.blocks() {
+            match block {
+                Block::Heading(level, text) => {
+                    let _ = writeln!(html, "<h{level}>{}</h{level}>", escape_html(&text));
+                }
+                Block::Paragraph(text) => {
+                    let _ = writeln!(html, "<p>{}</p>", escape_html(&text));
+                }
+                Block::List(items) => {
+                    html.push_str("<ul>\n");
+                    for item in items {
+                        let _ = writeln!(html, "<li>{}</li>", escape_html(&item));
+                    }
+                    html.push_str("</ul>\n");
+                }
+                Block::Table(headers, rows) => {
+                    html.push_str("<table>\n<tr>");
+                    for header in headers {
+                        let _ = write!(html, "<th>{}</th>", escape_html(header));
+                    }
+                    html.push_str("</tr>\n");
+                    for row in rows {
+                        html.push_str("<tr>");
+                        for cell in row {
+                            let _ = write!(html, "<td>{}</td>", escape_html(&cell));
+                        }
+                        html.push_str("</tr>\n");
+                    }
+                    html.push_str("</table>\n");
+                }
+            }
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Render the report as Markdown.
+    #[must_use]
+    pub fn markdown(&self) -> String {
+        let mut markdown: String = String::new();
+        for block in self.blocks() {
+            if !markdown.is_empty() {
+                markdown.push('\n');
+            }
+            match block {
+                Block::Heading(level, text) => {
+                    let marks: String = "#".repeat(usize::from(level));
+                    let _ = writeln!(markdown, "{marks} {}", escape_markdown(&text));
+                }
+                Block::Paragraph(text) => {
+                    let _ = writeln!(markdown, "{}", escape_markdown(&text));
+                }
+                Block::List(items) => {
+                    for item in items {
+                        let _ = writeln!(markdown, "- {}", escape_markdown(&item));
+                    }
+                }
+                Block::Table(headers, rows) => {
+                    let _ = writeln!(markdown, "| {} |", headers.join(" | "));
+                    let _ = writeln!(markdown, "|{}", " --- |".repeat(headers.len()));
+                    for row in rows {
+                        let cells: Vec<String> = row
+                            .iter()
+                            .map(|cell: &String| escape_markdown(cell))
+                            .collect();
+                        let _ = writeln!(markdown, "| {} |", cells.join(" | "));
+                    }
+                }
+            }
+        }
+        markdown
+    }
+
+    /// The content of the report.
+    fn blocks(&self) -> Vec<Block> {
+        let mut blocks: Vec<Block> = vec![
+            Block::Heading(1, "sscan Scan Report".to_owned()),
+            Block::Paragraph(format!("Generated by sscan {}.", env!("CARGO_PKG_VERSION"))),
+        ];
+        self.summary_blocks(&mut blocks);
+        self.engine_blocks(&mut blocks);
+        self.item_blocks(&mut blocks);
+        self.error_blocks(&mut blocks);
+        self.performance_blocks(&mut blocks);
+        blocks
+    }
+
+    /// Summary counts of the scan and its matches.
+    fn summary_blocks(&self, blocks: &mut Vec<Block>) {
+        let engines: usize = query::group_by(self.results, |result| &result.engine).len();
+        let items: usize = query::group_by(self.results, |result| &result.item.name).len();
+        let unknown = || "-".to_owned();
+        let summary: Option<&ScanSummary> = self.summary;
+        blocks.push(Block::Heading(2, "Summary".to_owned()));
+        blocks.push(Block::Table(
+            vec![
+                "Items Scanned",
+                "Failed",
+                "Matches",
+                "Matched Items",
+                "Engines Matched",
+                "Elapsed (ms)",
+            ],
+            vec![vec![
+                summary.map_or_else(unknown, |summary| summary.items.to_string()),
+                summary.map_or_else(unknown, |summary| summary.failed.to_string()),
+                self.results.len().to_string(),
+                items.to_string(),
+                engines.to_string(),
+                summary.map_or_else(unknown, |summary| {
+                    format!("{:.3}", summary.elapsed.as_secs_f64() * 1000.0)
+                }),
+            ]],
+        ));
+
+        // Count matches by severity, most severe first
+        let severities: [Option<Severity>; 6] = [
+            Some(Severity::Critical),
+            Some(Severity::High),
+            Some(Severity::Medium),
+            Some(Severity::Low),
+            Some(Severity::Info),
+            None,
+        ];
+        let rows: Vec<Vec<String>> = severities
+            .iter()
+            .filter_map(|&severity: &Option<Severity>| {
+                let count: usize = self
+                    .results
+                    .iter()
+                    .filter(|result: &&ScanResult| result.verdict.severity == severity)
+                    .count();
+                (count > 0).then(|| {
+                    let name: &str = severity.map_or("none", Severity::as_str);
+                    vec![name.to_owned(), count.to_string()]
+                })
+            })
+            .collect();
+        if !rows.is_empty() {
+            blocks.push(Block::Table(vec!["Severity", "Matches"], rows));
+        }
+    }
+
+    /// A table of matches for each engine.
+    fn engine_blocks(&self, blocks: &mut Vec<Block>) {
+        blocks.push(Block::Heading(2, "Matches by Engine".to_owned()));
+        if self.results.is_empty() {
+            blocks.push(Block::Paragraph("No matches.".to_owned()));
+            return;
+        }
+        for (engine, results) in query::group_by(self.results, |result| &result.engine) {
+            blocks.push(Block::Heading(3, engine));
+            let rows: Vec<Vec<String>> = results
+                .into_iter()
+                .map(|result: &ScanResult| {
+                    vec![
+                        result.item.name.clone(),
+                        item_path(result),
+                        score(result),
+                        severity(result),
+                        result.verdict.reason.clone().unwrap_or_default(),
+                        result.verdict.tags.join(", "),
+                        offsets(result),
+                    ]
+                })
+                .collect();
+            blocks.push(Block::Table(
+                vec![
+                    "Item", "Path", "Score", "Severity", "Reason", "Tags", "Offsets",
+                ],
+                rows,
+            ));
+        }
+    }
+
+    /// Details of each matched item, and the engines that matched it.
+    fn item_blocks(&self, blocks: &mut Vec<Block>) {
+        if self.results.is_empty() {
+            return;
+        }
+        blocks.push(Block::Heading(2, "Matched Items".to_owned()));
+        for (name, results) in query::group_by(self.results, |result| &result.item.name) {
+            let item = &results[0].item;
+            blocks.push(Block::Heading(3, name));
+            let mut details: Vec<String> = Vec::new();
+            if item.path.is_some() {
+                details.push(format!("Path: {}", item_path(results[0])));
+            }
+            details.push(format!("Size: {} bytes", item.size));
+            details.push(format!("SHA-256: {}", item.sha256));
+            for (key, value) in &item.metadata {
+                let value: String = match value {
+                    serde_json::Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                details.push(format!("Metadata {key}: {value}"));
+            }
+            blocks.push(Block::List(details));
+            let rows: Vec<Vec<String>> = results
+                .into_iter()
+                .map(|result: &ScanResult| {
+                    vec![
+                        result.engine.clone(),
+                        score(result),
+                        severity(result),
+                        result.verdict.reason.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            blocks.push(Block::Table(
+                vec!["Engine", "Score", "Severity", "Reason"],
+                rows,
+            ));
+        }
+    }
+
+    /// Why data items failed to load or scan, if known.
+    fn error_blocks(&self, blocks: &mut Vec<Block>) {
+        let Some(summary) = self.summary else {
+            return;
+        };
+        blocks.push(Block::Heading(2, "Errors".to_owned()));
+        if summary.errors.is_empty() {
+            blocks.push(Block::Paragraph("No errors.".to_owned()));
+        } else {
+            blocks.push(Block::List(summary.errors.clone()));
+        }
+    }
+
+    /// How long each engine took during the scan, if known.
+    fn performance_blocks(&self, blocks: &mut Vec<Block>) {
+        let Some(summary) = self.summary else {
+            return;
+        };
+        if summary.engines.engines.is_empty() {
+            return;
+        }
+        let millis =
+            |duration: std::time::Duration| format!("{:.3}", duration.as_secs_f64() * 1000.0);
+        let rows: Vec<Vec<String>> = summary
+            .engines
+            .engines
+            .iter()
+            .map(|(name, stats)| {
+                vec![
+                    name.clone(),
+                    stats.invocations.to_string(),
+                    millis(stats.total),
+                    millis(stats.mean()),
+                    millis(stats.max),
+                    stats.bytes.to_string(),
+                ]
+            })
+            .collect();
+        blocks.push(Block::Heading(2, "Engine Performance".to_owned()));
+        blocks.push(Block::Table(
+            vec![
+                "Engine",
+                "Calls",
+                "Total (ms)",
+                "Mean (ms)",
+                "Max (ms)",
+                "Bytes",
+            ],
+            rows,
+        ));
+    }
+}
+
+/// The path of a result's item, or nothing if it has none.
+fn item_path(result: &ScanResult) -> String {
+    result
+        .item
+        .path
+        .as_ref()
+        .map(|path| path.0.display().to_string())
+        .unwrap_or_default()
+}
+
+/// The score given by a result's engine, if any.
+fn score(result: &ScanResult) -> String {
+    result
+        .verdict
+        .score
+        .map(|score: f64| score.to_string())
+        .unwrap_or_default()
+}
+
+/// The severity given by a result's engine, if any.
+fn severity(result: &ScanResult) -> String {
+    result
+        .verdict
+        .severity
+        .map(Severity::as_str)
+        .unwrap_or_default()
+        .to_owned()
+}
+
+/// The offsets given by a result's engine, if any.
+fn offsets(result: &ScanResult) -> String {
+    result
+        .verdict
+        .offsets
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Escape text for HTML element content and attribute values.
+fn escape_html(text: &str) -> String {
+    let mut escaped: String = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape text for Markdown, keeping it on one line so it stays inside
+/// its paragraph, list item, or table cell.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped: String = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '#' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\r' | '\n' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,326 @@
+//! # SARIF Serialization of Scan Results
+//!
+//! Scan results can be exported as a [SARIF 2.1.0] log, the format
+//! code scanning dashboards and many security tools ingest:
+//!
+//! ```lua
+//! local results = scanmgr:scan()
+//! local file = io.open('results.sarif', 'w')
+//! file:write(results:sarif())
+//! file:close()
+//! ```
+//!
+//! Each scan engine that matched becomes a rule of the `sscan` tool,
+//! and each match a result of that rule, located at the data item's
+//! path. Data items without a path, such as raw data or process memory,
+//! are located by name instead.
+//!
+//! [SARIF 2.1.0]: https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+
+use crate::{
+    actors::{
+        queue::data_item::Metadata,
+        user_engine::verdict::{Severity, Verdict},
+    },
+    userscript_api::scanmgr_api::scanresult::ScanResult,
+};
+use serde::Serialize;
+use std::{fmt::Write, path::Path};
+
+/// The JSON schema of SARIF 2.1.0 logs.
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Top-level SARIF log object.
+#[derive(Serialize, Debug)]
+pub struct SarifLog {
+    /// URI of the SARIF schema.
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+
+    /// SARIF format version.
+    version: &'static str,
+
+    /// A single run of the `sscan` tool.
+    runs: [Run; 1],
+}
+
+/// A single run of an analysis tool.
+#[derive(Serialize, Debug)]
+struct Run {
+    /// The tool that produced the results.
+    tool: Tool,
+
+    /// Every match found during the run.
+    results: Vec<SarifResult>,
+}
+
+/// Describes the analysis tool.
+#[derive(Serialize, Debug)]
+struct Tool {
+    /// The tool's main component.
+    driver: ToolComponent,
+}
+
+/// Describes `sscan` and the scan engines it ran.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ToolComponent {
+    /// Name of the tool.
+    name: &'static str,
+
+    /// Version of the tool.
+    version: &'static str,
+
+    /// Where to learn more about the tool.
+    information_uri: &'static str,
+
+    /// Each scan engine with a match, as a rule.
+    rules: Vec<ReportingDescriptor>,
+}
+
+/// Describes a scan engine, as a SARIF rule.
+#[derive(Serialize, Debug)]
+struct ReportingDescriptor {
+    /// Name of the scan engine, which identifies the rule.
+    id: String,
+
+    /// Name of the scan engine.
+    name: String,
+}
+
+/// A match of a scan engine against a data item.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    /// Name of the scan engine that matched.
+    rule_id: String,
+
+    /// Index of the scan engine in the tool's rules.
+    rule_index: usize,
+
+    /// Severity of the match, as `error`, `warning`, or `note`.
+    level: &'static str,
+
+    /// Explanation of the match.
+    message: Message,
+
+    /// Where the match was found.
+    locations: Vec<Location>,
+
+    /// Engine verdict details SARIF has no field for.
+    properties: ResultProperties,
+}
+
+/// A plain-text message.
+#[derive(Serialize, Debug)]
+struct Message {
+    /// Text of the message.
+    text: String,
+}
+
+/// Where a match was found.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    /// The file the match was found in, if the item has a path.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    physical_location: Option<PhysicalLocation>,
+
+    /// The data item the match was found in, if it has no path.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    logical_locations: Vec<LogicalLocation>,
+}
+
+/// A location in a file.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    /// The file.
+    artifact_location: ArtifactLocation,
+
+    /// Where in the file the match was found, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<Region>,
+}
+
+/// Identifies a file.
+#[derive(Serialize, Debug)]
+struct ArtifactLocation {
+    /// URI of the file.
+    uri: String,
+}
+
+/// Part of a file.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    /// Byte offset of the match from the start of the file.
+    byte_offset: u64,
+}
+
+/// Identifies a data item without a path.
+#[derive(Serialize, Debug)]
+struct LogicalLocation {
+    /// Name of the data item.
+    name: String,
+
+    /// Kind of location, always `resource`.
+    kind: &'static str,
+}
+
+/// Engine verdict details carried in a result's property bag.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ResultProperties {
+    /// Name of the data item.
+    item_name: String,
+
+    /// Score given by the engine.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<f64>,
+
+    /// Severity given by the engine.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<Severity>,
+
+    /// Labels given by the engine.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+
+    /// Extra information attached to the data item when enqueued.
+    #[serde(skip_serializing_if = "Metadata::is_empty")]
+    metadata: Metadata,
+}
+
+impl SarifLog {
+    /// Build a SARIF log from scan results.
+    #[must_use]
+    pub fn from_results(scan_results: &[ScanResult]) -> Self {
+        let mut rules: Vec<ReportingDescriptor> = Vec::new();
+        let mut results: Vec<SarifResult> = Vec::with_capacity(scan_results.len());
+        for scan_result in scan_results {
+            // Each engine becomes a rule the first time it matches.
+            let rule_index: usize = rules
+                .iter()
+                .position(|rule: &ReportingDescriptor| rule.id == scan_result.engine)
+                .unwrap_or_else(|| {
+                    rules.push(ReportingDescriptor {
+                        id: scan_result.engine.clone(),
+                        name: scan_result.engine.clone(),
+                    });
+                    rules.len() - 1
+                });
+            results.push(SarifResult::new(scan_result, rule_index));
+        }
+
+        Self {
+            schema: SARIF_SCHEMA,
+            version: "2.1.0",
+            runs: [Run {
+                tool: Tool {
+                    driver: ToolComponent {
+                        name: env!("CARGO_PKG_NAME"),
+                        version: env!("CARGO_PKG_VERSION"),
+                        information_uri: env!("CARGO_PKG_REPOSITORY"),
+                        rules,
+                    },
+                },
+                results,
+            }],
+        }
+    }
+}
+
+impl SarifResult {
+    /// Describe a scan result as a result of the rule at `rule_index`.
+    fn new(scan_result: &ScanResult, rule_index: usize) -> Self {
+        let verdict: &Verdict = &scan_result.verdict;
+        let text: String = verdict.reason.clone().unwrap_or_else(|| {
+            format!(
+                "scan engine `{}` matched `{}`",
+                scan_result.engine, scan_result.item.name
+            )
+        });
+        Self {
+            rule_id: scan_result.engine.clone(),
+            rule_index,
+            level: level(verdict.severity),
+            message: Message { text },
+            locations: locations(scan_result),
+            properties: ResultProperties {
+                item_name: scan_result.item.name.clone(),
+                score: verdict.score,
+                severity: verdict.severity,
+                tags: verdict.tags.clone(),
+                metadata: scan_result.item.metadata.clone(),
+            },
+        }
+    }
+}
+
+/// The SARIF level for an engine's severity.
+///
+/// Matches without a severity are warnings, the SARIF default.
+fn level(severity: Option<Severity>) -> &'static str {
+    match severity {
+        Some(Severity::Critical | Severity::High) => "error",
+        Some(Severity::Medium) | None => "warning",
+        Some(Severity::Low | Severity::Info) => "note",
+    }
+}
+
+/// Locate a match at each of its offsets into the data item.
+fn locations(scan_result: &ScanResult) -> Vec<Location> {
+    let Some(path) = &scan_result.item.path else {
+        return vec![Location {
+            physical_location: None,
+            logical_locations: vec![LogicalLocation {
+                name: scan_result.item.name.clone(),
+                kind: "resource",
+            }],
+        }];
+    };
+    let uri: String = artifact_uri(&path.0);
+    let physical = |region: Option<Region>| Location {
+        physical_location: Some(PhysicalLocation {
+            artifact_location: ArtifactLocation { uri: uri.clone() },
+            region,
+        }),
+        logical_locations: Vec::new(),
+    };
+    if scan_result.verdict.offsets.is_empty() {
+        return vec![physical(None)];
+    }
+    scan_result
+        .verdict
+        .offsets
+        .iter()
+        .map(|&byte_offset: &u64| physical(Some(Region { byte_offset })))
+        .collect()
+}
+
+/// Convert a path into a URI, as a `file://` URI if it is absolute.
+fn artifact_uri(path: &Path) -> String {
+    let mut path: String = path.to_string_lossy().into_owned();
+    if cfg!(windows) {
+        path = path.replace('\\', "/");
+    }
+
+    // Percent-encode every byte that may not appear in a URI path,
+    // keeping the colon of Windows drive letters.
+    let unreserved: &[u8] = if cfg!(windows) { b"-._~/:" } else { b"-._~/" };
+    let mut encoded: String = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || unreserved.contains(&byte) {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+
+    match encoded.chars().next() {
+        Some('/') => format!("file://{encoded}"),
+        _ if Path::new(&path).is_absolute() => format!("file:///{encoded}"),
+        _ => encoded,
+    }
+}
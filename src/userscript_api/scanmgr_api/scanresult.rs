@@ -5,17 +5,36 @@
 //!
 //! [`ScanMgr`]: super::ScanMgr
 
-use crate::userscript_api::{
-    fs_api::path_obj::PathObj,
-    include::{
-        Lua, LuaExternalError, LuaFunction, LuaResult, LuaTable, LuaTableSequence, LuaUserData,
-        LuaUserDataRef,
+use crate::{
+    actors::{
+        queue::data_item::Metadata,
+        scanmgr::summary::ScanSummary,
+        user_engine::verdict::{Severity, Verdict},
+    },
+    userscript_api::{
+        fs_api::path_obj::PathObj,
+        include::{
+            Lua, LuaError, LuaExternalError, LuaFunction, LuaResult, LuaSerdeExt, LuaTable,
+            LuaTableSequence, LuaUserData, LuaUserDataRef, LuaValue,
+        },
+        scanmgr_api::{
+            csv::CsvOptions,
+            ecs::EcsOptions,
+            junit::{self, JunitOptions},
+            output::{OutputOptions, ResultFormat},
+            query::{self, SortKey},
+            report::Report,
+            sarif::SarifLog,
+            store::{ResultStore, StoreOptions},
+        },
     },
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fmt::Write, path::PathBuf, sync::Arc, time::SystemTime};
 
 /// Root return type for scan results.
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ScanResult {
     /// Name of the engine that matched a [`DataItem`]
     ///
@@ -26,77 +45,133 @@ pub struct ScanResult {
     ///
     /// [`DataItem`]: crate::actors::queue::data_item::DataItem
     pub item: DataItemResult,
+
+    /// Match details returned by the engine, if any.
+    #[serde(flatten)]
+    pub verdict: Verdict,
 }
 
 impl LuaUserData for ScanResult {
     fn add_fields<F: mlua::UserDataFields<Self>>(fields: &mut F) {
         fields.add_field_method_get("engine", |_, this: &ScanResult| Ok(this.engine.clone()));
         fields.add_field_method_get("item", |_, this: &ScanResult| Ok(this.item.clone()));
+        fields.add_field_method_get("score", |_, this: &ScanResult| Ok(this.verdict.score));
+        fields.add_field_method_get("severity", |_, this: &ScanResult| {
+            Ok(this.verdict.severity.map(Severity::as_str))
+        });
+        fields.add_field_method_get("reason", |_, this: &ScanResult| {
+            Ok(this.verdict.reason.clone())
+        });
+        fields.add_field_method_get("offsets", |_, this: &ScanResult| {
+            Ok(this.verdict.offsets.clone())
+        });
+        fields.add_field_method_get("tags", |_, this: &ScanResult| Ok(this.verdict.tags.clone()));
     }
 }
 
 /// Describes a [`DataItem`] match against a scan engine.
 ///
 /// [`DataItem`]: crate::actors::queue::data_item::DataItem
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DataItemResult {
     /// Name of the data item.
     pub name: String,
 
     /// Path of the data item, if applicable.
     pub path: Option<PathObj>,
+
+    /// Size of the data item's content, in bytes.
+    #[serde(default)]
+    pub size: usize,
+
+    /// Lowercase hex SHA-256 hash of the data item's content.
+    #[serde(default)]
+    pub sha256: String,
+
+    /// Extra information attached to the data item when enqueued.
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub metadata: Metadata,
 }
 
 impl LuaUserData for DataItemResult {
     fn add_fields<F: mlua::UserDataFields<Self>>(fields: &mut F) {
         fields.add_field_method_get("name", |_, this: &DataItemResult| Ok(this.name.clone()));
         fields.add_field_method_get("path", |_, this: &DataItemResult| Ok(this.path.clone()));
+        fields.add_field_method_get("size", |_, this: &DataItemResult| Ok(this.size));
+        fields.add_field_method_get("sha256", |_, this: &DataItemResult| Ok(this.sha256.clone()));
+        fields.add_field_method_get("metadata", |lua: &Lua, this: &DataItemResult| {
+            lua.to_value(&this.metadata)
+        });
     }
 }
 
-/// Add a `csv()` method to the scan results table.
-pub(super) async fn add_csv_method(lua: &Lua, results: &LuaTable) -> LuaResult<()> {
-    let csv_method: LuaFunction =
-        lua.create_async_function(|_, (this, headers): (LuaTable, Option<bool>)| async move {
-            // Create an iterator over the ScanResult table.
-            let mut scan_results: LuaTableSequence<'_, LuaUserDataRef<ScanResult>> =
-                this.sequence_values::<LuaUserDataRef<ScanResult>>();
-
-            // This vector stores the CSV rows for serialization.
-            let mut rows: Vec<String> = Vec::with_capacity(
-                usize::try_from(this.len()?).map_err(LuaExternalError::into_lua_err)? + 1,
-            );
-
-            // If headers is true, add headers.
-            if headers.is_some_and(|headers: bool| headers) {
-                let headers: String = r#""Scan Engine","Item Name","Item Path""#.to_string();
-                rows.push(headers);
-            }
+impl DataItemResult {
+    /// Describe a data item, hashing its `content`.
+    #[must_use]
+    pub fn new(name: String, path: Option<PathObj>, content: &[u8], metadata: Metadata) -> Self {
+        let sha256: String = Sha256::digest(content).iter().fold(
+            String::with_capacity(64),
+            |mut hex: String, byte: &u8| {
+                let _ = write!(hex, "{byte:02x}");
+                hex
+            },
+        );
+        Self {
+            name,
+            path,
+            size: content.len(),
+            sha256,
+            metadata,
+        }
+    }
+}
 
-            // Serialize each row to CSV
-            while let Some(Ok(scan_result)) = scan_results.next() {
-                let row: String = format!(
-                    r#""{}","{}","{}""#,
-                    scan_result.engine,
-                    scan_result.item.name,
-                    scan_result
-                        .item
-                        .path
-                        .clone()
-                        .unwrap_or_default()
-                        .0
-                        .to_string_lossy()
-                );
-                rows.push(row);
-            }
+/// Build a scan results table, with the serializer and query methods.
+///
+/// Tables returned by queries are built the same way, so they keep
+/// every method, including the summary of the scan, if there is one,
+/// which they share rather than copy. Results loaded from a file have
+/// no summary.
+pub(super) fn results_table(
+    lua: &Lua,
+    results: Vec<ScanResult>,
+    summary: Option<Arc<ScanSummary>>,
+) -> LuaResult<LuaTable> {
+    let table: LuaTable = lua.create_table_with_capacity(results.len(), 0)?;
+    for result in results {
+        table.push(result)?;
+    }
+
+    // Register result formatting methods
+    add_csv_method(lua, &table)?;
+    add_json_method(lua, &table)?;
+    add_ndjson_method(lua, &table)?;
+    add_sarif_method(lua, &table)?;
+    add_ecs_methods(lua, &table, summary.as_ref())?;
+    add_write_method(lua, &table, summary.clone())?;
+    add_store_method(lua, &table, summary.clone())?;
+    add_report_methods(lua, &table, summary.clone())?;
+    add_junit_method(lua, &table, summary.clone())?;
 
-            // Concat the rows vector to produce the final CSV.
-            // Append a blank line at the end.
-            let mut csv: String = rows.join("\n");
-            csv.push('\n');
+    // Register result query methods
+    add_query_methods(lua, &table, summary.as_ref())?;
+    if let Some(summary) = summary {
+        add_summary_method(lua, &table, summary)?;
+    }
+    Ok(table)
+}
+
+/// Add a `csv()` method to the scan results table.
+///
+/// Every record, including the last, ends with a line feed.
+fn add_csv_method(lua: &Lua, results: &LuaTable) -> LuaResult<()> {
+    let csv_method: LuaFunction =
+        lua.create_async_function(|_, (this, opts): (LuaTable, LuaValue)| async move {
+            let opts: CsvOptions = CsvOptions::from_lua(opts)?;
 
             // Return the CSV-serialized results.
-            Ok(csv)
+            let rows: Vec<ScanResult> = collect_results(&this)?;
+            Ok(opts.serialize(&rows))
         })?;
 
     // Add the CSV method to the results table.
@@ -105,75 +180,292 @@ pub(super) async fn add_csv_method(lua: &Lua, results: &LuaTable) -> LuaResult<(
 }
 
 /// Add a `json()` method to the scan results table.
-pub(super) async fn add_json_method(lua: &Lua, results: &LuaTable) -> LuaResult<()> {
+fn add_json_method(lua: &Lua, results: &LuaTable) -> LuaResult<()> {
     let json_method: LuaFunction =
         lua.create_async_function(|_, (this, pretty): (LuaTable, Option<bool>)| async move {
-            // Create an iterator over the ScanResult table
-            let mut scan_results: LuaTableSequence<'_, LuaUserDataRef<ScanResult>> =
-                this.sequence_values::<LuaUserDataRef<ScanResult>>();
-
-            // This vector stores the JSON objects for serialization.
-            let mut rows: Vec<ScanResult> = Vec::with_capacity(
-                usize::try_from(this.len()?).map_err(LuaExternalError::into_lua_err)?,
-            );
-
-            // Clone all ScanResults into the Vec
-            while let Some(Ok(scan_result)) = scan_results.next() {
-                let result: ScanResult = ScanResult {
-                    engine: scan_result.engine.clone(),
-                    item: scan_result.item.clone(),
-                };
-                rows.push(result);
-            }
+            // Serialize to JSON, as `write()` would
+            let opts: OutputOptions = OutputOptions {
+                format: ResultFormat::Json,
+                pretty: pretty.is_some_and(|pretty: bool| pretty),
+                ..OutputOptions::default()
+            };
+            opts.serialize(&collect_results(&this)?)
+                .map_err(LuaExternalError::into_lua_err)
+        })?;
+
+    results.set("json", json_method)?;
+    Ok(())
+}
+
+/// Add a `ndjson()` method to the scan results table.
+///
+/// Every line, including the last, ends with a line feed.
+fn add_ndjson_method(lua: &Lua, results: &LuaTable) -> LuaResult<()> {
+    let ndjson_method: LuaFunction = lua.create_async_function(|_, this: LuaTable| async move {
+        // Serialize to NDJSON, as `write()` would
+        let opts: OutputOptions = OutputOptions {
+            format: ResultFormat::Ndjson,
+            ..OutputOptions::default()
+        };
+        opts.serialize(&collect_results(&this)?)
+            .map_err(LuaExternalError::into_lua_err)
+    })?;
+
+    results.set("ndjson", ndjson_method)?;
+    Ok(())
+}
 
-            // Serialize to JSON
+/// Add a `sarif()` method to the scan results table.
+fn add_sarif_method(lua: &Lua, results: &LuaTable) -> LuaResult<()> {
+    let sarif_method: LuaFunction =
+        lua.create_async_function(|_, (this, pretty): (LuaTable, Option<bool>)| async move {
+            // Serialize to a SARIF log
+            let log: SarifLog = SarifLog::from_results(&collect_results(&this)?);
             let serialized: String = if pretty.is_some_and(|pretty: bool| pretty) {
-                serde_json::to_string_pretty(&rows)
+                serde_json::to_string_pretty(&log)
             } else {
-                serde_json::to_string(&rows)
+                serde_json::to_string(&log)
             }
             .map_err(LuaExternalError::into_lua_err)?;
             Ok(serialized)
         })?;
 
-    results.set("json", json_method)?;
+    results.set("sarif", sarif_method)?;
     Ok(())
 }
 
-/// Add a `ndjson()` method to the scan results table.
-pub(super) async fn add_ndjson_method(lua: &Lua, results: &LuaTable) -> LuaResult<()> {
-    let json_method: LuaFunction = lua.create_async_function(|_, this: LuaTable| async move {
-        // Create an iterator over the ScanResult table
-        let mut scan_results: LuaTableSequence<'_, LuaUserDataRef<ScanResult>> =
-            this.sequence_values::<LuaUserDataRef<ScanResult>>();
-
-        // This vector stores the JSON objects for serialization.
-        let mut rows: Vec<ScanResult> = Vec::with_capacity(
-            usize::try_from(this.len()?).map_err(LuaExternalError::into_lua_err)?,
-        );
+/// Add the `ecs()` and `hec()` methods to the scan results table.
+fn add_ecs_methods(
+    lua: &Lua,
+    results: &LuaTable,
+    summary: Option<&Arc<ScanSummary>>,
+) -> LuaResult<()> {
+    let time: Option<SystemTime> = summary.map(|summary| summary.started);
+    for (name, hec) in [("ecs", false), ("hec", true)] {
+        let method: LuaFunction =
+            lua.create_function(move |_, (this, opts): (LuaTable, LuaValue)| {
+                let options: EcsOptions = EcsOptions {
+                    time,
+                    ..EcsOptions::from_lua(opts)?
+                };
+                let rows: Vec<ScanResult> = collect_results(&this)?;
+                options
+                    .serialize(&rows, hec)
+                    .map_err(LuaExternalError::into_lua_err)
+            })?;
+        results.set(name, method)?;
+    }
+    Ok(())
+}
+
+/// Add a `write()` method to the scan results table.
+fn add_write_method(
+    lua: &Lua,
+    results: &LuaTable,
+    summary: Option<Arc<ScanSummary>>,
+) -> LuaResult<()> {
+    let time: Option<SystemTime> = summary.map(|summary| summary.started);
+    let write_method: LuaFunction = lua.create_async_function(
+        move |_, (this, path, opts): (LuaTable, PathBuf, Option<LuaTable>)| async move {
+            let mut options: OutputOptions = OutputOptions::from_lua(&path, opts)?;
+            options.ecs.time = time;
+            let rows: Vec<ScanResult> = collect_results(&this)?;
+            options.write(&path, &rows).map_err(|err: std::io::Error| {
+                LuaError::runtime(format!(
+                    "failed to write scan results to `{}`: {err}",
+                    path.display()
+                ))
+            })
+        },
+    )?;
 
-        // Clone all ScanResults into the Vec
-        while let Some(Ok(scan_result)) = scan_results.next() {
-            let result: ScanResult = ScanResult {
-                engine: scan_result.engine.clone(),
-                item: scan_result.item.clone(),
+    results.set("write", write_method)?;
+    Ok(())
+}
+
+/// Add a `store()` method to the scan results table.
+fn add_store_method(
+    lua: &Lua,
+    results: &LuaTable,
+    summary: Option<Arc<ScanSummary>>,
+) -> LuaResult<()> {
+    let store_method: LuaFunction = lua.create_function(
+        move |_, (this, path, opts): (LuaTable, PathBuf, Option<LuaTable>)| {
+            let options: StoreOptions = StoreOptions::from_lua(opts)?;
+            let rows: Vec<ScanResult> = collect_results(&this)?;
+            ResultStore::open(&path)
+                .and_then(|mut store: ResultStore| store.store(&rows, summary.as_deref(), &options))
+                .map_err(|err: rusqlite::Error| {
+                    LuaError::runtime(format!(
+                        "failed to store scan results in `{}`: {err}",
+                        path.display()
+                    ))
+                })
+        },
+    )?;
+
+    results.set("store", store_method)?;
+    Ok(())
+}
+
+/// Add the `html()` and `markdown()` report methods to the scan results
+/// table.
+fn add_report_methods(
+    lua: &Lua,
+    results: &LuaTable,
+    summary: Option<Arc<ScanSummary>>,
+) -> LuaResult<()> {
+    let scan: Option<Arc<ScanSummary>> = summary.clone();
+    let html_method: LuaFunction = lua.create_function(
+        move |_, (this, path): (LuaTable, Option<PathBuf>)| -> LuaResult<Option<String>> {
+            let rows: Vec<ScanResult> = collect_results(&this)?;
+            let html: String = Report::new(&rows, scan.as_deref()).html();
+            let Some(path) = path else {
+                return Ok(Some(html));
             };
-            rows.push(result);
-        }
+            std::fs::write(&path, html).map_err(|err: std::io::Error| {
+                LuaError::runtime(format!(
+                    "failed to write report to `{}`: {err}",
+                    path.display()
+                ))
+            })?;
+            Ok(None)
+        },
+    )?;
+    results.set("html", html_method)?;
 
-        // This vector stores the serialized NDJSON objects.
-        let mut ndjson: Vec<String> = Vec::with_capacity(rows.len());
-        for row in rows {
-            let serialized: String =
-                serde_json::to_string(&row).map_err(LuaExternalError::into_lua_err)?;
-            ndjson.push(serialized);
-        }
+    let markdown_method: LuaFunction = lua.create_function(move |_, this: LuaTable| {
+        let rows: Vec<ScanResult> = collect_results(&this)?;
+        Ok(Report::new(&rows, summary.as_deref()).markdown())
+    })?;
+    results.set("markdown", markdown_method)?;
+    Ok(())
+}
+
+/// Add a `junit()` method to the scan results table.
+fn add_junit_method(
+    lua: &Lua,
+    results: &LuaTable,
+    summary: Option<Arc<ScanSummary>>,
+) -> LuaResult<()> {
+    let junit_method: LuaFunction =
+        lua.create_function(move |_, (this, opts): (LuaTable, LuaValue)| {
+            let options: JunitOptions = JunitOptions::from_lua(opts)?;
+            let rows: Vec<ScanResult> = collect_results(&this)?;
+            Ok(junit::serialize(&rows, summary.as_deref(), options))
+        })?;
 
-        // Combine all NDJSON objects into a string.
-        let serialized: String = ndjson.join("\n");
-        Ok(serialized)
+    results.set("junit", junit_method)?;
+    Ok(())
+}
+
+/// Add the `filter()`, `by_engine()`, `by_item()`, `sort()`,
+/// `unique_items()`, and `count()` methods to the scan results table.
+fn add_query_methods(
+    lua: &Lua,
+    results: &LuaTable,
+    summary: Option<&Arc<ScanSummary>>,
+) -> LuaResult<()> {
+    let scan: Option<Arc<ScanSummary>> = summary.cloned();
+    let filter_method: LuaFunction = lua.create_function(
+        move |lua: &Lua, (this, predicate): (LuaTable, LuaFunction)| {
+            let mut kept: Vec<ScanResult> = Vec::new();
+            for result in collect_results(&this)? {
+                if predicate.call::<bool>(result.clone())? {
+                    kept.push(result);
+                }
+            }
+            results_table(lua, kept, scan.clone())
+        },
+    )?;
+    results.set("filter", filter_method)?;
+
+    let scan: Option<Arc<ScanSummary>> = summary.cloned();
+    let by_engine_method: LuaFunction = lua.create_function(move |lua: &Lua, this: LuaTable| {
+        let groups = query::group_by(collect_results(&this)?, |result: &ScanResult| {
+            &result.engine
+        });
+        groups_table(lua, groups, scan.as_ref())
+    })?;
+    results.set("by_engine", by_engine_method)?;
+
+    let scan: Option<Arc<ScanSummary>> = summary.cloned();
+    let by_item_method: LuaFunction = lua.create_function(move |lua: &Lua, this: LuaTable| {
+        let groups = query::group_by(collect_results(&this)?, |result: &ScanResult| {
+            &result.item.name
+        });
+        groups_table(lua, groups, scan.as_ref())
     })?;
+    results.set("by_item", by_item_method)?;
+
+    let scan: Option<Arc<ScanSummary>> = summary.cloned();
+    let sort_method: LuaFunction = lua.create_function(
+        move |lua: &Lua, (this, key, descending): (LuaTable, String, Option<bool>)| {
+            let key: SortKey = key.parse()?;
+            let mut sorted: Vec<ScanResult> = collect_results(&this)?;
+            query::sort(&mut sorted, key, descending.unwrap_or_default());
+            results_table(lua, sorted, scan.clone())
+        },
+    )?;
+    results.set("sort", sort_method)?;
+
+    let scan: Option<Arc<ScanSummary>> = summary.cloned();
+    let unique_items_method: LuaFunction =
+        lua.create_function(move |lua: &Lua, this: LuaTable| {
+            let unique: Vec<ScanResult> = query::unique_items(collect_results(&this)?);
+            results_table(lua, unique, scan.clone())
+        })?;
+    results.set("unique_items", unique_items_method)?;
+
+    let count_method: LuaFunction =
+        lua.create_function(|_, (this, predicate): (LuaTable, Option<LuaFunction>)| {
+            let results: Vec<ScanResult> = collect_results(&this)?;
+            let Some(predicate) = predicate else {
+                return Ok(results.len());
+            };
+            let mut count: usize = 0;
+            for result in results {
+                if predicate.call::<bool>(result)? {
+                    count += 1;
+                }
+            }
+            Ok(count)
+        })?;
+    results.set("count", count_method)?;
+    Ok(())
+}
+
+/// Convert groups of scan results into a table of results tables,
+/// keyed by group name.
+fn groups_table(
+    lua: &Lua,
+    groups: Vec<(String, Vec<ScanResult>)>,
+    summary: Option<&Arc<ScanSummary>>,
+) -> LuaResult<LuaTable> {
+    let table: LuaTable = lua.create_table_with_capacity(0, groups.len())?;
+    for (name, results) in groups {
+        table.set(name, results_table(lua, results, summary.cloned())?)?;
+    }
+    Ok(table)
+}
 
-    results.set("ndjson", json_method)?;
+/// Add a `summary()` method to the scan results table.
+fn add_summary_method(lua: &Lua, results: &LuaTable, summary: Arc<ScanSummary>) -> LuaResult<()> {
+    let summary_method: LuaFunction =
+        lua.create_function(move |_, _: LuaTable| Ok(ScanSummary::clone(&summary)))?;
+    results.set("summary", summary_method)?;
     Ok(())
 }
+
+/// Clone the scan results out of a scan results table.
+pub(super) fn collect_results(results: &LuaTable) -> LuaResult<Vec<ScanResult>> {
+    let mut scan_results: LuaTableSequence<'_, LuaUserDataRef<ScanResult>> =
+        results.sequence_values::<LuaUserDataRef<ScanResult>>();
+    let mut rows: Vec<ScanResult> = Vec::with_capacity(
+        usize::try_from(results.len()?).map_err(LuaExternalError::into_lua_err)?,
+    );
+    while let Some(Ok(scan_result)) = scan_results.next() {
+        rows.push(scan_result.clone());
+    }
+    Ok(rows)
+}
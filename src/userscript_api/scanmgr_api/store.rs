@@ -0,0 +1,400 @@
+//! # `SQLite` Store of Historical Scans
+//!
+//! Rather than stitching NDJSON files together, scan results can be kept
+//! in an `SQLite` database, one row per scan, engine, item, and match:
+//!
+//! ```lua
+//! local results = scanmgr:scan()
+//! results:store('scans.db', {scan_id = 'nightly-2025-01-31', host = 'web01'})
+//! ```
+//!
+//! Past scans and findings can then be listed with `sscan query`, or
+//! queried with any `SQLite` client. The schema is:
+//!
+//! - `scans`: each stored scan, by `scan_id`, with its host, the time it
+//!   was stored, and its summary, if known.
+//! - `engines`: each scan engine that matched, by name.
+//! - `items`: each data item that matched, by name, path, and hash.
+//! - `matches`: each match of an engine against an item during a scan,
+//!   with the engine's verdict.
+
+use crate::{
+    actors::{
+        queue::data_item::Metadata,
+        scanmgr::summary::ScanSummary,
+        user_engine::verdict::{Severity, Verdict},
+    },
+    userscript_api::{
+        fs_api::path_obj::PathObj,
+        include::{LuaResult, LuaTable},
+        scanmgr_api::scanresult::{DataItemResult, ScanResult},
+    },
+};
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+use serde::Serialize;
+use std::{collections::HashMap, path::Path};
+
+/// Creates the store's tables, if they do not exist yet.
+const SCHEMA: &str = "
+    PRAGMA foreign_keys = ON;
+    CREATE TABLE IF NOT EXISTS scans (
+        id INTEGER PRIMARY KEY,
+        scan_id TEXT NOT NULL UNIQUE,
+        host TEXT,
+        stored_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        items INTEGER,
+        failed INTEGER,
+        elapsed_ms REAL
+    );
+    CREATE TABLE IF NOT EXISTS engines (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE IF NOT EXISTS items (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        path TEXT,
+        size INTEGER NOT NULL,
+        sha256 TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS items_by_hash ON items (sha256);
+    CREATE TABLE IF NOT EXISTS matches (
+        id INTEGER PRIMARY KEY,
+        scan INTEGER NOT NULL REFERENCES scans (id) ON DELETE CASCADE,
+        engine INTEGER NOT NULL REFERENCES engines (id),
+        item INTEGER NOT NULL REFERENCES items (id),
+        score REAL,
+        severity TEXT,
+        reason TEXT,
+        offsets TEXT NOT NULL DEFAULT '[]',
+        tags TEXT NOT NULL DEFAULT '[]',
+        metadata TEXT NOT NULL DEFAULT '{}'
+    );
+    CREATE INDEX IF NOT EXISTS matches_by_scan ON matches (scan);
+    CREATE INDEX IF NOT EXISTS matches_by_engine ON matches (engine);
+";
+
+/// Options for storing scan results.
+#[derive(Debug, Clone, Default)]
+pub struct StoreOptions {
+    /// Identifies the scan. Results stored under an existing scan ID are
+    /// added to that scan. Defaults to `scan-<n>`, for the next `n`.
+    pub scan_id: Option<String>,
+
+    /// The host the scan ran on.
+    pub host: Option<String>,
+}
+
+impl StoreOptions {
+    /// Parse the options from a `results:store()` options table.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if an option has the wrong type.
+    pub fn from_lua(opts: Option<LuaTable>) -> LuaResult<Self> {
+        let Some(opts) = opts else {
+            return Ok(Self::default());
+        };
+        Ok(Self {
+            scan_id: opts.get("scan_id")?,
+            host: opts.get("host")?,
+        })
+    }
+}
+
+/// A scan kept in the store.
+#[derive(Serialize, Debug, Clone)]
+pub struct StoredScan {
+    /// Identifies the scan.
+    pub scan_id: String,
+
+    /// The host the scan ran on, if known.
+    pub host: Option<String>,
+
+    /// When the scan was stored, as a UTC `YYYY-MM-DD HH:MM:SS` time.
+    pub stored_at: String,
+
+    /// Number of data items scanned, if known.
+    pub items: Option<u64>,
+
+    /// Number of data items that failed to load or scan, if known.
+    pub failed: Option<u64>,
+
+    /// Number of matches stored for the scan.
+    pub matches: u64,
+
+    /// Wall-clock time the scan took, if known.
+    pub elapsed_ms: Option<f64>,
+}
+
+/// A match kept in the store.
+#[derive(Serialize, Debug, Clone)]
+pub struct StoredFinding {
+    /// Identifies the scan the match was found in.
+    pub scan_id: String,
+
+    /// The match, as a scan result.
+    #[serde(flatten)]
+    pub result: ScanResult,
+}
+
+/// Narrows the findings listed from the store.
+#[derive(Debug, Clone, Default)]
+pub struct FindingFilter {
+    /// Only list matches found during this scan.
+    pub scan_id: Option<String>,
+
+    /// Only list matches of this scan engine.
+    pub engine: Option<String>,
+}
+
+/// An `SQLite` database of scans and their results.
+pub struct ResultStore {
+    /// Connection to the database.
+    conn: Connection,
+}
+
+impl ResultStore {
+    /// Open the store at `path`, creating it if it does not exist.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the database cannot be opened or its tables created.
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let conn: Connection = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Store scan results in a single transaction, returning the scan ID
+    /// they were stored under.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the results cannot be written to the database.
+    pub fn store(
+        &mut self,
+        results: &[ScanResult],
+        summary: Option<&ScanSummary>,
+        options: &StoreOptions,
+    ) -> rusqlite::Result<String> {
+        let tx: Transaction<'_> = self.conn.transaction()?;
+        let (scan, scan_id): (i64, String) = upsert_scan(&tx, options)?;
+
+        // Add the summary to any already stored under the scan ID
+        if let Some(summary) = summary {
+            tx.execute(
+                "UPDATE scans SET
+                    items = COALESCE(items, 0) + ?2,
+                    failed = COALESCE(failed, 0) + ?3,
+                    elapsed_ms = COALESCE(elapsed_ms, 0) + ?4
+                 WHERE id = ?1",
+                params![
+                    scan,
+                    to_sql_int(summary.items),
+                    to_sql_int(summary.failed),
+                    summary.elapsed.as_secs_f64() * 1000.0,
+                ],
+            )?;
+        }
+
+        // Store each match, reusing engine and item rows
+        let mut engines: HashMap<&str, i64> = HashMap::new();
+        let mut items: HashMap<(&str, Option<String>, &str), i64> = HashMap::new();
+        for result in results {
+            let engine: i64 = if let Some(&engine) = engines.get(result.engine.as_str()) {
+                engine
+            } else {
+                let engine: i64 = upsert_engine(&tx, &result.engine)?;
+                engines.insert(&result.engine, engine);
+                engine
+            };
+            let key = (
+                result.item.name.as_str(),
+                item_path(&result.item),
+                result.item.sha256.as_str(),
+            );
+            let item: i64 = if let Some(&item) = items.get(&key) {
+                item
+            } else {
+                let item: i64 = upsert_item(&tx, &result.item)?;
+                items.insert(key, item);
+                item
+            };
+            let verdict: &Verdict = &result.verdict;
+            tx.execute(
+                "INSERT INTO matches
+                    (scan, engine, item, score, severity, reason, offsets, tags, metadata)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    scan,
+                    engine,
+                    item,
+                    verdict.score,
+                    verdict.severity.map(Severity::as_str),
+                    verdict.reason,
+                    serde_json::to_string(&verdict.offsets).unwrap_or_default(),
+                    serde_json::to_string(&verdict.tags).unwrap_or_default(),
+                    serde_json::to_string(&result.item.metadata).unwrap_or_default(),
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(scan_id)
+    }
+
+    /// List the stored scans, oldest first.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the database cannot be read.
+    pub fn scans(&self) -> rusqlite::Result<Vec<StoredScan>> {
+        let mut statement = self.conn.prepare(
+            "SELECT scans.scan_id, scans.host, scans.stored_at, scans.items,
+                    scans.failed, COUNT(matches.id), scans.elapsed_ms
+             FROM scans LEFT JOIN matches ON matches.scan = scans.id
+             GROUP BY scans.id
+             ORDER BY scans.id",
+        )?;
+        let scans = statement.query_map([], |row: &Row<'_>| {
+            Ok(StoredScan {
+                scan_id: row.get(0)?,
+                host: row.get(1)?,
+                stored_at: row.get(2)?,
+                items: row.get(3)?,
+                failed: row.get(4)?,
+                matches: row.get(5)?,
+                elapsed_ms: row.get(6)?,
+            })
+        })?;
+        scans.collect()
+    }
+
+    /// List the stored matches, oldest scan first, in the order they
+    /// were stored.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the database cannot be read.
+    pub fn findings(&self, filter: &FindingFilter) -> rusqlite::Result<Vec<StoredFinding>> {
+        let mut statement = self.conn.prepare(
+            "SELECT scans.scan_id, engines.name, items.name, items.path, items.size,
+                    items.sha256, matches.score, matches.severity, matches.reason,
+                    matches.offsets, matches.tags, matches.metadata
+             FROM matches
+             JOIN scans ON scans.id = matches.scan
+             JOIN engines ON engines.id = matches.engine
+             JOIN items ON items.id = matches.item
+             WHERE (?1 IS NULL OR scans.scan_id = ?1)
+               AND (?2 IS NULL OR engines.name = ?2)
+             ORDER BY scans.id, matches.id",
+        )?;
+        let findings =
+            statement.query_map(params![filter.scan_id, filter.engine], |row: &Row<'_>| {
+                let json = |index: usize| -> rusqlite::Result<String> { row.get(index) };
+                let metadata: Metadata = serde_json::from_str(&json(11)?).unwrap_or_default();
+                Ok(StoredFinding {
+                    scan_id: row.get(0)?,
+                    result: ScanResult {
+                        engine: row.get(1)?,
+                        item: DataItemResult {
+                            name: row.get(2)?,
+                            path: row
+                                .get::<_, Option<String>>(3)?
+                                .map(|path: String| PathObj(path.into())),
+                            size: usize::try_from(row.get::<_, i64>(4)?).unwrap_or_default(),
+                            sha256: row.get(5)?,
+                            metadata,
+                        },
+                        verdict: Verdict {
+                            score: row.get(6)?,
+                            severity: row
+                                .get::<_, Option<String>>(7)?
+                                .and_then(|severity: String| severity.parse().ok()),
+                            reason: row.get(8)?,
+                            offsets: serde_json::from_str(&json(9)?).unwrap_or_default(),
+                            tags: serde_json::from_str(&json(10)?).unwrap_or_default(),
+                        },
+                    },
+                })
+            })?;
+        findings.collect()
+    }
+}
+
+/// Find or create the scan row for the options' scan ID, returning its
+/// row ID and scan ID.
+fn upsert_scan(tx: &Transaction<'_>, options: &StoreOptions) -> rusqlite::Result<(i64, String)> {
+    let scan_id: String = if let Some(scan_id) = &options.scan_id {
+        scan_id.clone()
+    } else {
+        let next: i64 = tx.query_row("SELECT COALESCE(MAX(id), 0) + 1 FROM scans", [], |row| {
+            row.get(0)
+        })?;
+        format!("scan-{next}")
+    };
+    let existing: Option<i64> = tx
+        .query_row(
+            "SELECT id FROM scans WHERE scan_id = ?1",
+            [&scan_id],
+            |row: &Row<'_>| row.get(0),
+        )
+        .optional()?;
+    let scan: i64 = if let Some(scan) = existing {
+        tx.execute(
+            "UPDATE scans SET host = COALESCE(host, ?2) WHERE id = ?1",
+            params![scan, options.host],
+        )?;
+        scan
+    } else {
+        tx.execute(
+            "INSERT INTO scans (scan_id, host) VALUES (?1, ?2)",
+            params![scan_id, options.host],
+        )?;
+        tx.last_insert_rowid()
+    };
+    Ok((scan, scan_id))
+}
+
+/// Find or create the row of the named engine.
+fn upsert_engine(tx: &Transaction<'_>, name: &str) -> rusqlite::Result<i64> {
+    tx.execute(
+        "INSERT INTO engines (name) VALUES (?1) ON CONFLICT (name) DO NOTHING",
+        [name],
+    )?;
+    tx.query_row("SELECT id FROM engines WHERE name = ?1", [name], |row| {
+        row.get(0)
+    })
+}
+
+/// Find or create the row of a data item, by name, path, and hash.
+fn upsert_item(tx: &Transaction<'_>, item: &DataItemResult) -> rusqlite::Result<i64> {
+    let path: Option<String> = item_path(item);
+    let existing: Option<i64> = tx
+        .query_row(
+            "SELECT id FROM items WHERE name = ?1 AND path IS ?2 AND sha256 = ?3",
+            params![item.name, path, item.sha256],
+            |row: &Row<'_>| row.get(0),
+        )
+        .optional()?;
+    if let Some(item) = existing {
+        return Ok(item);
+    }
+    tx.execute(
+        "INSERT INTO items (name, path, size, sha256) VALUES (?1, ?2, ?3, ?4)",
+        params![item.name, path, to_sql_int(item.size), item.sha256],
+    )?;
+    Ok(tx.last_insert_rowid())
+}
+
+/// The path of a data item, as stored.
+fn item_path(item: &DataItemResult) -> Option<String> {
+    item.path
+        .as_ref()
+        .map(|path: &PathObj| path.0.to_string_lossy().into_owned())
+}
+
+/// Convert a count into an `SQLite` integer.
+fn to_sql_int(count: usize) -> i64 {
+    i64::try_from(count).unwrap_or(i64::MAX)
+}
@@ -3,6 +3,7 @@
 //! The [`UserEngine`] API provides methods to userscripts to register
 //! custom scan engines. Each scan engine should receive a byte string
 //! payload, returning true or false on match or non-match, respectively.
+//! Engines may instead return a verdict table to describe a match.
 //!
 //! ## Userscript API
 //!
@@ -40,17 +41,30 @@
 
 use crate::{
     actors::user_engine::{
+        engine_info::EngineInfo,
         error::Error,
-        messages::{RegisterUserEngine, ScanBytes},
+        item_info::ItemInfo,
+        limits::EngineLimits,
+        messages::{
+            DescribeUserEngine, GetEnginePool, GetEngineProfile, ListUserEngines,
+            RegisterUserEngine, SetDefaultLimits, SetUserEngineEnabled, StartEnginePool,
+            StopEnginePool, UnregisterUserEngine,
+        },
+        pool::{scan_with_pool, EnginePool},
+        selector::{string_or_list, Selector},
+        stats::EngineProfile,
         UserEngine,
     },
     userscript_api::{
-        include::{LuaFunction, LuaString, LuaUserData, LuaUserDataMethods, LuaUserDataRef},
+        include::{
+            Lua, LuaFunction, LuaString, LuaTable, LuaUserData, LuaUserDataMethods, LuaUserDataRef,
+        },
         ApiObject,
     },
 };
 use kameo::actor::WeakActorRef;
 use mlua::ExternalError;
+use std::{num::NonZeroUsize, path::PathBuf, sync::Arc};
 
 /// # The Userscript Scan Engine API
 ///
@@ -59,6 +73,10 @@ use mlua::ExternalError;
 /// environment. Once one or more userscript scan engines are registered,
 /// a scan can be launched using `user_engines:scan(<string>)`, where
 /// `<string>` may also be a bytestring.
+///
+/// Registered engines can be listed, described, disabled, re-enabled,
+/// or unregistered entirely during a session. Engines defined in a
+/// script can also be run in parallel by a pool of worker VMs.
 pub struct UserEngineApi {
     /// Weak ref to the user engine actor.
     engine_ref: WeakActorRef<UserEngine>,
@@ -74,27 +92,39 @@ impl UserEngineApi {
 
 impl LuaUserData for UserEngineApi {
     fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
-        methods.add_async_method("register", |_, this: LuaUserDataRef<UserEngineApi>, (name, spec): (String, LuaFunction)| async move {
-            if let Some(user_engine) = this.engine_ref.upgrade() {
-                user_engine.ask(RegisterUserEngine::using(name, spec)).await.map_err(mlua::ExternalError::into_lua_err)?;
-                Ok(())
-            } else {
-                Err(Error::NoUserEngine.into_lua_err())
-            }
-        });
+        methods.add_async_method("register", user_engines_register);
+        methods.add_async_method("unregister", user_engines_unregister);
+        methods.add_async_method("enable", user_engines_enable);
+        methods.add_async_method("disable", user_engines_disable);
+        methods.add_async_method("list", user_engines_list);
+        methods.add_async_method("describe", user_engines_describe);
+        methods.add_async_method("set_limits", user_engines_set_limits);
+        methods.add_async_method("start_pool", user_engines_start_pool);
+        methods.add_async_method("stop_pool", user_engines_stop_pool);
+        methods.add_async_method("stats", user_engines_stats);
 
         methods.add_async_method(
             "scan",
-            |_, this: LuaUserDataRef<UserEngineApi>, content: LuaString| async move {
+            |_,
+             this: LuaUserDataRef<UserEngineApi>,
+             (content, name): (LuaString, Option<String>)| async move {
                 if let Some(user_engine) = this.engine_ref.upgrade() {
                     // Convert `content` into a byte vector
-                    let scan_request: ScanBytes = content.as_bytes().to_vec().into();
+                    let content: Vec<u8> = content.as_bytes().to_vec();
+                    let info: ItemInfo = ItemInfo::named(name.as_deref().unwrap_or("<content>"));
 
-                    // Call the userscript scan engine service
-                    let scan_results: Vec<String> = user_engine
-                        .ask(scan_request)
+                    // Call the userscript scan engine service and pool
+                    let pool: Option<Arc<EnginePool>> = user_engine
+                        .ask(GetEnginePool)
                         .await
                         .map_err(mlua::ExternalError::into_lua_err)?;
+                    let scan_results: Vec<String> =
+                        scan_with_pool(&user_engine, pool.as_deref(), content, info)
+                            .await
+                            .map_err(mlua::ExternalError::into_lua_err)?
+                            .into_iter()
+                            .map(|(name, _)| name)
+                            .collect();
                     Ok(scan_results)
                 } else {
                     Err(Error::NoUserEngine.into_lua_err())
@@ -109,3 +139,200 @@ impl ApiObject for UserEngineApi {
         "user_engines"
     }
 }
+
+/// Userscript function `user_engines:register(name, spec, opts?)`
+async fn user_engines_register(
+    _: Lua,
+    this: LuaUserDataRef<UserEngineApi>,
+    (name, spec, opts): (String, LuaFunction, Option<LuaTable>),
+) -> mlua::Result<()> {
+    let Some(user_engine) = this.engine_ref.upgrade() else {
+        return Err(Error::NoUserEngine.into_lua_err());
+    };
+    let mut request: RegisterUserEngine = RegisterUserEngine::using(name, spec);
+    if let Some(opts) = opts {
+        request = request
+            .with_details(opts.get("description")?, opts.get("version")?)
+            .with_selector(Selector::from_opts(&opts)?)
+            .with_order(opts.get::<Option<i64>>("order")?.unwrap_or_default())
+            .with_requirements(string_or_list(opts.get("requires")?, "requires")?)
+            .with_limits(EngineLimits::from_opts(&opts)?);
+    }
+    user_engine
+        .ask(request)
+        .await
+        .map_err(mlua::ExternalError::into_lua_err)
+}
+
+/// Userscript function `user_engines:unregister(name)`
+async fn user_engines_unregister(
+    _: Lua,
+    this: LuaUserDataRef<UserEngineApi>,
+    name: String,
+) -> mlua::Result<()> {
+    let Some(user_engine) = this.engine_ref.upgrade() else {
+        return Err(Error::NoUserEngine.into_lua_err());
+    };
+    user_engine
+        .ask(UnregisterUserEngine(name))
+        .await
+        .map_err(mlua::ExternalError::into_lua_err)
+}
+
+/// Userscript function `user_engines:enable(name)`
+async fn user_engines_enable(
+    _: Lua,
+    this: LuaUserDataRef<UserEngineApi>,
+    name: String,
+) -> mlua::Result<()> {
+    let Some(user_engine) = this.engine_ref.upgrade() else {
+        return Err(Error::NoUserEngine.into_lua_err());
+    };
+    user_engine
+        .ask(SetUserEngineEnabled::enable(name))
+        .await
+        .map_err(mlua::ExternalError::into_lua_err)
+}
+
+/// Userscript function `user_engines:disable(name)`
+async fn user_engines_disable(
+    _: Lua,
+    this: LuaUserDataRef<UserEngineApi>,
+    name: String,
+) -> mlua::Result<()> {
+    let Some(user_engine) = this.engine_ref.upgrade() else {
+        return Err(Error::NoUserEngine.into_lua_err());
+    };
+    user_engine
+        .ask(SetUserEngineEnabled::disable(name))
+        .await
+        .map_err(mlua::ExternalError::into_lua_err)
+}
+
+/// Userscript function `user_engines:list()`
+///
+/// The returned array prints one engine per line in the REPL.
+async fn user_engines_list(
+    lua: Lua,
+    this: LuaUserDataRef<UserEngineApi>,
+    (): (),
+) -> mlua::Result<LuaTable> {
+    let Some(user_engine) = this.engine_ref.upgrade() else {
+        return Err(Error::NoUserEngine.into_lua_err());
+    };
+    let engines: Vec<EngineInfo> = user_engine
+        .ask(ListUserEngines)
+        .await
+        .map_err(mlua::ExternalError::into_lua_err)?;
+    engine_list(&lua, engines)
+}
+
+/// Userscript function `user_engines:start_pool(script, opts?)`
+///
+/// Returns the engines the script registered, as `user_engines:list()`.
+async fn user_engines_start_pool(
+    lua: Lua,
+    this: LuaUserDataRef<UserEngineApi>,
+    (script, opts): (PathBuf, Option<LuaTable>),
+) -> mlua::Result<LuaTable> {
+    let Some(user_engine) = this.engine_ref.upgrade() else {
+        return Err(Error::NoUserEngine.into_lua_err());
+    };
+    let workers: usize = match opts {
+        Some(opts) => opts.get::<Option<usize>>("workers")?,
+        None => None,
+    }
+    .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, NonZeroUsize::get));
+    let script: String = std::fs::read_to_string(&script).map_err(|err| {
+        mlua::Error::runtime(format!(
+            "cannot read engine script `{}`: {err}",
+            script.display()
+        ))
+    })?;
+    let engines: Vec<EngineInfo> = user_engine
+        .ask(StartEnginePool { script, workers })
+        .await
+        .map_err(mlua::ExternalError::into_lua_err)?;
+    engine_list(&lua, engines)
+}
+
+/// Userscript function `user_engines:stop_pool()`
+async fn user_engines_stop_pool(
+    _: Lua,
+    this: LuaUserDataRef<UserEngineApi>,
+    (): (),
+) -> mlua::Result<()> {
+    let Some(user_engine) = this.engine_ref.upgrade() else {
+        return Err(Error::NoUserEngine.into_lua_err());
+    };
+    user_engine
+        .ask(StopEnginePool)
+        .await
+        .map_err(mlua::ExternalError::into_lua_err)
+}
+
+/// Userscript function `user_engines:stats()`
+///
+/// The returned array prints as a table in the REPL.
+async fn user_engines_stats(
+    _: Lua,
+    this: LuaUserDataRef<UserEngineApi>,
+    (): (),
+) -> mlua::Result<EngineProfile> {
+    let Some(user_engine) = this.engine_ref.upgrade() else {
+        return Err(Error::NoUserEngine.into_lua_err());
+    };
+    user_engine
+        .ask(GetEngineProfile)
+        .await
+        .map_err(mlua::ExternalError::into_lua_err)
+}
+
+/// Create an array of engines which prints one engine per line.
+fn engine_list(lua: &Lua, engines: Vec<EngineInfo>) -> mlua::Result<LuaTable> {
+    // Summarize the engines for the `__tostring` metamethod.
+    let summary: String = engines
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join("\n");
+    let engines_table: LuaTable = lua.create_sequence_from(engines)?;
+    let metatable: LuaTable = lua.create_table()?;
+    metatable.set(
+        "__tostring",
+        lua.create_function(move |_, _: LuaTable| Ok(summary.clone()))?,
+    )?;
+    engines_table.set_metatable(Some(metatable));
+    Ok(engines_table)
+}
+
+/// Userscript function `user_engines:set_limits(limits)`
+async fn user_engines_set_limits(
+    _: Lua,
+    this: LuaUserDataRef<UserEngineApi>,
+    limits: LuaTable,
+) -> mlua::Result<()> {
+    let Some(user_engine) = this.engine_ref.upgrade() else {
+        return Err(Error::NoUserEngine.into_lua_err());
+    };
+    let limits: EngineLimits = EngineLimits::from_opts(&limits)?;
+    user_engine
+        .ask(SetDefaultLimits(limits))
+        .await
+        .map_err(mlua::ExternalError::into_lua_err)
+}
+
+/// Userscript function `user_engines:describe(name)`
+async fn user_engines_describe(
+    _: Lua,
+    this: LuaUserDataRef<UserEngineApi>,
+    name: String,
+) -> mlua::Result<Option<EngineInfo>> {
+    let Some(user_engine) = this.engine_ref.upgrade() else {
+        return Err(Error::NoUserEngine.into_lua_err());
+    };
+    user_engine
+        .ask(DescribeUserEngine(name))
+        .await
+        .map_err(mlua::ExternalError::into_lua_err)
+}
@@ -0,0 +1,40 @@
+//! Tests if userscript scan engines are passed data item information.
+//!
+//! This integration test enqueues files, raw data, and data carrying
+//! metadata, then checks that each engine sees the item's name, path,
+//! size, and metadata in its info table.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn should_pass_item_info_to_engines() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Write two files with the same content but different extensions.
+    let dir: PathBuf = std::env::temp_dir().join(format!("sscan-info-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("payload.ps1"), "IEX (New-Object Net.WebClient)").unwrap();
+    std::fs::write(dir.join("notes.txt"), "IEX (New-Object Net.WebClient)").unwrap();
+
+    // Run the Lua test script against the files.
+    let exec_request: ExecChunk = format!(
+        "local test_dir, self_pid = {:?}, {}\n{}",
+        dir,
+        std::process::id(),
+        include_str!("engine_item_info/info_test.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+
+    // Clean up the files before checking the result.
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+}
@@ -0,0 +1,57 @@
+-- Test if scan engines are passed an info table for each data item.
+-- Expects `test_dir` and `self_pid` to be defined by the caller.
+
+-- Only match PowerShell scripts, by name.
+user_engines:register('ps1_only', function(payload, info)
+    if not info.name:match('%.ps1$') then return false end
+    return string.find(payload, 'IEX', 1, true) ~= nil
+end)
+
+-- Record every info table seen, by item name.
+local seen = {}
+user_engines:register('record', function(payload, info)
+    seen[info.name] = info
+    return false
+end)
+
+queue:add_file(test_dir .. '/payload.ps1')
+queue:add_file(test_dir .. '/notes.txt')
+queue:add_raw('raw item', 'IEX but not a file')
+local results = scanmgr:scan()
+
+-- Only the .ps1 file should match.
+assert(#results == 1)
+assert(results[1].engine == 'ps1_only')
+assert(results[1].item.name == 'payload.ps1')
+
+-- File items carry their path and size.
+local ps1 = seen['payload.ps1']
+assert(ps1.size == 30)
+assert(ps1.path.name == 'payload.ps1')
+assert(ps1.path.parent.name == seen['notes.txt'].path.parent.name)
+assert(next(ps1.metadata) == nil)
+
+-- Raw items have no path.
+assert(seen['raw item'].path == nil)
+assert(seen['raw item'].size == 18)
+
+-- Enqueue-time metadata is passed through.
+local pids = {}
+user_engines:register('record', function(payload, info)
+    pids[#pids + 1] = info.metadata.pid
+    return false
+end)
+local count = queue:add_process(self_pid, {regions='anonymous'})
+scanmgr:scan()
+assert(count > 0 and #pids > 0)
+for _,pid in ipairs(pids) do assert(pid == self_pid) end
+
+-- Manual scans can name their content.
+user_engines:register('record', function(payload, info)
+    seen[info.name] = info
+    return false
+end)
+user_engines:scan('some IEX content', 'manual.ps1')
+assert(seen['manual.ps1'].size == 16)
+user_engines:scan('unnamed')
+assert(seen['<content>'] ~= nil)
@@ -0,0 +1,69 @@
+//! Tests if runaway userscript scan engines are stopped by their limits.
+//!
+//! This integration test registers engines that loop forever or
+//! allocate without bound, under instruction and memory limits, and
+//! checks that they are stopped and reported as failed instead of
+//! hanging the scan, and that the virtual machine keeps working
+//! afterwards.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::{
+    lua_vm::{
+        messages::{ExecChunk, GetUserEngine, SetMemoryLimit, WaitStartup},
+        LuaVM,
+    },
+    user_engine::{limits::EngineLimits, messages::SetDefaultLimits},
+};
+
+#[tokio::test]
+async fn should_stop_engines_at_their_limits() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Run the Lua test script.
+    let exec_request: ExecChunk = include_str!("engine_limits/limits_test.lua").into();
+    vm.ask(exec_request).await.unwrap();
+}
+
+#[tokio::test]
+async fn should_limit_vm_memory() {
+    // Spawn the virtual machine with a 32MB memory limit
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+    vm.ask(SetMemoryLimit(32 * 1024 * 1024)).await.unwrap();
+
+    // Allocating past the limit should fail.
+    let exec_request: ExecChunk = "local big = string.rep('A', 64 * 1024 * 1024)".into();
+    assert!(vm.ask(exec_request).await.is_err());
+
+    // Removing the limit should allow the allocation.
+    vm.ask(SetMemoryLimit(0)).await.unwrap();
+    let exec_request: ExecChunk = "local big = string.rep('A', 64 * 1024 * 1024)".into();
+    vm.ask(exec_request).await.unwrap();
+}
+
+#[tokio::test]
+async fn should_set_default_limits_from_rust() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Limit every engine, as `sscan --max-instructions` does.
+    let user_engine = vm.ask(GetUserEngine).await.unwrap().unwrap();
+    let limits: EngineLimits = EngineLimits {
+        max_instructions: Some(100_000),
+        ..EngineLimits::default()
+    };
+    user_engine.ask(SetDefaultLimits(limits)).await.unwrap();
+
+    // A runaway engine should be stopped by the default limit.
+    let exec_request: ExecChunk = r"
+        user_engines:register('spin', function() while true do end end)
+        assert(#user_engines:scan('anything') == 0)
+        assert(user_engines:stats()[1].failures == 1)
+    "
+    .into();
+    vm.ask(exec_request).await.unwrap();
+}
@@ -0,0 +1,82 @@
+-- Test if scan engines are stopped when they overrun their limits.
+
+-- Count the failed invocations of an engine over the session.
+local function failures(name)
+    for _, engine in ipairs(user_engines:stats()) do
+        if engine.engine == name then return engine.failures end
+    end
+end
+
+-- An engine that never returns is stopped by its instruction limit.
+user_engines:register('forever', function()
+    while true do end
+end, { max_instructions = 100000 })
+assert(#user_engines:scan('payload') == 0, 'infinite loop should have been stopped')
+assert(failures('forever') == 1)
+assert(user_engines:describe('forever').max_instructions == 100000)
+
+-- Protected calls inside the engine cannot escape the limit.
+user_engines:register('forever', function()
+    while true do pcall(function() while true do end end) end
+end, { max_instructions = 100000 })
+assert(#user_engines:scan('payload') == 0)
+assert(failures('forever') == 2)
+user_engines:unregister('forever')
+
+-- Engines within their limits are unaffected.
+user_engines:register('counts', function(payload)
+    local count = 0
+    for _ = 1, 1000 do count = count + 1 end
+    return count == 1000
+end, { max_instructions = 1000000, max_memory = '1MB' })
+assert(#user_engines:scan('payload') == 1)
+assert(failures('counts') == 0)
+
+-- Default limits apply to engines without their own, and an engine
+-- overrunning them does not fail the others.
+user_engines:set_limits({ max_instructions = 50000 })
+user_engines:register('busy', function()
+    local count = 0
+    for _ = 1, 100000 do count = count + 1 end
+    return true
+end)
+local matches = user_engines:scan('payload')
+assert(#matches == 1 and matches[1] == 'counts', #matches)
+assert(failures('busy') == 1)
+user_engines:register('busy', function()
+    local count = 0
+    for _ = 1, 100000 do count = count + 1 end
+    return true
+end, { max_instructions = 10000000 })
+assert(#user_engines:scan('payload') == 2)
+assert(failures('busy') == 1)
+user_engines:set_limits({})
+
+-- An engine allocating past its memory limit fails.
+user_engines:register('hungry', function(payload)
+    local chunks = {}
+    for i = 1, 64 do chunks[i] = string.rep(payload, 64 * 1024) .. i end
+    return true
+end, { max_memory = '1MB' })
+assert(#user_engines:scan('AAAAAAAAAAAAAAAA') == 2, 'allocation should have been stopped')
+assert(failures('hungry') == 1)
+user_engines:unregister('hungry')
+
+-- The memory limit is lifted once the engine returns.
+local big = string.rep('B', 8 * 1024 * 1024)
+assert(#big == 8 * 1024 * 1024)
+
+-- Scans with a runaway engine keep the other engines' matches, and
+-- report the runaway engine as failed.
+user_engines:register('forever', function()
+    while true do end
+end, { max_instructions = 100000 })
+queue:add_raw('item', 'payload')
+local results = scanmgr:scan()
+assert(#results == 2)
+assert(results[1].engine == 'counts' and results[2].engine == 'busy')
+local summary = results:summary()
+assert(summary.failed == 0, tostring(summary))
+for _, engine in ipairs(summary.engines) do
+    assert(engine.failures == (engine.engine == 'forever' and 1 or 0), engine.engine)
+end
@@ -0,0 +1,24 @@
+//! Tests if userscript scan engines run in a deterministic order.
+//!
+//! This integration test registers engines out of name order, with
+//! explicit orders and requirements on other engines, then checks the
+//! order engines run and report results in, and that engines requiring
+//! others are only called, with their verdicts, when those matched.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+
+#[tokio::test]
+async fn should_run_engines_in_order() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Run the Lua test script.
+    let exec_request: ExecChunk = include_str!("engine_order/order_test.lua").into();
+    vm.ask(exec_request).await.unwrap();
+}
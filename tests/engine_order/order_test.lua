@@ -0,0 +1,75 @@
+-- Test if scan engines run in order, and only after engines they require.
+
+-- Record the order engines are called in.
+local calls = {}
+local function always(engine)
+    return function()
+        table.insert(calls, engine)
+        return true
+    end
+end
+
+-- `deep_js` is registered before the engine it requires.
+local seen_verdicts = nil
+user_engines:register('deep_js', function(payload, info, verdicts)
+    table.insert(calls, 'deep_js')
+    seen_verdicts = verdicts
+    return payload:find('eval', 1, true) ~= nil
+end, { requires = 'is_javascript' })
+user_engines:register('zeta', always('zeta'))
+user_engines:register('is_javascript', function(payload)
+    table.insert(calls, 'is_javascript')
+    if not payload:find('function', 1, true) then return false end
+    return { score = 40, tags = { 'js' } }
+end)
+user_engines:register('alpha', always('alpha'))
+user_engines:register('first', always('first'), { order = -1 })
+
+-- Engines run by order, then registration, but after their requirements.
+local expected = 'first,zeta,is_javascript,deep_js,alpha'
+for _ = 1, 5 do
+    calls = {}
+    local matches = user_engines:scan('function f() { eval(x) }')
+    assert(table.concat(calls, ',') == expected, table.concat(calls, ','))
+    assert(table.concat(matches, ',') == expected, table.concat(matches, ','))
+end
+
+-- Listing engines shows them in invocation order.
+local names = {}
+for _, engine in ipairs(user_engines:list()) do table.insert(names, engine.name) end
+assert(table.concat(names, ',') == expected, table.concat(names, ','))
+assert(user_engines:describe('first').order == -1)
+assert(user_engines:describe('deep_js').requires[1] == 'is_javascript')
+
+-- Engines requiring others are passed the earlier verdicts.
+assert(seen_verdicts.is_javascript.match == true)
+assert(seen_verdicts.is_javascript.score == 40)
+assert(seen_verdicts.is_javascript.tags[1] == 'js')
+assert(seen_verdicts.zeta.match == true)
+assert(seen_verdicts.alpha == nil)
+
+-- Engines are not called unless the engines they require matched.
+calls = {}
+user_engines:scan('plain text with eval')
+assert(table.concat(calls, ',') == 'first,zeta,is_javascript,alpha', table.concat(calls, ','))
+
+-- Requiring a disabled or unregistered engine never matches.
+user_engines:register('needs_ghost', always('needs_ghost'), { requires = { 'ghost' } })
+user_engines:disable('is_javascript')
+calls = {}
+user_engines:scan('function f() { eval(x) }')
+assert(table.concat(calls, ',') == 'first,zeta,alpha', table.concat(calls, ','))
+user_engines:enable('is_javascript')
+
+-- Requirement cycles are rejected, leaving the engines unchanged.
+assert(not pcall(user_engines.register, user_engines, 'is_javascript', always('x'),
+    { requires = 'deep_js' }))
+assert(user_engines:describe('is_javascript').requires[1] == nil)
+assert(#user_engines:scan('function f() { eval(x) }') == 5)
+
+-- Scan results from the scan manager follow the same order.
+queue:add_raw('script.js', 'function f() { eval(x) }')
+local results = scanmgr:scan()
+local engines = {}
+for _, result in ipairs(results) do table.insert(engines, result.engine) end
+assert(table.concat(engines, ',') == expected, table.concat(engines, ','))
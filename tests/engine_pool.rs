@@ -0,0 +1,127 @@
+//! Tests if engines in an engine pool scan data items in parallel.
+//!
+//! This integration test writes an engine script to a temporary file,
+//! starts a pool of workers running it alongside a locally registered
+//! engine, and checks that scan results from both are merged in queue
+//! order. It also checks that a failing engine script is reported, and
+//! that stopping the pool leaves only the local engines, and that scans
+//! are spread across more than one worker.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::{
+    lua_vm::{
+        messages::{ExecChunk, GetUserEngine, SetMemoryLimit, WaitStartup},
+        LuaVM,
+    },
+    user_engine::{messages::GetEnginePool, stats::EngineProfile},
+};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn should_scan_items_across_engine_pool() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Write a working engine script, and one that fails to run.
+    let dir: PathBuf = std::env::temp_dir().join(format!("sscan-pool-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("engines.lua"),
+        include_str!("engine_pool/engines.lua"),
+    )
+    .unwrap();
+    std::fs::write(dir.join("broken.lua"), "error('broken engine script')").unwrap();
+
+    // Run the Lua test script against the engine scripts.
+    let exec_request: ExecChunk = format!(
+        "local test_dir = {:?}\n{}",
+        dir,
+        include_str!("engine_pool/pool_test.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+
+    // Clean up the files before checking the result.
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn should_spread_scans_across_workers() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Write the engine script.
+    let dir: PathBuf =
+        std::env::temp_dir().join(format!("sscan-pool-spread-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("engines.lua"),
+        include_str!("engine_pool/engines.lua"),
+    )
+    .unwrap();
+
+    // Start a pool, then scan a batch of items through it.
+    let exec_request: ExecChunk = format!(
+        "user_engines:start_pool({:?}, {{ workers = 3 }})\n\
+         for i = 1, 12 do queue:add_raw('item' .. i, 'eval(' .. i .. ')') end\n\
+         assert(#scanmgr:scan() == 12)",
+        dir.join("engines.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+
+    // Clean up the files before checking the result.
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+
+    // More than one worker should have run the pooled engine.
+    let user_engine = vm.ask(GetUserEngine).await.unwrap().unwrap();
+    let pool = user_engine.ask(GetEnginePool).await.unwrap().unwrap();
+    let profiles: Vec<EngineProfile> = pool.worker_profiles().await.unwrap();
+    let busy: usize = profiles
+        .iter()
+        .filter(|profile| {
+            profile
+                .get("pooled_eval")
+                .is_some_and(|stats| stats.invocations > 0)
+        })
+        .count();
+    assert_eq!(profiles.len(), 3);
+    assert!(busy > 1, "only {busy} worker(s) ran engines");
+}
+
+#[tokio::test]
+async fn should_start_workers_with_vm_settings() {
+    // Spawn the virtual machine with a 32MB memory limit
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+    vm.ask(SetMemoryLimit(32 * 1024 * 1024)).await.unwrap();
+
+    // Write an engine script that allocates past the limit.
+    let dir: PathBuf =
+        std::env::temp_dir().join(format!("sscan-pool-limit-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("engines.lua"),
+        "user_engines:register('hog', function() return #string.rep('A', 64 * 1024 * 1024) > 0 end)",
+    )
+    .unwrap();
+
+    // The workers should share the memory limit, failing the engine.
+    let exec_request: ExecChunk = format!(
+        "user_engines:start_pool({:?}, {{ workers = 2 }})\n\
+         assert(#user_engines:scan('anything') == 0)\n\
+         assert(user_engines:stats()[1].failures == 1)",
+        dir.join("engines.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+
+    // Clean up the files before checking the result.
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+}
@@ -0,0 +1,10 @@
+-- Engine script run by every worker in the engine pool.
+
+user_engines:register('pooled_eval', function(payload)
+    -- Spin for a while, so that items overlap across workers.
+    local spins = 0
+    for _ = 1, 200000 do spins = spins + 1 end
+    local offset = payload:find('eval(', 1, true)
+    if offset == nil then return false end
+    return { score = 60, offsets = { offset - 1 } }
+end, { description = 'Finds eval calls' })
@@ -0,0 +1,53 @@
+-- Test if pooled engines scan items in parallel, with ordered results.
+-- Expects `test_dir` to be defined by the caller.
+
+-- A local engine runs alongside the pool.
+user_engines:register('local_eval', function(payload)
+    return payload:find('eval(', 1, true) ~= nil
+end)
+
+-- Starting the pool lists the engines the script registered.
+local engines = user_engines:start_pool(test_dir .. '/engines.lua', { workers = 3 })
+assert(#engines == 1 and engines[1].name == 'pooled_eval', tostring(engines))
+assert(engines[1].description == 'Finds eval calls')
+
+-- Pooled engines do not share this environment's engines or globals.
+assert(#user_engines:list() == 1)
+
+-- Manual scans include pooled engines, after local ones.
+local matched = user_engines:scan('x = eval(y)')
+assert(table.concat(matched, ',') == 'local_eval,pooled_eval', table.concat(matched, ','))
+
+-- Scan results follow queue order, local results first for each item.
+for i = 1, 12 do
+    if i % 3 == 0 then
+        queue:add_raw('item' .. i, 'clean')
+    else
+        queue:add_raw('item' .. i, 'run eval(' .. i .. ')')
+    end
+end
+local results = scanmgr:scan()
+local order = {}
+for _, result in ipairs(results) do
+    table.insert(order, result.engine .. ':' .. result.item.name)
+end
+local expected = {}
+for i = 1, 12 do
+    if i % 3 ~= 0 then
+        table.insert(expected, 'local_eval:item' .. i)
+        table.insert(expected, 'pooled_eval:item' .. i)
+    end
+end
+assert(table.concat(order, ',') == table.concat(expected, ','), table.concat(order, ','))
+assert(results[2].score == 60 and results[2].offsets[1] == 4)
+
+-- A failing engine script is reported, and leaves no pool running.
+local ok, err = pcall(user_engines.start_pool, user_engines, test_dir .. '/broken.lua', { workers = 2 })
+assert(not ok and tostring(err):find('broken engine script', 1, true), tostring(err))
+assert(#user_engines:scan('eval(') == 1)
+
+-- Stopping the pool leaves only the local engines.
+user_engines:start_pool(test_dir .. '/engines.lua', { workers = 2 })
+assert(#user_engines:scan('eval(') == 2)
+user_engines:stop_pool()
+assert(#user_engines:scan('eval(') == 1)
@@ -0,0 +1,43 @@
+//! Tests if userscript scan engines are only called on selected items.
+//!
+//! This integration test writes files of several types and sizes, some
+//! under a `Downloads` directory, then registers engines filtering by
+//! extension, size, path glob, and magic type, and checks which items
+//! each engine is called on.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn should_only_call_engines_on_selected_items() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Write files of several types, some under a Downloads directory.
+    let dir: PathBuf = std::env::temp_dir().join(format!("sscan-select-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("Downloads")).unwrap();
+    std::fs::write(dir.join("Downloads/run.PS1"), "IEX payload").unwrap();
+    std::fs::write(dir.join("Downloads/big.ps1"), vec![b'A'; 8192]).unwrap();
+    std::fs::write(dir.join("module.psm1"), "IEX module").unwrap();
+    std::fs::write(dir.join("tool.exe"), b"MZ\x90\x00 not really a program").unwrap();
+    std::fs::write(dir.join("notes.txt"), "plain text").unwrap();
+
+    // Run the Lua test script against the files.
+    let exec_request: ExecChunk = format!(
+        "local test_dir = {:?}\n{}",
+        dir,
+        include_str!("engine_selectors/selector_test.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+
+    // Clean up the files before checking the result.
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+}
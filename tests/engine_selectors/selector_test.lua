@@ -0,0 +1,59 @@
+-- Test if scan engines are only called on items matching their filters.
+-- Expects `test_dir` to be defined by the caller.
+
+-- Record the names of the items each engine is called on.
+local seen = {}
+local function recorder(engine)
+    seen[engine] = {}
+    return function(payload, info)
+        table.insert(seen[engine], info.name)
+        return true
+    end
+end
+local function sorted(names)
+    table.sort(names)
+    return table.concat(names, ',')
+end
+
+user_engines:register('ps', recorder('ps'), { ext = { 'ps1', '.psm1' }, max_size = '4KB' })
+user_engines:register('downloads', recorder('downloads'), { path = '**/Downloads/**' })
+user_engines:register('exe', recorder('exe'), { magic = 'pe' })
+user_engines:register('large', recorder('large'), { min_size = 1024 })
+user_engines:register('all', recorder('all'))
+
+queue:add_file(test_dir .. '/Downloads/run.PS1')
+queue:add_file(test_dir .. '/Downloads/big.ps1')
+queue:add_file(test_dir .. '/module.psm1')
+queue:add_file(test_dir .. '/tool.exe')
+queue:add_file(test_dir .. '/notes.txt')
+queue:add_raw('raw.ps1', 'not a file, but named like one')
+scanmgr:scan()
+
+-- Extensions are case-insensitive, and may be given with a dot.
+assert(sorted(seen.ps) == 'module.psm1,raw.ps1,run.PS1', sorted(seen.ps))
+
+-- Path globs only match items with a path.
+assert(sorted(seen.downloads) == 'big.ps1,run.PS1', sorted(seen.downloads))
+
+-- Magic types are detected from the payload.
+assert(sorted(seen.exe) == 'tool.exe', sorted(seen.exe))
+
+-- Sizes may be given in bytes.
+assert(sorted(seen.large) == 'big.ps1', sorted(seen.large))
+
+-- Engines without filters see everything.
+assert(#seen.all == 6)
+
+-- Filters are shown when describing an engine.
+local ps = user_engines:describe('ps')
+assert(ps.filters == 'ext=ps1,psm1 max_size=4096', ps.filters)
+assert(tostring(ps) == 'ps [ext=ps1,psm1 max_size=4096]', tostring(ps))
+assert(user_engines:describe('all').filters == nil)
+
+-- Invalid filters are rejected at registration.
+local noop = function() return false end
+assert(not pcall(user_engines.register, user_engines, 'bad', noop, { max_size = '5 parsecs' }))
+assert(not pcall(user_engines.register, user_engines, 'bad', noop, { magic = 'jpeg2000' }))
+assert(not pcall(user_engines.register, user_engines, 'bad', noop, { path = '[unclosed' }))
+assert(not pcall(user_engines.register, user_engines, 'bad', noop, { ext = 42 }))
+assert(user_engines:describe('bad') == nil)
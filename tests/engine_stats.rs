@@ -0,0 +1,23 @@
+//! Tests if userscript scan engine invocations are profiled.
+//!
+//! This integration test registers a slow and a fast engine, scans
+//! several data items, and checks the engine statistics reported for
+//! the session and in the scan summary.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+
+#[tokio::test]
+async fn should_profile_engine_invocations() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Run the Lua test script.
+    let exec_request: ExecChunk = include_str!("engine_stats/stats_test.lua").into();
+    vm.ask(exec_request).await.unwrap();
+}
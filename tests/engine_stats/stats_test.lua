@@ -0,0 +1,47 @@
+-- Test if engine invocations are timed and counted.
+
+user_engines:register('slow', function(payload)
+    local sum = 0
+    for i = 1, 200000 do sum = sum + i end
+    return payload:find('x', 1, true) ~= nil
+end)
+user_engines:register('fast', function() return true end)
+user_engines:register('text_only', function() return true end, { ext = 'txt' })
+
+-- Engines that were never called are reported with no invocations.
+local stats = user_engines:stats()
+assert(#stats == 3)
+for _, engine in ipairs(stats) do
+    assert(engine.invocations == 0 and engine.total_ms == 0 and engine.bytes == 0)
+end
+
+-- Manual scans are profiled.
+user_engines:scan('xyz')
+stats = user_engines:stats()
+assert(stats[1].engine == 'slow' and stats[1].invocations == 1 and stats[1].bytes == 3)
+
+-- Scans report statistics for that scan only.
+queue:add_raw('one', 'xx')
+queue:add_raw('two', 'abcd')
+queue:add_raw('three.txt', 'x')
+local results = scanmgr:scan()
+local summary = results:summary()
+assert(summary.items == 3 and summary.failed == 0, tostring(summary))
+assert(summary.matches == #results and summary.matches == 6)
+assert(summary.elapsed_ms > 0)
+local by_name = {}
+for _, engine in ipairs(summary.engines) do by_name[engine.engine] = engine end
+assert(by_name.slow.invocations == 3 and by_name.slow.bytes == 7)
+assert(by_name.text_only.invocations == 1 and by_name.text_only.bytes == 1)
+assert(by_name.slow.max_ms >= by_name.slow.mean_ms)
+assert(by_name.slow.total_ms > by_name.fast.total_ms)
+
+-- Session statistics include every scan.
+stats = user_engines:stats()
+assert(stats[1].engine == 'slow' and stats[1].invocations == 4 and stats[1].bytes == 10)
+
+-- Statistics print as a table, slowest engines first.
+local report = tostring(user_engines:stats())
+assert(report:find('^Engine'), report)
+assert(report:find('slow', 1, true) < report:find('fast', 1, true), report)
+assert(tostring(summary):find('Scanned 3 items (0 failed)', 1, true), tostring(summary))
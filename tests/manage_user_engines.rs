@@ -0,0 +1,24 @@
+//! Tests if userscript scan engines can be listed, described, disabled,
+//! enabled, and unregistered.
+//!
+//! This integration test registers engines with and without details,
+//! then checks that disabled and unregistered engines are skipped by
+//! scans, and that unknown engine names are reported as errors.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+
+#[tokio::test]
+async fn should_manage_user_engines() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Run the Lua test script.
+    let exec_request: ExecChunk = include_str!("manage_user_engines/manage_test.lua").into();
+    vm.ask(exec_request).await.unwrap();
+}
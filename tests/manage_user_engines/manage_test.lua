@@ -0,0 +1,49 @@
+-- Register two engines, one with a description and version.
+user_engines:register('always', function() return true end)
+user_engines:register('find_hello', function(payload)
+    return string.find(payload, 'Hello', 1, true) ~= nil
+end, { description = 'Finds greetings', version = '1.2' })
+
+-- Engines are listed by name.
+local engines = user_engines:list()
+assert(#engines == 2, 'expected two engines, got ' .. #engines)
+assert(engines[1].name == 'always')
+assert(engines[1].description == nil and engines[1].version == nil)
+assert(engines[2].name == 'find_hello')
+assert(tostring(engines) == 'always\nfind_hello v1.2 - Finds greetings',
+    'unexpected listing: ' .. tostring(engines))
+
+-- Describe a single engine, or nil for an unknown one.
+local info = user_engines:describe('find_hello')
+assert(info.description == 'Finds greetings')
+assert(info.version == '1.2')
+assert(info.enabled)
+assert(user_engines:describe('missing') == nil)
+
+-- Disabled engines are skipped by scans.
+user_engines:disable('always')
+assert(not user_engines:describe('always').enabled)
+assert(tostring(user_engines:describe('always')) == 'always [disabled]')
+local matches = user_engines:scan('Hello World')
+assert(#matches == 1 and matches[1] == 'find_hello')
+
+-- Enabled engines are called again.
+user_engines:enable('always')
+assert(#user_engines:scan('Hello World') == 2)
+
+-- Re-registering replaces an engine and re-enables it.
+user_engines:disable('find_hello')
+user_engines:register('find_hello', function() return false end)
+assert(user_engines:describe('find_hello').enabled)
+assert(user_engines:describe('find_hello').version == nil)
+assert(#user_engines:scan('Hello World') == 1)
+
+-- Unregistered engines are gone.
+user_engines:unregister('always')
+assert(#user_engines:list() == 1)
+assert(#user_engines:scan('Hello World') == 0)
+
+-- Unknown engine names are errors.
+assert(not pcall(user_engines.unregister, user_engines, 'always'))
+assert(not pcall(user_engines.enable, user_engines, 'missing'))
+assert(not pcall(user_engines.disable, user_engines, 'missing'))
@@ -0,0 +1,50 @@
+//! Tests if open network sockets can be listed.
+//!
+//! This integration test opens TCP, UDP, and UNIX domain sockets, then
+//! checks that the network API lists them with the correct addresses,
+//! states, and owning process.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+use std::{
+    net::{TcpListener, TcpStream, UdpSocket},
+    os::unix::net::UnixListener,
+};
+
+#[tokio::test]
+async fn should_list_connections() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Open some sockets to look for.
+    let tcp_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let tcp_port: u16 = tcp_listener.local_addr().unwrap().port();
+    let _tcp_client = TcpStream::connect(("127.0.0.1", tcp_port)).unwrap();
+    let _tcp_server = tcp_listener.accept().unwrap();
+    let udp_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let udp_port: u16 = udp_socket.local_addr().unwrap().port();
+    let unix_path = std::env::temp_dir().join(format!("sscan-net-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&unix_path);
+    let _unix_listener = UnixListener::bind(&unix_path).unwrap();
+
+    // Run the Lua test script against our sockets.
+    let exec_request: ExecChunk = format!(
+        "local self_pid, tcp_port, udp_port, unix_path = {}, {}, {}, {:?}\n{}",
+        std::process::id(),
+        tcp_port,
+        udp_port,
+        unix_path,
+        include_str!("net_api/net_test.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+
+    // Clean up the UNIX socket before checking the result.
+    std::fs::remove_file(&unix_path).unwrap();
+    result.unwrap();
+}
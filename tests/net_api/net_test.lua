@@ -0,0 +1,55 @@
+-- Test if the network API can find the test's own sockets.
+-- Expects `self_pid`, `tcp_port`, `udp_port`, and `unix_path` to be
+-- defined by the caller.
+
+-- Find a socket in a list by a predicate.
+local function find(conns, predicate)
+    for _,conn in ipairs(conns) do
+        if predicate(conn) then return conn end
+    end
+end
+
+-- Can we find our TCP listener and who owns it?
+local tcp = net:connections 'tcp'
+local listener = find(tcp, function(c)
+    return c.local_port == tcp_port and c.state == 'LISTEN'
+end)
+assert(listener ~= nil)
+assert(listener.local_address == '127.0.0.1')
+assert(listener.type == 'stream')
+assert(listener.pid == self_pid)
+
+-- Can we find both ends of our established connection?
+local client = find(tcp, function(c)
+    return c.remote_port == tcp_port and c.state == 'ESTABLISHED'
+end)
+assert(client ~= nil)
+assert(client.remote_address == '127.0.0.1')
+local server = find(tcp, function(c)
+    return c.local_port == tcp_port and c.remote_port == client.local_port
+end)
+assert(server ~= nil)
+
+-- Can we find our UDP socket?
+local udp = find(net:connections 'udp', function(c)
+    return c.local_port == udp_port
+end)
+assert(udp ~= nil)
+assert(udp.type == 'dgram')
+assert(udp.pid == self_pid)
+
+-- Can we find our UNIX listener?
+local unix = find(net:connections 'unix', function(c)
+    return c.local_address == unix_path
+end)
+assert(unix ~= nil)
+assert(unix.state == 'LISTEN')
+assert(unix.local_port == nil)
+
+-- Does listing everything include all of the above?
+local all = net:connections()
+assert(#all >= #tcp)
+assert(find(all, function(c) return c.inode == unix.inode end))
+
+-- Unknown protocols should be rejected.
+assert(not pcall(net.connections, net, 'bogus'))
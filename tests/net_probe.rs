@@ -0,0 +1,48 @@
+//! Tests if TCP ports can be probed and their banners scanned.
+//!
+//! This integration test spins up localhost listeners, one which sends
+//! a service banner and one which stays silent, then probes them along
+//! with a closed port and scans the captured banners.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+use std::{io::Write, net::TcpListener};
+
+#[tokio::test]
+async fn should_probe_and_scan_banners() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // A listener that announces itself like an SSH server.
+    let banner_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let banner_port: u16 = banner_listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        let (mut stream, _) = banner_listener.accept().unwrap();
+        stream.write_all(b"SSH-2.0-OpenSSH_9.6\r\n").unwrap();
+    });
+
+    // A listener that accepts connections but never speaks.
+    let silent_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let silent_port: u16 = silent_listener.local_addr().unwrap().port();
+
+    // A port that nothing is listening on.
+    let closed_port: u16 = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    // Run the Lua test script against our listeners.
+    let exec_request: ExecChunk = format!(
+        "local banner_port, silent_port, closed_port = {banner_port}, {silent_port}, {closed_port}\n{}",
+        include_str!("net_probe/probe_test.lua"),
+    )
+    .into();
+    vm.ask(exec_request).await.unwrap();
+    drop(silent_listener);
+}
@@ -0,0 +1,50 @@
+-- Test if TCP ports can be probed and their banners scanned.
+-- Expects `banner_port`, `silent_port`, and `closed_port` to be
+-- defined by the caller.
+
+-- Register an engine that fingerprints SSH servers.
+user_engines:register('find_ssh', function(payload)
+    return string.find(payload, 'SSH-2.0-', 1, true) == 1
+end)
+
+-- Probe all three ports, capturing banners.
+local ports = { banner_port, silent_port, closed_port }
+local results = net:probe('127.0.0.1', ports, {timeout_ms=500, banner_bytes=64})
+assert(#results == 3)
+
+-- The banner port should be open and announce itself.
+assert(results[1].open)
+assert(results[1].port == banner_port)
+assert(results[1].name == '127.0.0.1:' .. banner_port)
+assert(results[1].banner == 'SSH-2.0-OpenSSH_9.6\r\n')
+
+-- The silent port should be open, but without a banner.
+assert(results[2].open)
+assert(results[2].banner == nil)
+
+-- The closed port should not be open.
+assert(not results[3].open)
+assert(results[3].banner == nil)
+
+-- Only the captured banner should have been enqueued.
+assert(#queue == 1)
+
+-- The banner should be matched, tagged with where it came from.
+local scan_results = scanmgr:scan()
+assert(#scan_results == 1)
+assert(scan_results[1].engine == 'find_ssh')
+assert(scan_results[1].item.name == '127.0.0.1:' .. banner_port)
+assert(scan_results[1].item.metadata.port == banner_port)
+assert(scan_results[1].item.metadata.address == '127.0.0.1')
+
+-- Connect checks alone should not enqueue anything.
+local single = net:probe('127.0.0.1', silent_port, {timeout_ms=100})
+assert(#single == 1 and single[1].open)
+assert(#queue == 0)
+
+-- Oversized banner limits should be capped, not allocated.
+local capped = net:probe('127.0.0.1', silent_port, {timeout_ms=100, banner_bytes=1 << 40})
+assert(#capped == 1 and capped[1].open)
+
+-- Unresolvable hosts should be rejected.
+assert(not pcall(net.probe, net, 'host.invalid', 80))
@@ -0,0 +1,55 @@
+//! Tests if running processes can be listed and inspected.
+//!
+//! This integration test checks whether the process API can find the
+//! test process itself, as well as a child process it spawns, and
+//! whether the parsed process fields and details are sane.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+use std::{
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+#[tokio::test]
+async fn should_inspect_processes() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Spawn a child process to look for
+    let mut child = Command::new("sleep")
+        .arg("30")
+        .env("SSCAN_TEST_VAR", "hello")
+        .stdin(Stdio::null())
+        .spawn()
+        .expect("should be able to spawn `sleep`");
+
+    // Wait for the child to finish exec'ing into `sleep`
+    let comm: String = format!("/proc/{}/comm", child.id());
+    for _ in 0..100 {
+        if std::fs::read_to_string(&comm).is_ok_and(|name: String| name.trim() == "sleep") {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    // Run the Lua test script against our own pid and the child's pid.
+    let exec_request: ExecChunk = format!(
+        "local self_pid, child_pid = {}, {}\n{}",
+        std::process::id(),
+        child.id(),
+        include_str!("proc_api/proc_test.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+
+    // Clean up the child before checking the result.
+    child.kill().unwrap();
+    child.wait().unwrap();
+    result.unwrap();
+}
@@ -0,0 +1,56 @@
+-- Test if the process API can find and inspect processes.
+-- Expects `self_pid` and `child_pid` to be defined by the caller.
+
+-- Can we get our own process?
+local me = proc:get(self_pid)
+assert(me ~= nil)
+assert(me.pid == self_pid)
+assert(me.exe ~= nil)
+assert(#me.cmdline > 0)
+assert(me.start_time ~= nil)
+
+-- Can we get our child, and is it parented to us?
+local child = proc:get(child_pid)
+assert(child ~= nil)
+assert(child.ppid == self_pid)
+assert(child.name == 'sleep')
+assert(child.cmdline[2] == '30')
+assert(child.uid == me.uid)
+assert(child.start_time >= me.start_time)
+
+-- Do both processes show up in the full listing?
+local found_me, found_child = false, false
+for _,process in ipairs(proc:list()) do
+    if process == me then found_me = true end
+    if process.pid == child_pid then found_child = true end
+end
+assert(found_me and found_child)
+
+-- Does a nonexistent pid return nil?
+assert(proc:get(4294967295) == nil)
+
+-- Can we read the child's environment?
+local environ = proc:environ(child_pid)
+assert(environ.SSCAN_TEST_VAR == 'hello')
+
+-- Is the child's stdin resolved to /dev/null?
+local stdin = proc:fds(child)[1]
+assert(stdin.fd == 0)
+assert(stdin.type == 'file')
+assert(stdin.path.path == '/dev/null')
+
+-- Does our own memory map include our executable?
+local found_exe = false
+for _,region in ipairs(proc:maps(me)) do
+    assert(region['end'] - region.start == region.size)
+    if region.path ~= nil and region.path == me.exe then found_exe = true end
+end
+assert(found_exe)
+
+-- Does the child share our namespaces and cgroups?
+local my_ns, child_ns = proc:namespaces(me), proc:namespaces(child)
+assert(my_ns.pid ~= nil and my_ns.pid == child_ns.pid)
+assert(my_ns.mnt ~= nil and my_ns.mnt == child_ns.mnt)
+local my_cgroups, child_cgroups = proc:cgroups(me), proc:cgroups(child)
+assert(#my_cgroups > 0 and #my_cgroups == #child_cgroups)
+assert(my_cgroups[1].path == child_cgroups[1].path)
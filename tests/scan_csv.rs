@@ -0,0 +1,40 @@
+//! Tests if scan results are serialized as RFC 4180 CSV.
+//!
+//! This integration test scans a file whose name contains a quote, a
+//! comma, and a line break, then checks that CSV output escapes every
+//! field, honors the chosen columns and delimiter, and ends every
+//! record with a line break, as NDJSON output does.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn should_serialize_results_as_csv() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Write a file with an awkward name to scan.
+    let dir: PathBuf = std::env::temp_dir().join(format!("sscan-csv-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("say \"hi\",\nthere.txt"), "beacon").unwrap();
+
+    // Run the Lua test script against the file.
+    let exec_request: ExecChunk = format!(
+        "local test_dir, self_pid = {:?}, {}\n{}",
+        dir,
+        std::process::id(),
+        include_str!("scan_csv/csv_test.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+
+    // Clean up the files before checking the result.
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+}
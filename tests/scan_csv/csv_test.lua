@@ -0,0 +1,64 @@
+-- Test if scan results serialize to well-formed CSV.
+-- Expects `test_dir` and `self_pid` to be defined by the caller.
+
+user_engines:register('find_beacon', function(payload)
+    return payload:find('beacon', 1, true) ~= nil
+end)
+
+queue:add_file(test_dir .. '/say "hi",\nthere.txt')
+queue:add_raw('memory', 'beacon')
+local results = scanmgr:scan()
+assert(#results == 2)
+
+-- Every field is quoted, with quotes doubled and line breaks kept.
+local csv = results:csv()
+local expected_name = '"say ""hi"",\nthere.txt"'
+assert(csv:find('"find_beacon",' .. expected_name .. ',"', 1, true), csv)
+
+-- Every record ends with a line feed, including the last.
+assert(csv:sub(-1) == '\n' and not csv:find('\r', 1, true), csv)
+local _, records = csv:gsub('"\n', '')
+assert(records == 2, csv)
+
+-- Headers are still enabled by passing true.
+local with_headers = results:csv(true)
+assert(with_headers:find('^"Scan Engine","Item Name","Item Path"\n'), with_headers)
+
+-- Columns can be chosen; items lack metadata keys they weren't given.
+local hash = '"8a62e967fcd6dfa5d75308c37808b4668a7faf1cdb06e09ac0a7161827603887"'
+csv = results:csv({
+    headers = true,
+    columns = { 'name', 'size', 'hash', 'metadata.pid' },
+})
+local lines = {}
+for line in csv:gmatch('[^\n]+\n') do table.insert(lines, line) end
+assert(lines[1] == '"Item Name","Item Size","SHA-256","pid"\n', lines[1])
+assert(lines[#lines] == '"memory","6",' .. hash .. ',""\n', lines[#lines])
+assert(results[2].item.sha256 == hash:sub(2, -2))
+assert(results[2].item.size == 6)
+
+-- A custom delimiter produces TSV.
+local tsv = results:csv({ columns = { 'engine', 'name' }, delimiter = '\t' })
+assert(tsv:find('"find_beacon"\t"memory"\n', 1, true), tsv)
+
+-- Unknown columns and bad delimiters are errors.
+assert(not pcall(results.csv, results, { columns = { 'nope' } }))
+assert(not pcall(results.csv, results, { delimiter = '"' }))
+assert(not pcall(results.csv, results, { delimiter = ',,' }))
+
+-- NDJSON also ends every line with a line feed, including the last.
+local ndjson = results:ndjson()
+assert(ndjson:sub(-1) == '\n')
+local _, objects = ndjson:gsub('}\n', '')
+assert(objects == 2, ndjson)
+
+-- Metadata keys can be chosen as columns.
+local matched = false
+user_engines:register('find_beacon', function()
+    if matched then return false end
+    matched = true
+    return true
+end)
+queue:add_process(self_pid, { regions = 'anonymous' })
+csv = scanmgr:scan():csv({ columns = { 'metadata.pid' } })
+assert(csv == '"' .. self_pid .. '"\n', csv)
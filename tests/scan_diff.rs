@@ -0,0 +1,41 @@
+//! Tests if scan result sets can be diffed.
+//!
+//! This integration test scans two files and a raw data item, saves the
+//! results as JSON and NDJSON, then loads them back and diffs them
+//! against a later scan, checking which findings are reported added,
+//! removed, and unchanged.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn should_diff_scan_results() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Write the files to scan.
+    let dir: PathBuf = std::env::temp_dir().join(format!("sscan-diff-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("kept.txt"), "beacon").unwrap();
+    std::fs::write(dir.join("fixed.txt"), "beacon").unwrap();
+
+    // Run the Lua test script against the files.
+    let exec_request: ExecChunk = format!(
+        "local test_dir, self_pid = {:?}, {}\n{}",
+        dir,
+        std::process::id(),
+        include_str!("scan_diff/diff_test.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+
+    // Clean up the files before checking the result.
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+}
@@ -0,0 +1,61 @@
+-- Test if scan result sets can be saved, loaded, and diffed.
+-- Expects `test_dir` and `self_pid` to be defined by the caller.
+
+user_engines:register('find_beacon', function(payload)
+    return payload:find('beacon', 1, true) ~= nil
+end)
+
+-- The first scan finds both files and a raw item.
+queue:add_file(test_dir .. '/kept.txt')
+queue:add_file(test_dir .. '/fixed.txt')
+queue:add_raw('memory', 'beacon')
+local old = scanmgr:scan()
+assert(#old == 3)
+old:write(test_dir .. '/old.json', { pretty = true })
+old:write(test_dir .. '/old.ndjson')
+
+-- Saved results load back from JSON and NDJSON alike.
+for _, name in ipairs({ 'old.json', 'old.ndjson' }) do
+    local loaded = scanmgr:load(test_dir .. '/' .. name)
+    assert(loaded:count() == 3, name)
+    assert(loaded[1].engine == 'find_beacon')
+    assert(loaded[1].item.path.name == 'kept.txt')
+    assert(loaded[3].item.name == 'memory' and loaded[3].item.path == nil)
+    assert(loaded[3].item.sha256 == old[3].item.sha256)
+    assert(loaded:ndjson() == old:ndjson())
+    assert(loaded.summary == nil)
+end
+
+-- The second scan drops one file and adds another item.
+queue:add_file(test_dir .. '/kept.txt')
+queue:add_raw('memory', 'beacon, changed')
+queue:add_raw('network', 'beacon')
+local new = scanmgr:scan()
+assert(#new == 3)
+
+-- Findings match on engine and path, or name without a path.
+local diff = scanmgr:diff(test_dir .. '/old.ndjson', new)
+assert(diff.added:count() == 1 and diff.added[1].item.name == 'network')
+assert(diff.removed:count() == 1 and diff.removed[1].item.path.name == 'fixed.txt')
+assert(diff.unchanged:count() == 2)
+assert(diff.unchanged[2].item.size == #'beacon, changed')
+assert(tostring(diff):find('^1 added, 1 removed, 2 unchanged%.'), tostring(diff))
+assert(tostring(diff):find('+ find_beacon  network', 1, true), tostring(diff))
+
+-- Either side may be a results table or a path, and diffs keep the
+-- result methods.
+local same = scanmgr:diff(old, test_dir .. '/old.json')
+assert(same.added:count() == 0 and same.removed:count() == 0)
+assert(same.unchanged:count() == 3)
+assert(diff.added:csv():find('"network"', 1, true))
+
+-- Bad arguments and files are errors.
+assert(not pcall(scanmgr.diff, scanmgr, 42, new))
+local ok, err = pcall(scanmgr.load, scanmgr, test_dir .. '/missing.json')
+assert(not ok)
+assert(tostring(err):find('failed to load scan results', 1, true), tostring(err))
+local file = assert(io.open(test_dir .. '/bad.ndjson', 'w'))
+file:write(new:ndjson() .. 'not json\n')
+file:close()
+ok, err = pcall(scanmgr.load, scanmgr, test_dir .. '/bad.ndjson')
+assert(not ok and tostring(err):find('line 4', 1, true), tostring(err))
@@ -0,0 +1,74 @@
+//! Tests if scan results serialize as Elastic Common Schema events and
+//! Splunk HEC envelopes.
+//!
+//! This integration test scans a file and a raw data item, then checks
+//! the serialized events in Lua, and the ECS fields of the events the
+//! script wrote to files.
+//!
+
+use kameo::actor::ActorRef;
+use serde_json::Value;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn should_serialize_ecs() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Create a directory to write results to.
+    let dir: PathBuf = std::env::temp_dir().join(format!("sscan-ecs-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // Run the Lua test script against the directory.
+    let exec_request: ExecChunk = format!(
+        "local test_dir, self_pid = {:?}, {}\n{}",
+        dir,
+        std::process::id(),
+        include_str!("scan_ecs/ecs_test.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+    let ecs = std::fs::read_to_string(dir.join("ecs.ndjson"));
+    let hec = std::fs::read_to_string(dir.join("hec.ndjson"));
+
+    // Clean up the files before checking the result.
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+
+    // Check the ECS fields of the event.
+    let event: Value = serde_json::from_str(ecs.unwrap().trim_end()).unwrap();
+    assert_eq!(event["ecs"]["version"], "8.11.0");
+    assert_eq!(event["event"]["kind"], "alert");
+    assert_eq!(event["event"]["severity"], 73);
+    assert_eq!(event["event"]["risk_score"], 70.0);
+    assert_eq!(event["rule"]["name"], "find_beacon");
+    assert_eq!(event["file"]["name"], "beacon.bin");
+    assert_eq!(
+        event["file"]["path"],
+        dir.join("beacon.bin").to_string_lossy().as_ref()
+    );
+    assert_eq!(event["file"]["size"], 6);
+    assert_eq!(
+        event["file"]["hash"]["sha256"],
+        "8a62e967fcd6dfa5d75308c37808b4668a7faf1cdb06e09ac0a7161827603887"
+    );
+    assert_eq!(event["host"]["name"], "web01");
+    assert_eq!(event["host"]["os"]["type"], std::env::consts::OS);
+    assert_eq!(event["tags"], serde_json::json!(["c2"]));
+    assert_eq!(event["sscan"]["severity"], "high");
+    assert_eq!(event["sscan"]["offsets"], serde_json::json!([0]));
+
+    // Check the HEC envelope, which defaults to the local hostname.
+    let envelope: Value = serde_json::from_str(hec.unwrap().trim_end()).unwrap();
+    assert!(envelope["time"].as_f64().unwrap() > 0.0);
+    assert_eq!(envelope["source"], "sscan");
+    assert_eq!(envelope["sourcetype"], "sscan");
+    assert!(envelope.get("index").is_none());
+    assert_eq!(envelope["host"], envelope["event"]["host"]["name"]);
+    assert_eq!(envelope["event"]["rule"]["name"], "find_beacon");
+}
@@ -0,0 +1,50 @@
+-- Test if scan results serialize as ECS events and Splunk HEC envelopes.
+-- Expects `test_dir` and `self_pid` to be defined by the caller.
+
+user_engines:register('find_beacon', function(payload)
+    if payload:find('beacon', 1, true) then
+        return { score = 70, severity = 'high', reason = 'C2 beacon', tags = { 'c2' }, offsets = { 0 } }
+    end
+    return false
+end)
+
+local file = assert(io.open(test_dir .. '/beacon.bin', 'w'))
+file:write('beacon')
+file:close()
+queue:add_file(test_dir .. '/beacon.bin')
+queue:add_raw('clean', 'nothing')
+local results = scanmgr:scan()
+assert(#results == 1)
+
+local function has(text, pattern)
+    assert(text:find(pattern, 1, true), pattern .. ' not found in:\n' .. text)
+end
+
+-- ECS events are NDJSON, one per result, under the given host.
+local ecs = results:ecs({ host = 'web01' })
+assert(select(2, ecs:gsub('\n', '')) == 1, ecs)
+assert(ecs:match('^{"@timestamp":"%d%d%d%d%-%d%d%-%d%dT%d%d:%d%d:%d%d%.%d%d%dZ"'), ecs)
+has(ecs, '"message":"C2 beacon"')
+has(ecs, '"rule":{"name":"find_beacon","ruleset":"sscan","description":"C2 beacon"}')
+has(ecs, '"host":{"name":"web01","hostname":"web01"')
+
+-- HEC envelopes wrap the same events.
+local hec = results:hec({ host = 'web01', index = 'security' })
+assert(hec:match('^{"time":%d+'), hec)
+has(hec, '"host":"web01","source":"sscan","sourcetype":"_json","index":"security","event":{')
+has(hec, '"rule":{"name":"find_beacon"')
+
+-- Events are stamped with the scan time, however much later written.
+local started = os.clock()
+while os.clock() - started < 0.05 do end
+local timestamp = ecs:match('"@timestamp":"([^"]+)"')
+assert(results:ecs():match('"@timestamp":"([^"]+)"') == timestamp)
+assert(results:sort('engine'):ecs():match('"@timestamp":"([^"]+)"') == timestamp)
+assert(results:hec():match('"time":([%d.]+)') == hec:match('"time":([%d.]+)'))
+
+-- Both can be written to files, with options in the same table.
+results:write(test_dir .. '/ecs.ndjson', { format = 'ecs', host = 'web01' })
+results:write(test_dir .. '/hec.ndjson', { format = 'hec', sourcetype = 'sscan' })
+
+-- Options must be a table.
+assert(not pcall(results.ecs, results, 'web01'))
@@ -0,0 +1,60 @@
+//! Tests if scan results serialize as JUnit XML, and if `sscan run`
+//! fails when a scan matched.
+//!
+//! The first test scans raw data items and a missing file, then checks
+//! the test suites, failures, errors, and passing test cases of the
+//! report. The others run the sscan binary and check its exit status.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+use std::{
+    path::PathBuf,
+    process::{Command, ExitStatus},
+};
+
+#[tokio::test]
+async fn should_serialize_junit() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Create a directory to save results to.
+    let dir: PathBuf = std::env::temp_dir().join(format!("sscan-junit-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // Run the Lua test script against the directory.
+    let exec_request: ExecChunk = format!(
+        "local test_dir, self_pid = {:?}, {}\n{}",
+        dir,
+        std::process::id(),
+        include_str!("scan_junit/junit_test.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+
+    // Clean up the files before checking the result.
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+}
+
+/// Runs `tests/scan_junit/matches.lua` with the given payload.
+fn run_matches(payload: &str) -> ExitStatus {
+    Command::new(env!("CARGO_BIN_EXE_sscan"))
+        .args(["run", "tests/scan_junit/matches.lua", payload])
+        .status()
+        .expect("the sscan binary should be runnable")
+}
+
+#[test]
+fn should_fail_run_on_matches() {
+    assert_eq!(run_matches("match").code(), Some(1));
+}
+
+#[test]
+fn should_pass_run_without_matches() {
+    assert_eq!(run_matches("clean").code(), Some(0));
+}
@@ -0,0 +1,51 @@
+-- Test if scan results serialize as JUnit XML test reports.
+-- Expects `test_dir` and `self_pid` to be defined by the caller.
+
+user_engines:register('find_beacon', function(payload)
+    if payload:find('beacon', 1, true) then
+        return { score = 70, severity = 'high', reason = 'C2 & <beacon>', tags = { 'c2' } }
+    end
+    return false
+end)
+user_engines:register('find_nothing', function()
+    return false
+end)
+
+queue:add_raw('beacon_item', 'beacon')
+queue:add_raw('clean', 'nothing')
+queue:add_file(test_dir .. '/missing.txt')
+local results = scanmgr:scan()
+assert(#results == 1)
+
+local function has(text, pattern)
+    assert(text:find(pattern, 1, true), pattern .. ' not found in:\n' .. text)
+end
+
+-- Each engine is a suite, matches fail, and scan errors are errors.
+local xml = results:junit()
+has(xml, '<?xml version="1.0" encoding="UTF-8"?>\n')
+has(xml, '<testsuites name="sscan" tests="2" failures="1" errors="1" ')
+has(xml, '<testsuite name="find_beacon" tests="1" failures="1" errors="0" ')
+has(xml, '<testsuite name="find_nothing" tests="0" failures="0" errors="0" ')
+has(xml, '<testcase name="beacon_item" classname="find_beacon">')
+has(xml, '<failure message="C2 &amp; &lt;beacon&gt;" type="high">score: 70&#10;tags: c2&#10;')
+has(xml, '<testsuite name="sscan" tests="1" failures="0" errors="1" ')
+has(xml, '<error message="failed to load data item: ')
+assert(not xml:find('name="clean"', 1, true))
+
+-- Items an engine did not match can be passing test cases.
+local passing = results:junit({ passing = true })
+has(passing, '<testsuites name="sscan" tests="5" failures="1" errors="1" ')
+has(passing, '<testsuite name="find_beacon" tests="2" failures="1" ')
+has(passing, '<testcase name="clean" classname="find_beacon"/>\n')
+has(passing, '<testsuite name="find_nothing" tests="2" failures="0" ')
+has(passing, '<testcase name="beacon_item" classname="find_nothing"/>\n')
+
+-- Loaded results have no summary, so only their matches are reported.
+results:write(test_dir .. '/results.ndjson')
+local loaded = scanmgr:load(test_dir .. '/results.ndjson'):junit({ passing = true })
+has(loaded, '<testsuites name="sscan" tests="1" failures="1" errors="0" ')
+assert(not loaded:find('find_nothing', 1, true))
+
+-- Options must be a table.
+assert(not pcall(results.junit, results, 'passing'))
@@ -0,0 +1,8 @@
+-- Scans a raw data item, which matches if the first argument is "match".
+-- Returns nothing, so the exit status depends on whether it matched.
+
+user_engines:register("find_match", function(payload)
+    return payload == "match"
+end)
+queue:add_raw("item", arg[1])
+scanmgr:scan()
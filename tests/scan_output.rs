@@ -0,0 +1,141 @@
+//! Tests if scan results are written directly to files.
+//!
+//! This integration test writes scan results with `results:write()` in
+//! each format, then sets a scan output, as `sscan run --output` does,
+//! and checks that every following scan is written to it, with JSON
+//! rewritten as a single document.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::{
+    actors::{
+        lua_vm::{
+            messages::{ExecChunk, GetScanMgr, WaitStartup},
+            LuaVM,
+        },
+        scanmgr::{messages::SetScanOutput, ScanMgr},
+    },
+    userscript_api::scanmgr_api::output::{OutputOptions, ResultFormat},
+};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn should_write_results_to_files() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Create a directory to write results to.
+    let dir: PathBuf = std::env::temp_dir().join(format!("sscan-output-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // Run the Lua test script against the directory.
+    let exec_request: ExecChunk = format!(
+        "local test_dir, self_pid = {:?}, {}\n{}",
+        dir,
+        std::process::id(),
+        include_str!("scan_output/output_test.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+
+    // Clean up the files before checking the result.
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn should_write_every_scan_to_scan_output() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Write a stale file, which the first scan should replace.
+    let dir: PathBuf =
+        std::env::temp_dir().join(format!("sscan-scan-output-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path: PathBuf = dir.join("results.csv");
+    std::fs::write(&path, "stale").unwrap();
+
+    // Write every scan's results to the file as CSV.
+    let scanmgr: ActorRef<ScanMgr> = vm.ask(GetScanMgr).await.unwrap().unwrap();
+    let options: OutputOptions = OutputOptions::for_path(&path, None);
+    assert_eq!(options.format, ResultFormat::Csv);
+    scanmgr
+        .ask(SetScanOutput(Some((path.clone(), options))))
+        .await
+        .unwrap();
+
+    // Run two scans, then stop writing results.
+    let exec_request: ExecChunk = "
+        user_engines:register('find_beacon', function(payload)
+            return payload:find('beacon', 1, true) ~= nil
+        end)
+        queue:add_raw('first', 'beacon')
+        scanmgr:scan()
+        queue:add_raw('second', 'beacon')
+        scanmgr:scan()
+    "
+    .into();
+    vm.ask(exec_request).await.unwrap();
+    scanmgr.ask(SetScanOutput(None)).await.unwrap();
+    let exec_request: ExecChunk = "
+        queue:add_raw('third', 'beacon')
+        scanmgr:scan()
+    "
+    .into();
+    vm.ask(exec_request).await.unwrap();
+
+    // Only the first two scans should be in the file.
+    let written: String = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let lines: Vec<&str> = written.split_terminator('\n').collect();
+    assert_eq!(lines.len(), 2, "{written}");
+    assert!(lines[0].starts_with("\"find_beacon\",\"first\""));
+    assert!(lines[1].starts_with("\"find_beacon\",\"second\""));
+}
+
+#[tokio::test]
+async fn should_rewrite_json_scan_output() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Write every scan's results to a JSON file.
+    let dir: PathBuf =
+        std::env::temp_dir().join(format!("sscan-json-output-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path: PathBuf = dir.join("results.json");
+    let scanmgr: ActorRef<ScanMgr> = vm.ask(GetScanMgr).await.unwrap().unwrap();
+    let options: OutputOptions = OutputOptions::for_path(&path, None);
+    assert_eq!(options.format, ResultFormat::Json);
+    scanmgr
+        .ask(SetScanOutput(Some((path.clone(), options))))
+        .await
+        .unwrap();
+
+    // Run two scans.
+    let exec_request: ExecChunk = "
+        user_engines:register('find_beacon', function(payload)
+            return payload:find('beacon', 1, true) ~= nil
+        end)
+        queue:add_raw('first', 'beacon')
+        scanmgr:scan()
+        queue:add_raw('second', 'beacon')
+        scanmgr:scan()
+    "
+    .into();
+    vm.ask(exec_request).await.unwrap();
+
+    // The file should be one JSON array holding both scans' results.
+    let written: String = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let results: serde_json::Value = serde_json::from_str(&written).unwrap();
+    let names: Vec<&str> = results
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| result["item"]["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["first", "second"], "{written}");
+}
@@ -0,0 +1,60 @@
+-- Test if scan results are written straight to files.
+-- Expects `test_dir` and `self_pid` to be defined by the caller.
+
+local function read(name)
+    local file = assert(io.open(test_dir .. '/' .. name, 'rb'))
+    local contents = file:read('a')
+    file:close()
+    return contents
+end
+
+user_engines:register('find_beacon', function(payload)
+    return payload:find('beacon', 1, true) ~= nil
+end)
+
+queue:add_raw('first', 'beacon')
+queue:add_raw('second', 'beacon')
+local results = scanmgr:scan()
+assert(#results == 2)
+
+-- The format is taken from the file extension.
+results:write(test_dir .. '/results.ndjson')
+assert(read('results.ndjson') == results:ndjson())
+results:write(test_dir .. '/results.jsonl')
+assert(read('results.jsonl') == results:ndjson())
+results:write(test_dir .. '/results.json')
+assert(read('results.json') == results:json())
+results:write(test_dir .. '/results.sarif')
+assert(read('results.sarif') == results:sarif())
+
+-- Unrecognized extensions default to NDJSON, unless a format is given.
+results:write(test_dir .. '/results.out')
+assert(read('results.out') == results:ndjson())
+results:write(test_dir .. '/results.out', { format = 'json', pretty = true })
+assert(read('results.out') == results:json(true))
+
+-- Files are replaced unless appending.
+results:write(test_dir .. '/results.ndjson')
+assert(read('results.ndjson') == results:ndjson())
+results:write(test_dir .. '/results.ndjson', { append = true })
+assert(read('results.ndjson') == results:ndjson() .. results:ndjson())
+
+-- JSON and SARIF files hold one document, so cannot be appended to.
+assert(not pcall(results.write, results, test_dir .. '/results.json', { append = true }))
+assert(not pcall(results.write, results, test_dir .. '/results.out', { format = 'sarif', append = true }))
+
+-- CSV options are accepted, and headers are left out when appending.
+local opts = { headers = true, columns = { 'engine', 'name' } }
+results:write(test_dir .. '/results.csv', opts)
+opts.append = true
+results:write(test_dir .. '/results.csv', opts)
+local expected = '"Scan Engine","Item Name"\n'
+    .. '"find_beacon","first"\n"find_beacon","second"\n'
+    .. '"find_beacon","first"\n"find_beacon","second"\n'
+assert(read('results.csv') == expected, read('results.csv'))
+
+-- Unknown formats and unwritable paths are errors.
+assert(not pcall(results.write, results, test_dir .. '/bad', { format = 'xml' }))
+local ok, err = pcall(results.write, results, test_dir .. '/missing/results.json')
+assert(not ok)
+assert(tostring(err):find('failed to write scan results', 1, true), tostring(err))
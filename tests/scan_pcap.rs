@@ -0,0 +1,252 @@
+//! Tests if packet captures can be reassembled into flows and scanned.
+//!
+//! This integration test writes a small pcap file containing a TCP
+//! connection (with out-of-order and retransmitted segments) and a UDP
+//! exchange, plus a pcapng file carrying VLAN-tagged IPv6 traffic, then
+//! checks each flow's reassembled payload and metadata.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+use std::path::PathBuf;
+
+/// TCP SYN flag.
+const SYN: u8 = 0x02;
+
+/// TCP ACK flag.
+const ACK: u8 = 0x10;
+
+/// Build a TCP header and payload.
+fn tcp(src_port: u16, dst_port: u16, seq: u32, flags: u8, payload: &[u8]) -> Vec<u8> {
+    let mut segment: Vec<u8> = Vec::new();
+    segment.extend(src_port.to_be_bytes());
+    segment.extend(dst_port.to_be_bytes());
+    segment.extend(seq.to_be_bytes());
+    segment.extend(0u32.to_be_bytes());
+    segment.extend([5 << 4, flags, 0xff, 0xff, 0, 0, 0, 0]);
+    segment.extend(payload);
+    segment
+}
+
+/// Build a UDP header and payload.
+fn udp(src_port: u16, dst_port: u16, payload: &[u8]) -> Vec<u8> {
+    let length: u16 = u16::try_from(payload.len() + 8).unwrap();
+    let mut datagram: Vec<u8> = Vec::new();
+    datagram.extend(src_port.to_be_bytes());
+    datagram.extend(dst_port.to_be_bytes());
+    datagram.extend(length.to_be_bytes());
+    datagram.extend([0, 0]);
+    datagram.extend(payload);
+    datagram
+}
+
+/// Wrap a transport payload in IPv4 and Ethernet headers.
+fn ethernet_ipv4(src: [u8; 4], dst: [u8; 4], protocol: u8, transport: &[u8]) -> Vec<u8> {
+    let length: u16 = u16::try_from(transport.len() + 20).unwrap();
+    let mut frame: Vec<u8> = vec![0; 12];
+    frame.extend([0x08, 0x00, 0x45, 0]);
+    frame.extend(length.to_be_bytes());
+    frame.extend([0, 0, 0x40, 0, 64, protocol, 0, 0]);
+    frame.extend(src);
+    frame.extend(dst);
+    frame.extend(transport);
+    frame
+}
+
+/// Wrap a transport payload in IPv6 and VLAN-tagged Ethernet headers.
+fn vlan_ipv6(src: [u8; 16], dst: [u8; 16], protocol: u8, transport: &[u8]) -> Vec<u8> {
+    let length: u16 = u16::try_from(transport.len()).unwrap();
+    let mut frame: Vec<u8> = vec![0; 12];
+    frame.extend([0x81, 0x00, 0x00, 0x2a, 0x86, 0xdd]);
+    frame.extend([0x60, 0, 0, 0]);
+    frame.extend(length.to_be_bytes());
+    frame.extend([protocol, 64]);
+    frame.extend(src);
+    frame.extend(dst);
+    frame.extend(transport);
+    frame
+}
+
+/// Build a little-endian, microsecond pcap file of Ethernet frames.
+fn pcap(frames: &[(u32, u32, Vec<u8>)]) -> Vec<u8> {
+    let mut file: Vec<u8> = Vec::new();
+    file.extend(0xa1b2_c3d4u32.to_le_bytes());
+    file.extend(2u16.to_le_bytes());
+    file.extend(4u16.to_le_bytes());
+    file.extend([0; 8]);
+    file.extend(65535u32.to_le_bytes());
+    file.extend(1u32.to_le_bytes());
+    for (seconds, micros, frame) in frames {
+        let length: u32 = u32::try_from(frame.len()).unwrap();
+        file.extend(seconds.to_le_bytes());
+        file.extend(micros.to_le_bytes());
+        file.extend(length.to_le_bytes());
+        file.extend(length.to_le_bytes());
+        file.extend(frame);
+    }
+    file
+}
+
+/// Build a pcapng block of the given type.
+fn pcapng_block(block_type: u32, body: &[u8]) -> Vec<u8> {
+    let mut body: Vec<u8> = body.to_vec();
+    body.resize(body.len().next_multiple_of(4), 0);
+    let length: u32 = u32::try_from(body.len() + 12).unwrap();
+    let mut block: Vec<u8> = Vec::new();
+    block.extend(block_type.to_be_bytes());
+    block.extend(length.to_be_bytes());
+    block.extend(body);
+    block.extend(length.to_be_bytes());
+    block
+}
+
+/// Build a big-endian, nanosecond pcapng file of Ethernet frames.
+fn pcapng(frames: &[(u64, Vec<u8>)]) -> Vec<u8> {
+    // Section header: byte-order magic, version 1.0, unknown length.
+    let mut section: Vec<u8> = Vec::new();
+    section.extend(0x1a2b_3c4du32.to_be_bytes());
+    section.extend([0, 1, 0, 0]);
+    section.extend([0xff; 8]);
+    let mut file: Vec<u8> = pcapng_block(0x0a0d_0d0a, &section);
+
+    // Interface: Ethernet, with if_tsresol of nanoseconds.
+    let mut interface: Vec<u8> = Vec::new();
+    interface.extend(1u16.to_be_bytes());
+    interface.extend([0, 0]);
+    interface.extend(65535u32.to_be_bytes());
+    interface.extend(9u16.to_be_bytes());
+    interface.extend(1u16.to_be_bytes());
+    interface.extend([9, 0, 0, 0]);
+    interface.extend([0; 4]);
+    file.extend(pcapng_block(1, &interface));
+
+    // One enhanced packet block per frame.
+    for (nanos, frame) in frames {
+        let length: u32 = u32::try_from(frame.len()).unwrap();
+        let mut packet: Vec<u8> = Vec::new();
+        packet.extend(0u32.to_be_bytes());
+        packet.extend(u32::try_from(nanos >> 32).unwrap().to_be_bytes());
+        packet.extend(u32::try_from(nanos & 0xffff_ffff).unwrap().to_be_bytes());
+        packet.extend(length.to_be_bytes());
+        packet.extend(length.to_be_bytes());
+        packet.extend(frame);
+        file.extend(pcapng_block(6, &packet));
+    }
+    file
+}
+
+/// Write test captures, returning their paths.
+fn write_captures() -> (PathBuf, PathBuf, PathBuf) {
+    let (client, server, resolver) = ([10, 0, 0, 1], [10, 0, 0, 2], [10, 0, 0, 3]);
+    let tcp_frame =
+        |from: [u8; 4], to: [u8; 4], segment: Vec<u8>| ethernet_ipv4(from, to, 6, &segment);
+
+    // A TCP connection whose request arrives out of order, with a
+    // retransmission, followed by a UDP exchange and an ARP frame.
+    let frames: Vec<(u32, u32, Vec<u8>)> = vec![
+        (
+            100,
+            0,
+            tcp_frame(client, server, tcp(51000, 80, 1000, SYN, b"")),
+        ),
+        (
+            100,
+            10,
+            tcp_frame(server, client, tcp(80, 51000, 5000, SYN | ACK, b"")),
+        ),
+        (
+            100,
+            20,
+            tcp_frame(client, server, tcp(51000, 80, 1001, ACK, b"")),
+        ),
+        (
+            100,
+            30,
+            tcp_frame(
+                client,
+                server,
+                tcp(51000, 80, 1009, ACK, b"ware.exe HTTP/1.1\r\n\r\n"),
+            ),
+        ),
+        (
+            100,
+            40,
+            tcp_frame(client, server, tcp(51000, 80, 1001, ACK, b"GET /mal")),
+        ),
+        (
+            100,
+            50,
+            tcp_frame(client, server, tcp(51000, 80, 1001, ACK, b"GET /mal")),
+        ),
+        (
+            101,
+            0,
+            tcp_frame(
+                server,
+                client,
+                tcp(80, 51000, 5001, ACK, b"HTTP/1.1 404 Not Found\r\n\r\n"),
+            ),
+        ),
+        (
+            102,
+            0,
+            ethernet_ipv4(client, resolver, 17, &udp(53000, 53, b"query1;")),
+        ),
+        (
+            102,
+            500_000,
+            ethernet_ipv4(client, resolver, 17, &udp(53000, 53, b"query2;")),
+        ),
+        (
+            103,
+            0,
+            [vec![0xff; 12], vec![0x08, 0x06], vec![0; 28]].concat(),
+        ),
+    ];
+    let pcap_path: PathBuf =
+        std::env::temp_dir().join(format!("sscan-{}.pcap", std::process::id()));
+    std::fs::write(&pcap_path, pcap(&frames)).unwrap();
+
+    // A single IPv6 UDP datagram on a VLAN, in pcapng.
+    let mut v6_src: [u8; 16] = [0; 16];
+    let mut v6_dst: [u8; 16] = [0; 16];
+    v6_src[0..2].copy_from_slice(&[0xfd, 0x00]);
+    v6_src[15] = 1;
+    v6_dst[0..2].copy_from_slice(&[0xfd, 0x00]);
+    v6_dst[15] = 2;
+    let beacon: Vec<u8> = vlan_ipv6(v6_src, v6_dst, 17, &udp(40000, 4444, b"beacon-v6"));
+    let pcapng_path: PathBuf =
+        std::env::temp_dir().join(format!("sscan-{}.pcapng", std::process::id()));
+    std::fs::write(&pcapng_path, pcapng(&[(1_700_000_000_250_000_000, beacon)])).unwrap();
+
+    // A file which is not a capture at all.
+    let bogus_path: PathBuf =
+        std::env::temp_dir().join(format!("sscan-{}.notpcap", std::process::id()));
+    std::fs::write(&bogus_path, b"this is not a packet capture").unwrap();
+    (pcap_path, pcapng_path, bogus_path)
+}
+
+#[tokio::test]
+async fn should_scan_pcap_flows() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Run the Lua test script against the captures.
+    let (pcap_path, pcapng_path, bogus_path) = write_captures();
+    let exec_request: ExecChunk = format!(
+        "local pcap_path, pcapng_path, bogus_path = {pcap_path:?}, {pcapng_path:?}, {bogus_path:?}\n{}",
+        include_str!("scan_pcap/pcap_test.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+
+    // Clean up the captures before checking the result.
+    for path in [pcap_path, pcapng_path, bogus_path] {
+        std::fs::remove_file(path).unwrap();
+    }
+    result.unwrap();
+}
@@ -0,0 +1,53 @@
+-- Test if packet captures can be reassembled into flows and scanned.
+-- Expects `pcap_path`, `pcapng_path`, and `bogus_path` to be defined
+-- by the caller.
+
+-- Files that are not packet captures should be rejected.
+assert(not pcall(queue.add_pcap, queue, bogus_path))
+assert(#queue == 0)
+
+-- Each direction of each conversation is its own flow.
+assert(queue:add_pcap(pcap_path) == 3)
+assert(#queue == 3)
+
+-- The request is reassembled in order, without the retransmission.
+local name, path, content, meta = queue:dequeue()
+assert(name == 'tcp 10.0.0.1:51000 -> 10.0.0.2:80')
+assert(path == nil)
+assert(content == 'GET /malware.exe HTTP/1.1\r\n\r\n')
+assert(meta.protocol == 'tcp')
+assert(meta.src_ip == '10.0.0.1' and meta.src_port == 51000)
+assert(meta.dst_ip == '10.0.0.2' and meta.dst_port == 80)
+assert(meta.first_timestamp == 100 and meta.last_timestamp == 100.00005)
+assert(meta.packets == 5)
+assert(meta.capture == pcap_path)
+
+-- The response flows the other way.
+name, path, content, meta = queue:dequeue()
+assert(name == 'tcp 10.0.0.2:80 -> 10.0.0.1:51000')
+assert(content == 'HTTP/1.1 404 Not Found\r\n\r\n')
+
+-- UDP payloads are concatenated in capture order.
+name, path, content, meta = queue:dequeue()
+assert(name == 'udp 10.0.0.1:53000 -> 10.0.0.3:53')
+assert(content == 'query1;query2;')
+assert(meta.first_timestamp == 102 and meta.last_timestamp == 102.5)
+assert(meta.packets == 2)
+
+-- pcapng captures with VLAN-tagged IPv6 traffic are supported too.
+assert(queue:add_pcap(pcapng_path) == 1)
+name, path, content, meta = queue:dequeue()
+assert(name == 'udp [fd00::1]:40000 -> [fd00::2]:4444')
+assert(content == 'beacon-v6')
+assert(meta.src_ip == 'fd00::1' and meta.dst_port == 4444)
+assert(meta.first_timestamp == 1700000000.25)
+
+-- Flows can be scanned like any other data item.
+user_engines:register('find_malware', function(payload)
+    return string.find(payload, 'malware.exe', 1, true) ~= nil
+end)
+queue:add_pcap(pcap_path)
+local results = scanmgr:scan()
+assert(#results == 1)
+assert(results[1].item.name == 'tcp 10.0.0.1:51000 -> 10.0.0.2:80')
+assert(results[1].item.metadata.dst_port == 80)
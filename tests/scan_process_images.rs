@@ -0,0 +1,54 @@
+//! Tests if process executables and mapped files can be scanned.
+//!
+//! This integration test enqueues the executable and shared libraries
+//! of the test process itself, then checks that each enqueued file is
+//! linked back to the pids using it. It also checks that files are read
+//! through a process's root directory, as containers see them.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::{
+    lua_vm::{
+        messages::{ExecChunk, WaitStartup},
+        LuaVM,
+    },
+    queue::data_item::{DataItem, FileDatum, Metadata},
+};
+use std::path::{Path, PathBuf};
+
+#[tokio::test]
+async fn should_scan_process_images() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Run the Lua test script against our own pid and executable.
+    let self_exe = std::env::current_exe().unwrap().canonicalize().unwrap();
+    let exec_request: ExecChunk = format!(
+        "local self_pid, self_exe = {}, {:?}\n{}",
+        std::process::id(),
+        self_exe,
+        include_str!("scan_process_images/images_test.lua"),
+    )
+    .into();
+    vm.ask(exec_request).await.unwrap();
+}
+
+#[test]
+fn should_read_images_through_root() {
+    // Create a root directory, standing in for a container's.
+    let root: PathBuf = std::env::temp_dir().join(format!("sscan-root-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("usr/lib")).unwrap();
+    std::fs::write(root.join("usr/lib/libdemo.so"), b"container lib").unwrap();
+
+    // The file is read through the root, but keeps its own path.
+    let item = FileDatum::in_root("/usr/lib/libdemo.so", &root, Metadata::new());
+    let result = item.realize();
+
+    // Clean up the files before checking the result.
+    std::fs::remove_dir_all(&root).unwrap();
+    let (name, path, content) = result.unwrap();
+    assert_eq!(name, "libdemo.so");
+    assert_eq!(path.as_deref(), Some(Path::new("/usr/lib/libdemo.so")));
+    assert_eq!(content, b"container lib");
+}
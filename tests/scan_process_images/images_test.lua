@@ -0,0 +1,33 @@
+-- Test if process executables and mapped files can be enqueued.
+-- Expects `self_pid` and `self_exe` to be defined by the caller.
+
+-- Match any ELF binary.
+user_engines:register('is_elf', function(payload)
+    return payload:sub(1, 4) == '\x7fELF'
+end)
+
+-- Enqueue our own executable and shared libraries.
+local count = queue:add_process_images(proc:get(self_pid))
+assert(count > 0)
+assert(#queue == count)
+
+-- Every image should be an ELF linked back to our pid.
+local results = scanmgr:scan()
+assert(#results == count)
+local found_exe = false
+for _,result in ipairs(results) do
+    assert(result.item.metadata.pids[1] == self_pid)
+    if result.item.path.path == self_exe then found_exe = true end
+end
+assert(found_exe)
+
+-- Sweeping all processes should find at least as many files, once each.
+local total = queue:add_all_process_images()
+assert(total >= count)
+local seen = {}
+while #queue > 0 do
+    local _, path, _, metadata = queue:dequeue()
+    assert(seen[path] == nil)
+    assert(#metadata.pids > 0)
+    seen[path] = true
+end
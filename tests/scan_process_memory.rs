@@ -0,0 +1,47 @@
+//! Tests if live process memory can be scanned.
+//!
+//! This integration test enqueues the memory regions of the test
+//! process itself, then scans them for a marker string held by the
+//! Lua virtual machine running in the same process. It also checks
+//! that regions too large to read fail without being allocated.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::{
+    lua_vm::{
+        messages::{ExecChunk, WaitStartup},
+        LuaVM,
+    },
+    queue::memory_region::{MemoryRegion, MAX_REGION_SIZE},
+};
+
+#[tokio::test]
+async fn should_scan_process_memory() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Run the Lua test script against our own pid.
+    let exec_request: ExecChunk = format!(
+        "local self_pid = {}\n{}",
+        std::process::id(),
+        include_str!("scan_process_memory/memscan_test.lua"),
+    )
+    .into();
+    vm.ask(exec_request).await.unwrap();
+}
+
+#[test]
+fn should_refuse_oversized_regions() {
+    // A region past the size limit fails before anything is allocated.
+    let region = MemoryRegion {
+        start: 0,
+        end: MAX_REGION_SIZE + 1,
+        perms: "rw-p".to_owned(),
+        offset: 0,
+        inode: 0,
+        pathname: None,
+    };
+    let err: std::io::Error = region.read(std::process::id()).unwrap_err();
+    assert!(err.to_string().contains("byte limit"), "{err}");
+}
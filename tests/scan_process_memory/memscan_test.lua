@@ -0,0 +1,27 @@
+-- Test if live process memory can be enqueued and scanned.
+-- Expects `self_pid` to be defined by the caller.
+
+-- Build the marker at runtime so it only exists in heap memory.
+marker = string.rep('sscan', 2) .. '-memory-marker'
+
+-- Register an engine that finds the marker.
+user_engines:register('find_marker', function(payload)
+    return string.find(payload, marker, 1, true) ~= nil
+end)
+
+-- Unknown region selectors should be rejected.
+assert(not pcall(queue.add_process, queue, self_pid, {regions='bogus'}))
+
+-- Enqueue all anonymous memory regions of this process.
+local count = queue:add_process(self_pid, {regions='anonymous'})
+assert(count > 0)
+assert(#queue == count)
+
+-- The marker should be found, tagged with our pid and region address.
+local results = scanmgr:scan()
+assert(#results > 0)
+for _,result in ipairs(results) do
+    assert(result.item.metadata.pid == self_pid)
+    assert(result.item.metadata.base_address > 0)
+    assert(result.item.path == nil)
+end
@@ -0,0 +1,23 @@
+//! Tests if scan results can be filtered, grouped, sorted, and deduped.
+//!
+//! This integration test scans several raw data items against engines
+//! returning different scores and severities, then checks each query
+//! method, and that the tables they return keep the serializers.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+
+#[tokio::test]
+async fn should_query_scan_results() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Run the Lua test script.
+    let exec_request: ExecChunk = include_str!("scan_queries/queries_test.lua").into();
+    vm.ask(exec_request).await.unwrap();
+}
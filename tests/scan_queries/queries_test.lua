@@ -0,0 +1,64 @@
+-- Test if scan results tables can be queried.
+
+user_engines:register('find_beacon', function(payload)
+    if payload:find('beacon', 1, true) then
+        return { match = true, score = #payload, severity = 'high' }
+    end
+    return false
+end)
+user_engines:register('find_anything', function()
+    return true
+end)
+
+queue:add_raw('a', 'beacon one')
+queue:add_raw('b', 'nothing')
+queue:add_raw('c', 'beacon')
+queue:add_raw('a', 'beacon one')
+local results = scanmgr:scan()
+assert(results:count() == 7, results:count())
+
+-- Filters keep the matching results, in order.
+local beacons = results:filter(function(result)
+    return result.engine == 'find_beacon'
+end)
+assert(beacons:count() == 3)
+assert(beacons[1].item.name == 'a' and beacons[2].item.name == 'c')
+assert(results:count(function(result) return result.severity == 'high' end) == 3)
+
+-- Query results keep the serializers and queries.
+local _, lines = beacons:ndjson():gsub('\n', '')
+assert(lines == 3)
+assert(beacons:csv():find('"find_beacon","c"', 1, true))
+assert(beacons:filter(function() return false end):count() == 0)
+assert(beacons:summary().items == 4)
+
+-- Grouping by engine and item.
+local by_engine = results:by_engine()
+assert(by_engine.find_beacon:count() == 3)
+assert(by_engine.find_anything:count() == 4)
+local by_item = results:by_item()
+assert(by_item.a:count() == 4)
+assert(by_item.b:count() == 1)
+assert(by_item.c:count() == 2)
+assert(by_item.c:json():find('"name":"c"', 1, true))
+
+-- Sorting is stable, with missing values last in either order.
+local ascending = results:sort('score')
+assert(ascending[1].item.name == 'c' and ascending[1].score == 6)
+assert(ascending[2].score == 10 and ascending[3].score == 10)
+assert(ascending[4].score == nil and ascending[4].item.name == 'a')
+local descending = results:sort('score', true)
+assert(descending[1].score == 10 and descending[3].score == 6)
+assert(descending[7].score == nil)
+local by_name = results:sort('name')
+assert(by_name[1].item.name == 'a' and by_name[7].item.name == 'c')
+assert(by_name[1].engine == 'find_beacon' and by_name[2].engine == 'find_anything')
+assert(not pcall(results.sort, results, 'nope'))
+
+-- Unique items are keyed by name and content.
+local unique = results:unique_items()
+assert(unique:count() == 3)
+assert(unique[1].item.name == 'a' and unique[3].item.name == 'c')
+
+-- The original results are left untouched.
+assert(results[1].item.name == 'a' and results:count() == 7)
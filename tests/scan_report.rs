@@ -0,0 +1,39 @@
+//! Tests if scan results render as HTML and Markdown reports.
+//!
+//! This integration test scans raw data items, one of them matching an
+//! engine returning a verdict table, alongside a missing file, then
+//! checks the summary counts, engine and item tables, and errors in
+//! each report, and that values are escaped.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn should_render_reports() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Create a directory to write reports to.
+    let dir: PathBuf = std::env::temp_dir().join(format!("sscan-report-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // Run the Lua test script against the directory.
+    let exec_request: ExecChunk = format!(
+        "local test_dir, self_pid = {:?}, {}\n{}",
+        dir,
+        std::process::id(),
+        include_str!("scan_report/report_test.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+
+    // Clean up the files before checking the result.
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+}
@@ -0,0 +1,60 @@
+-- Test if scan results render as HTML and Markdown reports.
+-- Expects `test_dir` and `self_pid` to be defined by the caller.
+
+user_engines:register('find_beacon', function(payload)
+    if payload:find('beacon', 1, true) then
+        return { score = 70, severity = 'high', reason = 'C2 | <beacon>', tags = { 'c2' } }
+    end
+    return false
+end)
+user_engines:register('find_anything', function()
+    return true
+end)
+
+queue:add_raw('beacon_item', 'beacon')
+queue:add_raw('clean', 'nothing')
+queue:add_file(test_dir .. '/missing.txt')
+local results = scanmgr:scan()
+assert(#results == 3)
+
+-- Failures are kept in the summary for the report.
+local summary = results:summary()
+assert(summary.failed == 1 and #summary.errors == 1)
+assert(summary.errors[1]:find('failed to load data item', 1, true), summary.errors[1])
+
+-- The Markdown report has summary counts, tables, and errors.
+local markdown = results:markdown()
+local function has(text, pattern)
+    assert(text:find(pattern, 1, true), pattern .. ' not found in:\n' .. text)
+end
+has(markdown, '# sscan Scan Report\n')
+has(markdown, '| Items Scanned | Failed | Matches | Matched Items | Engines Matched |')
+assert(markdown:find('| 3 | 1 | 3 | 2 | 2 | ', 1, true), markdown)
+has(markdown, '| high | 1 |\n| none | 2 |\n')
+has(markdown, '### find\\_beacon\n')
+has(markdown, '| beacon\\_item |  | 70 | high | C2 \\| \\<beacon\\> | c2 |  |\n')
+has(markdown, '### clean\n\n- Size: 7 bytes\n- SHA-256: ')
+has(markdown, '## Errors\n\n- failed to load data item: ')
+has(markdown, '## Engine Performance\n')
+
+-- The HTML report escapes values, and can be written to a file.
+local html = results:html()
+has(html, '<!DOCTYPE html>')
+has(html, '<style>')
+has(html, '<td>C2 | &lt;beacon&gt;</td>')
+has(html, '<h3>find_beacon</h3>')
+assert(results:html(test_dir .. '/report.html') == nil)
+local file = assert(io.open(test_dir .. '/report.html', 'r'))
+assert(file:read('a') == html)
+file:close()
+
+-- Reports of results without a summary leave out what is unknown.
+local clean = results:filter(function(result) return result.item.name == 'clean' end)
+has(clean:markdown(), '| 3 | 1 | 1 | 1 | 1 | ')
+results:write(test_dir .. '/results.ndjson')
+local loaded = scanmgr:load(test_dir .. '/results.ndjson'):markdown()
+has(loaded, '| - | - | 3 | 2 | 2 | - |')
+assert(not loaded:find('## Errors', 1, true))
+assert(not loaded:find('## Engine Performance', 1, true))
+has(scanmgr:load(test_dir .. '/results.ndjson'):filter(function() return false end):markdown(),
+    '## Matches by Engine\n\nNo matches.\n')
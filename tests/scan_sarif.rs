@@ -0,0 +1,84 @@
+//! Tests if scan results can be serialized as a SARIF log.
+//!
+//! This integration test scans a file and a raw data item against two
+//! engines, one returning a verdict table, then parses the SARIF log
+//! produced by `results:sarif()` and checks its rules, levels, and
+//! locations.
+//!
+
+use kameo::actor::ActorRef;
+use serde_json::Value;
+use sscan::actors::lua_vm::{
+    messages::{EvalChunk, ExecChunk, WaitStartup},
+    LuaVM,
+};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn should_serialize_results_as_sarif() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Write a file to scan, with a space in its name.
+    let dir: PathBuf = std::env::temp_dir().join(format!("sscan-sarif-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file: PathBuf = dir.join("beacon sample.bin");
+    std::fs::write(&file, "xxbeacon").unwrap();
+
+    // Register the engines and scan the file and a raw item.
+    let exec_request: ExecChunk = format!(
+        "local test_file = {:?}\n{}",
+        file,
+        include_str!("scan_sarif/sarif_test.lua"),
+    )
+    .into();
+    let result = vm.ask(exec_request).await;
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+
+    // Parse the SARIF log.
+    let sarif: mlua::Value = vm.ask(EvalChunk::from("sarif")).await.unwrap();
+    let sarif: String = sarif.as_string().unwrap().to_str().unwrap().to_owned();
+    let sarif: Value = serde_json::from_str(&sarif).unwrap();
+    assert_eq!(sarif["version"], "2.1.0");
+    assert_eq!(sarif["runs"].as_array().unwrap().len(), 1);
+
+    // Each engine that matched is a rule, in order of first match.
+    let run: &Value = &sarif["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "sscan");
+    assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "plain");
+    assert_eq!(run["tool"]["driver"]["rules"][1]["id"], "rich");
+    assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 2);
+
+    // Each match is a result; plain matches are warnings.
+    let results: &Vec<Value> = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 4);
+    let plain: &Value = &results[0];
+    assert_eq!(plain["ruleId"], "plain");
+    assert_eq!(plain["ruleIndex"], 0);
+    assert_eq!(plain["level"], "warning");
+    assert_eq!(
+        plain["message"]["text"],
+        "scan engine `plain` matched `beacon sample.bin`"
+    );
+
+    // Files are located by URI, and verdict offsets become regions.
+    let rich: &Value = &results[1];
+    assert_eq!(rich["ruleIndex"], 1);
+    assert_eq!(rich["level"], "error");
+    assert_eq!(rich["message"]["text"], "found beacon marker");
+    let location: &Value = &rich["locations"][0]["physicalLocation"];
+    let uri: &str = location["artifactLocation"]["uri"].as_str().unwrap();
+    assert!(uri.starts_with("file://"), "{uri}");
+    assert!(uri.ends_with("/beacon%20sample.bin"), "{uri}");
+    assert_eq!(location["region"]["byteOffset"], 2);
+    assert_eq!(rich["properties"]["score"], 70.0);
+    assert_eq!(rich["properties"]["tags"][0], "c2");
+
+    // Items without a path are located by name.
+    let raw: &Value = &results[3];
+    assert_eq!(raw["locations"][0]["logicalLocations"][0]["name"], "memory");
+    assert!(raw["locations"][0].get("physicalLocation").is_none());
+    assert_eq!(raw["properties"]["severity"], "high");
+}
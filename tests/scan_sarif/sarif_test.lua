@@ -0,0 +1,27 @@
+-- Scan a file and a raw item, keeping the results as a SARIF log.
+-- Expects `test_file` to be defined by the caller.
+
+user_engines:register('plain', function(payload)
+    return payload:find('beacon', 1, true) ~= nil
+end)
+user_engines:register('rich', function(payload)
+    local offset = payload:find('beacon', 1, true)
+    if offset == nil then return false end
+    return {
+        score = 70,
+        severity = 'high',
+        reason = 'found beacon marker',
+        offsets = { offset - 1 },
+        tags = { 'c2' },
+    }
+end)
+
+queue:add_file(test_file)
+queue:add_raw('memory', 'beacon')
+local results = scanmgr:scan()
+assert(#results == 4)
+
+-- Pretty and minified logs hold the same data.
+sarif = results:sarif()
+assert(#results:sarif(true) > #sarif)
+assert(sarif:find('"$schema":"https://json.schemastore.org/sarif-2.1.0.json"', 1, true))
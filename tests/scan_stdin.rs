@@ -0,0 +1,33 @@
+//! Tests if data piped to the sscan binary can be scanned.
+//!
+//! This integration test runs `sscan run <script> -`, piping a payload
+//! into standard input, and checks that the userscript's scan engine
+//! matched the piped data.
+//!
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+#[test]
+fn should_scan_piped_stdin() {
+    // Run sscan with a lone `-` argument to enqueue stdin
+    let mut sscan = Command::new(env!("CARGO_BIN_EXE_sscan"))
+        .args(["run", "tests/scan_stdin/rules.lua", "-"])
+        .stdin(Stdio::piped())
+        .spawn()
+        .expect("the sscan binary should be runnable");
+
+    // Pipe the payload to sscan, then close stdin.
+    sscan
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"blablabla-Hello World-blablabla")
+        .unwrap();
+
+    // The script exits with the number of matches.
+    let status = sscan.wait().unwrap();
+    assert_eq!(status.code(), Some(1));
+}
@@ -0,0 +1,9 @@
+-- Scans whatever was piped into sscan through stdin.
+-- Exits with the number of matching scan engines.
+
+function engine_helloworld(payload)
+    return string.find(payload, "Hello World") ~= nil
+end
+
+user_engines:register("helloworld", engine_helloworld)
+return #scanmgr:scan()
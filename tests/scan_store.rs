@@ -0,0 +1,90 @@
+//! Tests if scan results are stored in an SQLite database.
+//!
+//! This integration test stores several scans with `results:store()`,
+//! then reads them back through the [`ResultStore`] that `sscan query`
+//! uses, checking the scans, their findings, and that engine and item
+//! rows are shared between scans.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::{
+    actors::lua_vm::{
+        messages::{ExecChunk, WaitStartup},
+        LuaVM,
+    },
+    userscript_api::scanmgr_api::store::{FindingFilter, ResultStore, StoredFinding, StoredScan},
+};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn should_store_scan_results() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Write a file to scan.
+    let dir: PathBuf = std::env::temp_dir().join(format!("sscan-store-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("sample.txt"), "beacon").unwrap();
+
+    // Run the Lua test script, which stores its scans.
+    let exec_request: ExecChunk = format!(
+        "local test_dir, self_pid = {:?}, {}\n{}",
+        dir,
+        std::process::id(),
+        include_str!("scan_store/store_test.lua"),
+    )
+    .into();
+    vm.ask(exec_request).await.unwrap();
+
+    // Read the scans and findings back.
+    let database: PathBuf = dir.join("scans.db");
+    let store: ResultStore = ResultStore::open(&database).unwrap();
+    let scans: Vec<StoredScan> = store.scans().unwrap();
+    let all: Vec<StoredFinding> = store.findings(&FindingFilter::default()).unwrap();
+    let nightly: Vec<StoredFinding> = store
+        .findings(&FindingFilter {
+            scan_id: Some("nightly".to_owned()),
+            engine: Some("find_beacon".to_owned()),
+        })
+        .unwrap();
+    let conn = rusqlite::Connection::open(&database).unwrap();
+    let count = |table: &str| -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    };
+    let (engines, items) = (count("engines"), count("items"));
+    drop(conn);
+    drop(store);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // Each scan is listed once, with its summary if it had one.
+    let ids: Vec<&str> = scans.iter().map(|scan| scan.scan_id.as_str()).collect();
+    assert_eq!(ids, ["scan-1", "nightly", "filtered"]);
+    assert_eq!(scans[0].host.as_deref(), Some("web01"));
+    assert_eq!(
+        (scans[0].items, scans[0].failed, scans[0].matches),
+        (Some(2), Some(0), 4)
+    );
+    assert_eq!(scans[1].host.as_deref(), Some("web02"));
+    assert_eq!((scans[1].items, scans[1].matches), (Some(2), 3));
+    assert_eq!((scans[2].items, scans[2].matches), (None, 1));
+
+    // Findings keep their verdicts and items.
+    assert_eq!(all.len(), 8);
+    let file = &all[0].result;
+    assert_eq!(file.engine, "find_beacon");
+    assert_eq!(file.item.path.as_ref().unwrap().0, dir.join("sample.txt"));
+    assert_eq!(file.item.size, 6);
+    assert_eq!(file.verdict.score, Some(70.0));
+    assert_eq!(file.verdict.offsets, [0]);
+    assert_eq!(file.verdict.tags, ["c2"]);
+    assert_eq!(nightly.len(), 1);
+    assert_eq!(nightly[0].result.item.name, "memory");
+
+    // Engines and items are shared between scans.
+    assert_eq!(engines, 2);
+    assert_eq!(items, 3);
+}
@@ -0,0 +1,39 @@
+-- Test if scan results are stored in an SQLite database.
+-- Expects `test_dir` and `self_pid` to be defined by the caller.
+
+local db = test_dir .. '/scans.db'
+
+user_engines:register('find_beacon', function(payload)
+    if payload:find('beacon', 1, true) then
+        return { score = 70, severity = 'high', offsets = { 0 }, tags = { 'c2' } }
+    end
+    return false
+end)
+user_engines:register('find_anything', function()
+    return true
+end)
+
+-- Scan IDs default to the next scan number.
+queue:add_file(test_dir .. '/sample.txt')
+queue:add_raw('memory', 'beacon')
+assert(scanmgr:scan():store(db, { host = 'web01' }) == 'scan-1')
+
+-- Results stored under an existing scan ID are added to that scan.
+queue:add_raw('memory', 'beacon')
+local results = scanmgr:scan()
+assert(results:store(db, { scan_id = 'nightly' }) == 'nightly')
+queue:add_raw('network', 'nothing')
+assert(scanmgr:scan():store(db, { scan_id = 'nightly', host = 'web02' }) == 'nightly')
+
+-- Loaded results have no summary, and query results can be stored.
+results:write(test_dir .. '/results.ndjson')
+local beacons = scanmgr:load(test_dir .. '/results.ndjson'):filter(function(result)
+    return result.engine == 'find_beacon'
+end)
+assert(beacons:store(db, { scan_id = 'filtered' }) == 'filtered')
+
+-- Bad options and databases are errors.
+assert(not pcall(results.store, results, db, { scan_id = {} }))
+local ok, err = pcall(results.store, results, test_dir .. '/missing/scans.db')
+assert(not ok)
+assert(tostring(err):find('failed to store scan results', 1, true), tostring(err))
@@ -0,0 +1,23 @@
+//! Tests if userscript scan engines can return rich verdicts.
+//!
+//! This integration test registers engines returning plain booleans
+//! alongside engines returning verdict tables, then checks that the
+//! verdict details are carried into scan results and serializers.
+//!
+
+use kameo::actor::ActorRef;
+use sscan::actors::lua_vm::{
+    messages::{ExecChunk, WaitStartup},
+    LuaVM,
+};
+
+#[tokio::test]
+async fn should_carry_verdicts_into_results() {
+    // Spawn the virtual machine
+    let vm: ActorRef<LuaVM> = LuaVM::spawn(None);
+    vm.ask(WaitStartup).await.unwrap();
+
+    // Run the Lua test script.
+    let exec_request: ExecChunk = include_str!("scan_verdicts/verdicts_test.lua").into();
+    vm.ask(exec_request).await.unwrap();
+}
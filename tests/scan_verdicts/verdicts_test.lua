@@ -0,0 +1,70 @@
+-- Test if scan engines can return verdict tables.
+
+-- A plain boolean engine should keep working.
+user_engines:register('plain', function(payload)
+    return string.find(payload, 'beacon', 1, true) ~= nil
+end)
+
+-- A verdict engine describes where and why it matched.
+user_engines:register('rich', function(payload)
+    local offset = string.find(payload, 'beacon', 1, true)
+    if offset == nil then return {match=false} end
+    return {
+        score = 70,
+        severity = 'high',
+        reason = 'found "beacon" marker',
+        offsets = { offset - 1 },
+        tags = { 'c2', 'network' },
+    }
+end)
+
+-- Only matching payloads should produce results.
+assert(#user_engines:scan('nothing to see here') == 0)
+assert(#user_engines:scan('xxbeacon') == 2)
+
+-- Verdict details are carried into scan results.
+queue:add_raw('sample', 'xxbeacon')
+local results = scanmgr:scan()
+assert(#results == 2)
+local plain, rich
+for _,result in ipairs(results) do
+    if result.engine == 'plain' then plain = result else rich = result end
+end
+assert(plain.score == nil and plain.severity == nil and plain.reason == nil)
+assert(#plain.offsets == 0 and #plain.tags == 0)
+assert(rich.score == 70)
+assert(rich.severity == 'high')
+assert(rich.reason == 'found "beacon" marker')
+assert(rich.offsets[1] == 2)
+assert(rich.tags[1] == 'c2' and rich.tags[2] == 'network')
+
+-- Verdict details are carried into every serializer.
+local json = results:json()
+assert(string.find(json, '"severity":"high"', 1, true))
+assert(string.find(json, '"offsets":[2]', 1, true))
+assert(string.find(results:ndjson(), '"tags":["c2","network"]', 1, true))
+local csv = results:csv({
+    headers = true,
+    columns = { 'engine', 'name', 'score', 'severity', 'reason', 'tags', 'offsets' },
+})
+assert(string.find(csv, '"Score","Severity","Reason","Tags","Offsets"', 1, true))
+assert(string.find(csv, '"rich","sample","70","high","found ""beacon"" marker","c2;network","2"', 1, true))
+assert(string.find(csv, '"plain","sample","","","","",""', 1, true))
+
+-- Verdict columns are only serialized when chosen.
+assert(not string.find(results:csv(true), 'Score', 1, true))
+
+-- Invalid verdicts fail the engine, but not the scan.
+user_engines:register('rich', function() return {severity='apocalyptic'} end)
+local matches = user_engines:scan('xxbeacon')
+assert(#matches == 1 and matches[1] == 'plain')
+
+-- Engines returning nil do not match, as do engines falling off the end.
+user_engines:register('plain', function() end)
+user_engines:register('rich', function() return nil end)
+assert(#user_engines:scan('xxbeacon') == 0)
+
+-- Other non-table values match by truthiness.
+user_engines:register('rich', function() return 1 end)
+local truthy = user_engines:scan('anything')
+assert(#truthy == 1 and truthy[1] == 'rich')